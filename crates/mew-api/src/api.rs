@@ -1,19 +1,43 @@
+use std::collections::HashMap;
+
 use mew_bundle::Bundler;
 use mew_parse::{
-    span::{Span, Spanned},
-    syntax::{
-        Alias, FormalTemplateParameter, GlobalDeclaration, PathPart, TranslationUnit,
-        TypeExpression,
-    },
+    span::{FileId, SourceMap, Spanned},
+    syntax::{Alias, GlobalDeclaration, PathPart, SpansMut, TranslationUnit, TypeExpression},
+};
+use mew_types::{
+    layout::{struct_layouts, UniformLayoutChecker},
+    mangling::mangle_path,
+    CompilerPass,
+};
+
+use crate::{
+    error::MewError,
+    loader::{load_missing_modules, ModuleLoader},
+    reflect::{reflect, Reflection},
+    validate::parse_and_validate,
+    MewErrorInner,
 };
-use mew_types::{mangling::mangle_path, CompilerPass, CompilerPassError, InternalCompilerError};
 
 #[derive(Default, Debug)]
 pub struct MewApi {
     pub translation_unit: TranslationUnit,
+    pub loader: Option<Box<dyn ModuleLoader>>,
+    pub loaded_files: Vec<std::path::PathBuf>,
+    /// The source text of every module added as text or loaded. Spans of errors and of
+    /// the translation unit point into it.
+    pub source_map: SourceMap,
+    /// The file of every module added as text or loaded, keyed by module name.
+    pub modules: HashMap<String, FileId>,
+    /// Validate the compiled WGSL with naga, allowing these capabilities. Catches WGSL
+    /// that is invalid only after specialization and that the type checker does not catch,
+    /// e.g. a template argument that indexes out of bounds. `None` skips validation.
+    pub validation: Option<naga::valid::Capabilities>,
 }
 
 pub enum Source<'a> {
+    /// A syntax tree without source text. Its spans are moved to a range of the source map
+    /// that belongs to no file, so errors in it point to no file.
     Ast(&'a mut TranslationUnit),
     Text(&'a str),
 }
@@ -23,23 +47,6 @@ pub struct ModuleDescriptor<'a> {
     pub source: Source<'a>,
 }
 
-#[derive(Debug)]
-pub enum MewErrorInner {
-    ModuleNotFound,
-    SymbolNotFound(Vec<PathPart>),
-    MissingRequiredTemplateArgument(FormalTemplateParameter),
-    InternalError(InternalCompilerError),
-    MalformedTemplateArgument,
-    ParseError(String),
-}
-
-#[derive(Debug)]
-pub struct MewError {
-    pub span: Option<Span>,
-    pub module_name: Option<String>,
-    pub error: MewErrorInner,
-}
-
 pub enum Path {
     Parsed(Vec<PathPart>),
     Text(String),
@@ -47,44 +54,48 @@ pub enum Path {
 
 type Result<T = ()> = std::result::Result<T, MewError>;
 
-impl From<CompilerPassError> for MewError {
-    fn from(value: CompilerPassError) -> Self {
-        match value {
-            CompilerPassError::SymbolNotFound(vec, range) => MewError {
-                span: Some(range),
-                module_name: None,
-                error: MewErrorInner::SymbolNotFound(vec),
-            },
-            CompilerPassError::UnableToResolvePath(vec) => MewError {
-                span: None,
-                module_name: None,
-                error: MewErrorInner::SymbolNotFound(vec),
-            },
-            CompilerPassError::MissingRequiredTemplateArgument(spanned, range) => MewError {
-                span: Some(range),
-                module_name: None,
-                error: MewErrorInner::MissingRequiredTemplateArgument(spanned.value),
-            },
-            CompilerPassError::InternalError(internal_compiler_error) => MewError {
-                span: None,
-                module_name: None,
-                error: MewErrorInner::InternalError(internal_compiler_error),
-            },
-            CompilerPassError::MalformedTemplateArgument(range) => MewError {
-                span: Some(range),
-                module_name: None,
-                error: MewErrorInner::MalformedTemplateArgument,
-            },
-            CompilerPassError::ParseError(parse_err, span) => MewError {
-                span: Some(span),
-                module_name: None,
-                error: MewErrorInner::ParseError(parse_err),
-            },
+impl Path {
+    pub(crate) fn parts(&self) -> Result<Vec<PathPart>> {
+        match self {
+            Path::Parsed(path) => Ok(path.clone()),
+            Path::Text(path) => Ok(mew_parse::Parser::parse_path(path)
+                .map_err(|err| MewError {
+                    // the span points into `path`, not into the source map
+                    span: None,
+                    module_name: None,
+                    error: MewErrorInner::ParseError(format!("{}", err)),
+                })?
+                .path
+                .value),
         }
     }
 }
 
 impl MewApi {
+    pub fn with_loader(loader: impl ModuleLoader + 'static) -> Self {
+        Self {
+            loader: Some(Box::new(loader)),
+            ..Default::default()
+        }
+    }
+
+    /// Asks the loader for every module referenced by a `use` or `extend` directive that
+    /// has not been added yet, and adds it as a nested module. Does nothing without a loader.
+    pub fn load_missing_modules(&mut self) -> Result {
+        if let Some(loader) = self.loader.as_mut() {
+            let loaded = load_missing_modules(
+                loader.as_mut(),
+                &mut self.translation_unit,
+                &mut self.source_map,
+            )?;
+            for (module_name, file, path) in loaded {
+                self.loaded_files.push(path);
+                self.modules.insert(module_name, file);
+            }
+        }
+        Ok(())
+    }
+
     pub fn remove_module(&mut self, module_name: &String) -> Result {
         let prev_len = self.translation_unit.global_declarations.len();
         self.translation_unit.global_declarations.retain(|x| {
@@ -92,7 +103,8 @@ impl MewApi {
                 || x.name().as_ref().map(|x| &x.value) != Some(module_name)
         });
 
-        if prev_len < self.translation_unit.global_declarations.len() {
+        if prev_len > self.translation_unit.global_declarations.len() {
+            self.modules.remove(module_name);
             Ok(())
         } else {
             Err(MewError {
@@ -106,69 +118,158 @@ impl MewApi {
     pub fn add_module(&mut self, module: ModuleDescriptor<'_>) -> Result {
         match module.source {
             Source::Ast(translation_unit) => {
+                // the tree has no source text, its spans move to offsets of no file
+                let mut len = 0;
+                translation_unit.spans_mut(&mut |span| len = len.max(span.end));
+                let start = self.source_map.reserve(len);
+                translation_unit.spans_mut(&mut |span| {
+                    span.start += start;
+                    span.end += start;
+                });
                 self.translation_unit
                     .global_declarations
                     .append(&mut translation_unit.global_declarations);
                 self.translation_unit
                     .global_directives
                     .append(&mut translation_unit.global_directives);
-                Ok(())
             }
             Source::Text(text) => {
+                let file = self.source_map.add_file(module.module_name, text);
                 let mut bundler = Bundler {
                     sources: vec![text],
                     enclosing_module_name: Some(module.module_name.to_string()),
+                    offset: self.source_map.file(file).start,
                 };
-                bundler.apply_mut(&mut self.translation_unit)?;
-                Ok(())
+                bundler
+                    .apply_mut(&mut self.translation_unit)
+                    .map_err(|err| MewError::from(err).in_module(module.module_name))?;
+                self.modules.insert(module.module_name.to_string(), file);
             }
         }
+        self.load_missing_modules()
     }
 
+    /// Compiles the module member at `path` and everything it uses to WGSL. If a pass fails,
+    /// the error holds every error that pass found, see [`MewError::into_errors`].
     pub fn compile(&self, path: &Path) -> Result<String> {
-        let path = match path {
-            Path::Parsed(path) => path.clone(),
-            Path::Text(path) => {
-                mew_parse::Parser::parse_path(path)
-                    .map_err(|err| CompilerPassError::ParseError(format!("{}", err), err.span()))?
-                    .path
-                    .value
-            }
-        };
+        self.compile_many(std::slice::from_ref(path))
+    }
 
-        let mut alias_name_path = path.clone();
-        mangle_path(&mut alias_name_path);
+    /// Like [`MewApi::compile`], but compiles the module members at every path of `paths`
+    /// into a single module. The passes run once, and declarations used by several entry
+    /// points, including template instantiations, are emitted once.
+    pub fn compile_many(&self, paths: &[Path]) -> Result<String> {
+        let (result, wgsl) = self.compile_translation_unit(paths)?;
+        if let Some(capabilities) = self.validation {
+            self.validate(&result, &wgsl, capabilities)?;
+        }
+        Ok(wgsl)
+    }
+
+    /// Like [`MewApi::compile_many`], but also returns the bindings and entry points of the
+    /// compiled module, with both their mangled name and their path in the mew source, and
+    /// the memory layout of its structs.
+    pub fn compile_with_reflection(&self, paths: &[Path]) -> Result<(String, Reflection)> {
+        let mut result = self
+            .mangle_paths(paths)
+            .map_err(|err| self.attribute_to_module(err))?;
+        let mut reflection = reflect(&result, &self.source_map);
+        self.flatten(&mut result)
+            .map_err(|err| self.attribute_to_module(err))?;
+        reflection.structs = struct_layouts(&result);
+        let wgsl = format!("{result}");
+        if let Some(capabilities) = self.validation {
+            self.validate(&result, &wgsl, capabilities)?;
+        }
+        Ok((wgsl, reflection))
+    }
+
+    /// The flattened translation unit of the module members at `paths`, and its text.
+    pub(crate) fn compile_translation_unit(
+        &self,
+        paths: &[Path],
+    ) -> Result<(TranslationUnit, String)> {
+        let result = self
+            .compile_paths(paths)
+            .map_err(|err| self.attribute_to_module(err))?;
+        let wgsl = format!("{result}");
+        Ok((result, wgsl))
+    }
 
+    /// Validates `wgsl`, the text of `result`, with naga.
+    pub(crate) fn validate(
+        &self,
+        result: &TranslationUnit,
+        wgsl: &str,
+        capabilities: naga::valid::Capabilities,
+    ) -> Result<(naga::Module, naga::valid::ModuleInfo)> {
+        parse_and_validate(wgsl, capabilities)
+            .map_err(|failure| self.attribute_to_module(failure.into_error(result, wgsl)))
+    }
+
+    fn compile_paths(&self, paths: &[Path]) -> Result<TranslationUnit> {
+        let mut result = self.mangle_paths(paths)?;
+        self.flatten(&mut result)?;
+        Ok(result)
+    }
+
+    /// Flattens a mangled translation unit, then checks the layout of its uniform buffers,
+    /// which needs every struct at the root.
+    fn flatten(&self, result: &mut TranslationUnit) -> Result<()> {
+        let mut flattener = mew_flatten::Flattener;
+        flattener.apply_mut(result)?;
+        UniformLayoutChecker.apply_mut(result)?;
+        Ok(())
+    }
+
+    /// Runs every pass but the flattener: declarations are still nested in their modules,
+    /// with mangled names.
+    fn mangle_paths(&self, paths: &[Path]) -> Result<TranslationUnit> {
         let mut resolver = mew_resolve::Resolver;
         let mut result = self.translation_unit.clone();
 
-        let alias = Alias {
-            name: Spanned::new(
-                alias_name_path
-                    .into_iter()
-                    .map(|x| x.name.value)
-                    .collect::<Vec<String>>()
-                    .join("_"),
-                0..0,
-            ),
-            typ: Spanned::new(
-                TypeExpression {
-                    path: Spanned::new(path, 0..0),
-                },
-                0..0,
-            ),
-            template_parameters: vec![],
-        };
+        // every entry point is referenced by an alias at the root of the translation unit,
+        // named after its mangled path.
+        let mut entry_paths = vec![];
+        for path in paths {
+            let path = path.parts()?;
+
+            let mut alias_name_path = path.clone();
+            mangle_path(&mut alias_name_path);
+
+            let alias = Alias {
+                attributes: vec![],
+                name: Spanned::new(
+                    alias_name_path
+                        .into_iter()
+                        .map(|x| x.name.value)
+                        .collect::<Vec<String>>()
+                        .join("_"),
+                    0..0,
+                ),
+                typ: Spanned::new(
+                    TypeExpression {
+                        path: Spanned::new(path, 0..0),
+                    },
+                    0..0,
+                ),
+                template_parameters: vec![],
+            };
 
-        let entry_path = vec![PathPart {
-            name: alias.name.clone(),
-            template_args: None,
-            inline_template_args: None,
-        }];
+            let entry_path = vec![PathPart {
+                name: alias.name.clone(),
+                template_args: None,
+                inline_template_args: None,
+            }];
+            if entry_paths.contains(&entry_path) {
+                continue;
+            }
+            entry_paths.push(entry_path);
 
-        result
-            .global_declarations
-            .push(Spanned::new(GlobalDeclaration::Alias(alias), 0..0));
+            result
+                .global_declarations
+                .push(Spanned::new(GlobalDeclaration::Alias(alias), 0..0));
+        }
 
         resolver.apply_mut(&mut result)?;
 
@@ -179,26 +280,71 @@ impl MewApi {
         normalizer.apply_mut(&mut result)?;
 
         let mut specializer = mew_specialize::Specializer {
-            entrypoint: Some(entry_path),
+            entrypoints: entry_paths.clone(),
         };
 
         specializer.apply_mut(&mut result)?;
 
-        let mut dealiaser = mew_dealias::Dealiaser;
+        // the entry points are aliases, the eliminator needs the declarations they point to
+        let mut dealiaser = mew_dealias::Dealiaser {
+            entrypoints: entry_paths,
+        };
 
         dealiaser.apply_mut(&mut result)?;
 
+        // types are concrete once specialized and dealiased, names are still unmangled
+        let mut type_checker = mew_typecheck::TypeChecker;
+
+        type_checker.apply_mut(&mut result)?;
+
+        let mut eliminator = mew_dce::DeadCodeEliminator {
+            entrypoints: dealiaser.entrypoints,
+        };
+
+        eliminator.apply_mut(&mut result)?;
+
         let mut mangler = mew_mangle::Mangler;
 
         mangler.apply_mut(&mut result)?;
 
-        let mut flattener = mew_flatten::Flattener;
-        flattener.apply_mut(&mut result)?;
+        Ok(result)
+    }
 
-        Ok(format!("{result}"))
+    /// Renders `err` with the source of the file it occurred in.
+    pub fn format_error(&self, err: &MewError) -> String {
+        err.render(&self.source_map)
     }
 
-    // pub fn format_error(&self, _: MewError) -> String {
-    //     todo!();
-    // }
+    /// Like [`MewApi::format_error`], without colors.
+    pub fn format_error_plain(&self, err: &MewError) -> String {
+        err.render_plain(&self.source_map)
+    }
+
+    /// Passes report spans but not the module they belong to. Fills in the module whose
+    /// file contains the span of `err`.
+    pub(crate) fn attribute_to_module(&self, err: MewError) -> MewError {
+        if let MewErrorInner::Many(errors) = err.error {
+            return MewError {
+                error: MewErrorInner::Many(
+                    errors
+                        .into_iter()
+                        .map(|err| self.attribute_to_module(err))
+                        .collect(),
+                ),
+                ..err
+            };
+        }
+        let (None, Some(span)) = (&err.module_name, &err.span) else {
+            return err;
+        };
+        let Some(file) = self.source_map.lookup(span) else {
+            return err;
+        };
+        let module_name = self
+            .modules
+            .iter()
+            .find(|(_, module_file)| **module_file == file)
+            .map(|(module_name, _)| module_name.clone());
+        MewError { module_name, ..err }
+    }
 }
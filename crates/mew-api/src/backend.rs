@@ -0,0 +1,88 @@
+//! Translates compiled modules to other shading languages with naga's backends. Each
+//! backend takes naga's options for it, e.g. the GLSL version, HLSL shader model or MSL
+//! version.
+
+use std::fmt::Display;
+
+use naga::{
+    back::{glsl, hlsl, msl, spv},
+    proc::BoundsCheckPolicies,
+    valid::{Capabilities, ModuleInfo},
+};
+
+use crate::{MewApi, MewError, MewErrorInner, Path};
+
+type Result<T> = std::result::Result<T, MewError>;
+
+impl MewApi {
+    /// Compiles the module member at `path` to a naga module, the input of the backends. The
+    /// module is validated with [`MewApi::validation`], or with every capability if it is
+    /// `None`.
+    pub fn compile_module(&self, path: &Path) -> Result<(naga::Module, ModuleInfo)> {
+        let (result, wgsl) = self.compile_translation_unit(std::slice::from_ref(path))?;
+        let capabilities = self.validation.unwrap_or(Capabilities::all());
+        self.validate(&result, &wgsl, capabilities)
+    }
+
+    /// Compiles the module member at `path` to SPIR-V words.
+    pub fn compile_spirv(&self, path: &Path, options: &spv::Options) -> Result<Vec<u32>> {
+        let (module, info) = self.compile_module(path)?;
+        spv::write_vec(&module, &info, options, None).map_err(|err| backend_error("SPIR-V", err))
+    }
+
+    /// Compiles the module member at `path` to GLSL. A GLSL shader has a single entry point,
+    /// so the compiled module must have exactly one.
+    pub fn compile_glsl(&self, path: &Path, options: &glsl::Options) -> Result<String> {
+        let (module, info) = self.compile_module(path)?;
+        let [entry_point] = module.entry_points.as_slice() else {
+            let message = format!(
+                "expected exactly one entry point, found {}",
+                module.entry_points.len()
+            );
+            return Err(backend_error("GLSL", message));
+        };
+        let pipeline_options = glsl::PipelineOptions {
+            shader_stage: entry_point.stage,
+            entry_point: entry_point.name.clone(),
+            multiview: None,
+        };
+        let mut glsl = String::new();
+        glsl::Writer::new(
+            &mut glsl,
+            &module,
+            &info,
+            options,
+            &pipeline_options,
+            BoundsCheckPolicies::default(),
+        )
+        .and_then(|mut writer| writer.write())
+        .map_err(|err| backend_error("GLSL", err))?;
+        Ok(glsl)
+    }
+
+    /// Compiles the module member at `path` to HLSL.
+    pub fn compile_hlsl(&self, path: &Path, options: &hlsl::Options) -> Result<String> {
+        let (module, info) = self.compile_module(path)?;
+        let mut hlsl = String::new();
+        hlsl::Writer::new(&mut hlsl, options)
+            .write(&module, &info, None)
+            .map_err(|err| backend_error("HLSL", err))?;
+        Ok(hlsl)
+    }
+
+    /// Compiles the module member at `path` to MSL.
+    pub fn compile_msl(&self, path: &Path, options: &msl::Options) -> Result<String> {
+        let (module, info) = self.compile_module(path)?;
+        msl::write_string(&module, &info, options, &msl::PipelineOptions::default())
+            .map(|(msl, _)| msl)
+            .map_err(|err| backend_error("MSL", err))
+    }
+}
+
+fn backend_error(language: &str, err: impl Display) -> MewError {
+    MewError {
+        span: None,
+        module_name: None,
+        error: MewErrorInner::BackendError(format!("{language}: {err}")),
+    }
+}
@@ -0,0 +1,375 @@
+//! A [`MewError`] is the error type returned by [`crate::MewApi`].
+
+use std::fmt::Display;
+
+use annotate_snippets::*;
+use itertools::Itertools;
+use mew_parse::{
+    span::{SourceMap, Span},
+    syntax::{FormalTemplateParameter, PathPart},
+};
+use mew_types::{layout::LayoutError, typing::TypeError, CompilerPassError, InternalCompilerError};
+
+use crate::loader::LoadError;
+
+#[derive(Debug)]
+pub enum MewErrorInner {
+    ModuleNotFound,
+    SymbolNotFound(Vec<PathPart>),
+    MissingRequiredTemplateArgument(Box<FormalTemplateParameter>),
+    InternalError(Box<InternalCompilerError>),
+    MalformedTemplateArgument,
+    ParseError(String),
+    /// See [`mew_types::layout`].
+    InvalidLayout(LayoutError),
+    /// See [`mew_typecheck::TypeChecker`].
+    TypeError(TypeError),
+    LoaderError(std::io::Error),
+    /// naga rejected the compiled WGSL, see [`crate::MewApi::validation`].
+    ValidationError(String),
+    /// A naga backend failed to translate the compiled module, see [`crate::backend`].
+    BackendError(String),
+    /// A rename to this name would clash with another declaration, see
+    /// [`crate::MewApi::rename`].
+    NameConflict(String),
+    /// A rename that cannot be done, e.g. to a keyword.
+    InvalidRename(String),
+    /// A path refers to a member marked `@private` from outside its module. The span of the
+    /// error is that of the reference, `declaration` that of the name of the member.
+    PrivateMember {
+        path: Vec<PathPart>,
+        declaration: Span,
+    },
+    /// Several errors, e.g. every unresolved symbol of a module.
+    Many(Vec<MewError>),
+}
+
+#[derive(Debug)]
+pub struct MewError {
+    pub span: Option<Span>,
+    pub module_name: Option<String>,
+    pub error: MewErrorInner,
+}
+
+impl From<CompilerPassError> for MewError {
+    fn from(value: CompilerPassError) -> Self {
+        match value {
+            CompilerPassError::SymbolNotFound(vec, range) => MewError {
+                span: Some(range),
+                module_name: None,
+                error: MewErrorInner::SymbolNotFound(vec),
+            },
+            CompilerPassError::UnableToResolvePath(vec) => MewError {
+                span: None,
+                module_name: None,
+                error: MewErrorInner::SymbolNotFound(vec),
+            },
+            CompilerPassError::MissingRequiredTemplateArgument(spanned, range) => MewError {
+                span: Some(range),
+                module_name: None,
+                error: MewErrorInner::MissingRequiredTemplateArgument(Box::new(spanned.value)),
+            },
+            CompilerPassError::InternalError(internal_compiler_error) => MewError {
+                span: match &internal_compiler_error {
+                    InternalCompilerError::UnexpectedGlobalDirective(_, span)
+                    | InternalCompilerError::UnexpectedModuleDirective(_, span)
+                    | InternalCompilerError::UnexpectedCompoundDirective(_, span) => {
+                        Some(span.clone())
+                    }
+                    InternalCompilerError::UnexpectedMember => None,
+                },
+                module_name: None,
+                error: MewErrorInner::InternalError(Box::new(internal_compiler_error)),
+            },
+            CompilerPassError::MalformedTemplateArgument(range) => MewError {
+                span: Some(range),
+                module_name: None,
+                error: MewErrorInner::MalformedTemplateArgument,
+            },
+            CompilerPassError::ParseError(parse_err, span) => MewError {
+                span: Some(span),
+                module_name: None,
+                error: MewErrorInner::ParseError(parse_err),
+            },
+            CompilerPassError::PrivateMember(path, span, declaration) => MewError {
+                span: Some(span),
+                module_name: None,
+                error: MewErrorInner::PrivateMember { path, declaration },
+            },
+            CompilerPassError::InvalidLayout(err, span) => MewError {
+                span: Some(span),
+                module_name: None,
+                error: MewErrorInner::InvalidLayout(err),
+            },
+            CompilerPassError::TypeError(err, span) => MewError {
+                span: Some(span),
+                module_name: None,
+                error: MewErrorInner::TypeError(err),
+            },
+            CompilerPassError::Many(errors) => MewError {
+                span: None,
+                module_name: None,
+                error: MewErrorInner::Many(errors.into_iter().map(Into::into).collect()),
+            },
+        }
+    }
+}
+
+impl From<LoadError> for MewError {
+    fn from(value: LoadError) -> Self {
+        match value {
+            LoadError::Io(module_path, err) => MewError {
+                span: None,
+                module_name: Some(module_path.join("::")),
+                error: MewErrorInner::LoaderError(err),
+            },
+            LoadError::Pass(module_path, err) => {
+                MewError::from(*err).in_module(&module_path.join("::"))
+            }
+        }
+    }
+}
+
+impl MewErrorInner {
+    /// The text of the annotation attached to the offending span.
+    fn label(&self) -> String {
+        match self {
+            MewErrorInner::ModuleNotFound => "this module does not exist".to_string(),
+            MewErrorInner::SymbolNotFound(_) => "not found in this scope".to_string(),
+            MewErrorInner::MissingRequiredTemplateArgument(param) => {
+                format!("`{}` has no default value and must be provided", param.name)
+            }
+            MewErrorInner::InternalError(_) => "while processing this".to_string(),
+            MewErrorInner::MalformedTemplateArgument => {
+                "this template argument could not be interpreted".to_string()
+            }
+            MewErrorInner::ParseError(_) => "while parsing this".to_string(),
+            MewErrorInner::InvalidLayout(_) => "in the memory layout of this".to_string(),
+            MewErrorInner::TypeError(_) => "in this expression".to_string(),
+            MewErrorInner::LoaderError(_) => "while loading this module".to_string(),
+            MewErrorInner::ValidationError(_) => "in the WGSL generated for this".to_string(),
+            MewErrorInner::NameConflict(_) => "conflicts with this".to_string(),
+            MewErrorInner::PrivateMember { .. } => "not accessible from here".to_string(),
+            MewErrorInner::BackendError(_)
+            | MewErrorInner::InvalidRename(_)
+            | MewErrorInner::Many(_) => String::new(),
+        }
+    }
+}
+
+impl Display for MewErrorInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MewErrorInner::ModuleNotFound => write!(f, "module not found"),
+            MewErrorInner::SymbolNotFound(path) => {
+                write!(f, "cannot find `{}`", path.iter().format("::"))
+            }
+            MewErrorInner::MissingRequiredTemplateArgument(param) => {
+                write!(f, "missing required template argument `{}`", param.name)
+            }
+            MewErrorInner::InternalError(err) => match err.as_ref() {
+                InternalCompilerError::UnexpectedGlobalDirective(directive, _) => {
+                    write!(
+                        f,
+                        "internal compiler error: unexpected directive `{directive}`"
+                    )
+                }
+                InternalCompilerError::UnexpectedModuleDirective(directive, _) => {
+                    write!(
+                        f,
+                        "internal compiler error: unexpected directive `{directive}`"
+                    )
+                }
+                InternalCompilerError::UnexpectedCompoundDirective(directive, _) => {
+                    write!(
+                        f,
+                        "internal compiler error: unexpected directive `{directive}`"
+                    )
+                }
+                InternalCompilerError::UnexpectedMember => {
+                    write!(f, "internal compiler error: unexpected member")
+                }
+            },
+            MewErrorInner::MalformedTemplateArgument => write!(f, "malformed template argument"),
+            // parse errors are already rendered by the parser
+            MewErrorInner::ParseError(message) => write!(f, "{message}"),
+            MewErrorInner::InvalidLayout(err) => write!(f, "{err}"),
+            MewErrorInner::TypeError(err) => write!(f, "{err}"),
+            MewErrorInner::LoaderError(err) => write!(f, "failed to load module: {err}"),
+            MewErrorInner::ValidationError(message) => write!(f, "invalid WGSL: {message}"),
+            MewErrorInner::BackendError(message) => write!(f, "failed to write {message}"),
+            MewErrorInner::NameConflict(name) => {
+                write!(
+                    f,
+                    "cannot rename to `{name}`, which is already declared in scope"
+                )
+            }
+            MewErrorInner::InvalidRename(reason) => write!(f, "cannot rename: {reason}"),
+            MewErrorInner::PrivateMember { path, .. } => {
+                write!(f, "`{}` is private", path.iter().format("::"))
+            }
+            MewErrorInner::Many(errors) => write!(f, "{}", errors.iter().format("\n")),
+        }
+    }
+}
+
+impl MewError {
+    /// Sets the module of this error, and of the errors it contains, if it is not known yet.
+    pub fn in_module(mut self, module_name: &str) -> Self {
+        if let MewErrorInner::Many(errors) = self.error {
+            self.error = MewErrorInner::Many(
+                errors
+                    .into_iter()
+                    .map(|err| err.in_module(module_name))
+                    .collect(),
+            );
+        }
+        self.module_name
+            .get_or_insert_with(|| module_name.to_string());
+        self
+    }
+
+    /// Flattens [`MewErrorInner::Many`] into the errors it contains.
+    pub fn into_errors(self) -> Vec<MewError> {
+        match self.error {
+            MewErrorInner::Many(errors) => {
+                errors.into_iter().flat_map(MewError::into_errors).collect()
+            }
+            _ => vec![self],
+        }
+    }
+
+    /// Renders the error with the part of the file it points to highlighted.
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        self.render_with(source_map, true)
+    }
+
+    /// Like [`MewError::render`], without colors, e.g. for logs and compiler messages.
+    pub fn render_plain(&self, source_map: &SourceMap) -> String {
+        self.render_with(source_map, false)
+    }
+
+    /// The error message on a single line, without colors.
+    pub fn message(&self) -> String {
+        match &self.error {
+            // the title of the rendered parse error
+            MewErrorInner::ParseError(message) => {
+                let message = strip_styles(message);
+                let title = message.lines().next().unwrap_or_default();
+                let title = title.strip_prefix("error: ").unwrap_or(title);
+                match &self.module_name {
+                    Some(module_name) => format!("{title} (in module `{module_name}`)"),
+                    None => title.to_string(),
+                }
+            }
+            MewErrorInner::Many(errors) => errors.iter().map(MewError::message).join("; "),
+            _ => self.to_string(),
+        }
+    }
+
+    fn render_with(&self, source_map: &SourceMap, styled: bool) -> String {
+        match &self.error {
+            MewErrorInner::ParseError(message) if styled => return message.clone(),
+            MewErrorInner::ParseError(message) => return strip_styles(message),
+            MewErrorInner::Many(errors) => {
+                return errors
+                    .iter()
+                    .map(|err| err.render_with(source_map, styled))
+                    .join("\n");
+            }
+            _ => {}
+        }
+        let renderer = if styled {
+            Renderer::styled()
+        } else {
+            Renderer::plain()
+        };
+        let title = self.error.to_string();
+        let label = self.error.label();
+        let note = self
+            .module_name
+            .as_ref()
+            .map(|module_name| format!("in module `{module_name}`"));
+        let file = self
+            .span
+            .as_ref()
+            .and_then(|span| Some((span, source_map.lookup(span)?)));
+        // the declaration that the error refers to, e.g. that of a private member
+        let secondary = match &self.error {
+            MewErrorInner::PrivateMember { declaration, .. } => source_map
+                .lookup(declaration)
+                .map(|file| (declaration, file, "declared private here")),
+            _ => None,
+        };
+        let mut message = Level::Error.title(&title);
+        match file {
+            Some((span, file)) => {
+                let source = source_map.file(file);
+                let snippet = Snippet::source(&source.text)
+                    .origin(&source.name)
+                    .fold(true)
+                    .annotation(Level::Error.span(source.local_span(span)).label(&label));
+                let secondary = secondary.map(|(span, other, label)| {
+                    let other_source = source_map.file(other);
+                    let annotation = Level::Info.span(other_source.local_span(span)).label(label);
+                    (other, other_source, annotation)
+                });
+                match secondary {
+                    Some((other, _, annotation)) if other == file => {
+                        message = message.snippet(snippet.annotation(annotation));
+                    }
+                    Some((_, other_source, annotation)) => {
+                        message = message.snippet(snippet).snippet(
+                            Snippet::source(&other_source.text)
+                                .origin(&other_source.name)
+                                .fold(true)
+                                .annotation(annotation),
+                        );
+                    }
+                    None => message = message.snippet(snippet),
+                }
+            }
+            None => {
+                if let Some(note) = note.as_ref() {
+                    message = message.footer(Level::Note.title(note));
+                }
+            }
+        }
+        format!("{}", renderer.render(message))
+    }
+}
+
+/// Removes the ANSI escape sequences of errors rendered by the parser.
+fn strip_styles(message: &str) -> String {
+    let mut plain = String::with_capacity(message.len());
+    let mut chars = message.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // `ESC [ parameters final-byte`
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+impl std::error::Error for MewError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.error {
+            MewErrorInner::LoaderError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Display for MewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.module_name, &self.error) {
+            (Some(module_name), error) if !matches!(error, MewErrorInner::Many(_)) => {
+                write!(f, "{} (in module `{module_name}`)", self.error)
+            }
+            _ => write!(f, "{}", self.error),
+        }
+    }
+}
@@ -1,2 +1,14 @@
 pub mod api;
+pub mod backend;
+pub mod error;
+pub mod loader;
+pub mod reflect;
+pub mod rename;
+mod validate;
 pub use api::*;
+pub use error::*;
+pub use loader::*;
+pub use reflect::Reflection;
+pub use rename::RenamedSource;
+
+pub use naga;
@@ -0,0 +1,340 @@
+use std::{collections::HashSet, fmt::Debug, path::PathBuf};
+
+use mew_bundle::Bundler;
+use mew_parse::{
+    span::{FileId, SourceMap, Spanned},
+    syntax::{
+        visit, ExtendDirective, GlobalDeclaration, Module, ModuleMemberDeclaration,
+        TranslationUnit, Use, UseContent, Visit,
+    },
+};
+use mew_resolve::{PACKAGE_ROOT, SELF_ROOT, SUPER_ROOT};
+use mew_types::CompilerPass;
+
+/// The source text of a module found by a [`ModuleLoader`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleSource {
+    pub path: PathBuf,
+    pub text: String,
+}
+
+/// Finds the source of modules that are referenced by `use` and `extend` directives but
+/// were never registered with [`crate::MewApi::add_module`].
+pub trait ModuleLoader: Debug {
+    /// Returns the source of the module at `module_path` (e.g. `["foo", "bar"]` for
+    /// `foo::bar`), or `None` if this loader does not know the module.
+    fn load(&mut self, module_path: &[String]) -> std::io::Result<Option<ModuleSource>>;
+}
+
+/// Loads modules from a directory tree: `foo::bar` is looked up in `foo/bar.mew`,
+/// then in `foo/bar/mod.mew`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSystemLoader {
+    pub root: PathBuf,
+    pub extension: String,
+}
+
+impl FileSystemLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            extension: "mew".to_string(),
+        }
+    }
+
+    fn candidates(&self, module_path: &[String]) -> [PathBuf; 2] {
+        let mut base = self.root.clone();
+        for part in module_path {
+            base.push(part);
+        }
+        [
+            base.with_extension(&self.extension),
+            base.join("mod").with_extension(&self.extension),
+        ]
+    }
+}
+
+impl ModuleLoader for FileSystemLoader {
+    fn load(&mut self, module_path: &[String]) -> std::io::Result<Option<ModuleSource>> {
+        if module_path.is_empty() {
+            return Ok(None);
+        }
+        for candidate in self.candidates(module_path) {
+            if candidate.is_file() {
+                let text = std::fs::read_to_string(&candidate)?;
+                return Ok(Some(ModuleSource {
+                    path: candidate,
+                    text,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+pub(crate) enum LoadError {
+    Io(Vec<String>, std::io::Error),
+    Pass(Vec<String>, Box<mew_types::CompilerPassError>),
+}
+
+/// Walks the `use` and `extend` directives of a translation unit and loads every module
+/// they refer to that is not declared yet, until no new module is found. Loaded sources
+/// are added to `source_map`. Returns the module path (e.g. `foo::bar`), file and origin
+/// of every loaded module.
+pub(crate) fn load_missing_modules(
+    loader: &mut dyn ModuleLoader,
+    translation_unit: &mut TranslationUnit,
+    source_map: &mut SourceMap,
+) -> Result<Vec<(String, FileId, PathBuf)>, LoadError> {
+    let mut attempted: HashSet<Vec<String>> = Default::default();
+    let mut loaded = vec![];
+    loop {
+        let mut paths = vec![];
+        collect_translation_unit_paths(translation_unit, &mut paths);
+
+        let mut found_new = false;
+        for path in paths {
+            found_new |= load_path(
+                loader,
+                translation_unit,
+                source_map,
+                &path,
+                &mut attempted,
+                &mut loaded,
+            )?;
+        }
+        if !found_new {
+            return Ok(loaded);
+        }
+    }
+}
+
+fn load_path(
+    loader: &mut dyn ModuleLoader,
+    translation_unit: &mut TranslationUnit,
+    source_map: &mut SourceMap,
+    path: &[String],
+    attempted: &mut HashSet<Vec<String>>,
+    loaded: &mut Vec<(String, FileId, PathBuf)>,
+) -> Result<bool, LoadError> {
+    let mut existing: Vec<String> = vec![];
+    let mut missing: Vec<String> = vec![];
+    for part in path {
+        if missing.is_empty() {
+            match find_member(translation_unit, &existing, part) {
+                Member::Module => {
+                    existing.push(part.clone());
+                    continue;
+                }
+                Member::Other => return Ok(false),
+                Member::Missing => {}
+            }
+        }
+        missing.push(part.clone());
+
+        let module_path: Vec<String> = existing.iter().chain(missing.iter()).cloned().collect();
+        if !attempted.insert(module_path.clone()) {
+            continue;
+        }
+        let source = loader
+            .load(&module_path)
+            .map_err(|err| LoadError::Io(module_path.clone(), err))?;
+        if let Some(source) = source {
+            let name = missing.pop().unwrap();
+            for intermediate in missing.drain(..) {
+                insert_module(
+                    translation_unit,
+                    &existing,
+                    Module {
+                        name: Spanned::new(intermediate.clone(), 0..0),
+                        ..Default::default()
+                    },
+                );
+                existing.push(intermediate);
+            }
+            let file = source_map.add_file(source.path.display().to_string(), source.text);
+            let mut bundled = TranslationUnit::default();
+            let mut bundler = Bundler {
+                sources: vec![source_map.file(file).text.as_str()],
+                enclosing_module_name: Some(name.clone()),
+                offset: source_map.file(file).start,
+            };
+            bundler
+                .apply_mut(&mut bundled)
+                .map_err(|err| LoadError::Pass(module_path.clone(), Box::new(err)))?;
+            translation_unit
+                .global_directives
+                .append(&mut bundled.global_directives);
+            for decl in bundled.global_declarations {
+                if let GlobalDeclaration::Module(module) = decl.value {
+                    insert_module(translation_unit, &existing, module);
+                }
+            }
+            existing.push(name);
+            loaded.push((module_path.join("::"), file, source.path));
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+enum Member {
+    Module,
+    Other,
+    Missing,
+}
+
+fn find_member(translation_unit: &TranslationUnit, parent: &[String], name: &str) -> Member {
+    let is_named = |decl_name: Option<Spanned<String>>| {
+        decl_name.as_ref().map(|x| x.value.as_str()) == Some(name)
+    };
+    if let Some((first, rest)) = parent.split_first() {
+        let mut module = translation_unit
+            .global_declarations
+            .iter()
+            .find_map(|decl| match &decl.value {
+                GlobalDeclaration::Module(m) if &m.name.value == first => Some(m),
+                _ => None,
+            });
+        for part in rest {
+            module = module.and_then(|m| find_child_module(m, part));
+        }
+        match module.and_then(|m| m.members.iter().find(|x| is_named(x.name()))) {
+            Some(member) if matches!(member.value, ModuleMemberDeclaration::Module(_)) => {
+                Member::Module
+            }
+            Some(_) => Member::Other,
+            None => Member::Missing,
+        }
+    } else {
+        match translation_unit
+            .global_declarations
+            .iter()
+            .find(|x| is_named(x.name()))
+        {
+            Some(decl) if matches!(decl.value, GlobalDeclaration::Module(_)) => Member::Module,
+            Some(_) => Member::Other,
+            None => Member::Missing,
+        }
+    }
+}
+
+fn find_child_module<'a>(module: &'a Module, name: &str) -> Option<&'a Module> {
+    module.members.iter().find_map(|x| match &x.value {
+        ModuleMemberDeclaration::Module(m) if m.name.value == name => Some(m),
+        _ => None,
+    })
+}
+
+fn insert_module(translation_unit: &mut TranslationUnit, parent: &[String], module: Module) {
+    let Some((first, rest)) = parent.split_first() else {
+        translation_unit
+            .global_declarations
+            .push(Spanned::new(GlobalDeclaration::Module(module), 0..0));
+        return;
+    };
+    let mut current = translation_unit
+        .global_declarations
+        .iter_mut()
+        .find_map(|decl| match &mut decl.value {
+            GlobalDeclaration::Module(m) if &m.name.value == first => Some(m),
+            _ => None,
+        })
+        .expect("parent module was loaded before its children");
+    for part in rest {
+        current = current
+            .members
+            .iter_mut()
+            .find_map(|x| match &mut x.value {
+                ModuleMemberDeclaration::Module(m) if &m.name.value == part => Some(m),
+                _ => None,
+            })
+            .expect("parent module was loaded before its children");
+    }
+    current
+        .members
+        .push(Spanned::new(ModuleMemberDeclaration::Module(module), 0..0));
+}
+
+fn collect_use_paths(usage: &Use, prefix: &[String], paths: &mut Vec<Vec<String>>) {
+    let mut path: Vec<String> = prefix.to_vec();
+    path.extend(usage.path.iter().map(|x| x.name.value.clone()));
+    match &usage.content.value {
+        UseContent::Item(item) => {
+            path.push(item.name.value.clone());
+            paths.push(path);
+        }
+        UseContent::Collection(collection) => {
+            for usage in collection.iter() {
+                collect_use_paths(usage, &path, paths);
+            }
+        }
+    }
+}
+
+/// Collects the module paths of every `use` and `extend` directive, from the root of the
+/// translation unit.
+#[derive(Default)]
+struct DirectivePaths {
+    module_path: Vec<String>,
+    paths: Vec<Vec<String>>,
+}
+
+impl DirectivePaths {
+    /// Adds `path`, whose `self::`, `super::` or `package::` root is replaced by the path of
+    /// the module it names.
+    fn push(&mut self, mut path: Vec<String>) {
+        let path = match path.first().map(String::as_str) {
+            Some(PACKAGE_ROOT) => path.split_off(1),
+            Some(SELF_ROOT) => self
+                .module_path
+                .iter()
+                .cloned()
+                .chain(path.drain(1..))
+                .collect(),
+            Some(SUPER_ROOT) => {
+                let levels = path.iter().take_while(|name| *name == SUPER_ROOT).count();
+                let Some(depth) = self.module_path.len().checked_sub(levels) else {
+                    // above the translation unit, reported by the resolver
+                    return;
+                };
+                self.module_path[..depth]
+                    .iter()
+                    .cloned()
+                    .chain(path.drain(levels..))
+                    .collect()
+            }
+            _ => path,
+        };
+        self.paths.push(path);
+    }
+}
+
+impl Visit for DirectivePaths {
+    fn visit_module(&mut self, module: &Module) {
+        self.module_path.push(module.name.value.clone());
+        visit::walk_module(self, module);
+        self.module_path.pop();
+    }
+
+    fn visit_use(&mut self, usage: &Use) {
+        let mut paths = vec![];
+        collect_use_paths(usage, &[], &mut paths);
+        for path in paths {
+            self.push(path);
+        }
+    }
+
+    fn visit_extend_directive(&mut self, extend: &ExtendDirective) {
+        self.push(extend.path.iter().map(|x| x.name.value.clone()).collect());
+    }
+}
+
+fn collect_translation_unit_paths(
+    translation_unit: &TranslationUnit,
+    paths: &mut Vec<Vec<String>>,
+) {
+    let mut visitor = DirectivePaths::default();
+    visitor.visit_translation_unit(translation_unit);
+    paths.append(&mut visitor.paths);
+}
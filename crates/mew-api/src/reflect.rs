@@ -0,0 +1,270 @@
+//! The bindings and entry points of a compiled module, see
+//! [`crate::MewApi::compile_with_reflection`].
+
+use std::collections::HashMap;
+
+use mew_parse::{
+    span::{SourceMap, Spanned},
+    syntax::{
+        visit, Attribute, Declaration, DeclarationKind, Expression, Function, LiteralExpression,
+        Module, Struct, TranslationUnit, TypeExpression, Visit,
+    },
+};
+use mew_types::layout::StructLayout;
+
+/// The interface of a compiled module, in declaration order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Reflection {
+    pub bindings: Vec<Binding>,
+    pub entry_points: Vec<EntryPoint>,
+    /// The layout of every struct that can be shared with the host, by mangled name.
+    pub structs: Vec<StructLayout>,
+}
+
+/// The name of a declaration in the compiled WGSL, and its path in the mew source.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReflectedName {
+    pub mangled: String,
+    /// e.g. `["my_module", "Lib", "main"]`. Template arguments of specialized declarations
+    /// are only part of the mangled name.
+    pub path: Vec<String>,
+}
+
+/// A `var` with `@group` and `@binding` attributes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    pub name: ReflectedName,
+    /// `None` if the attribute is not a literal.
+    pub group: Option<u32>,
+    /// `None` if the attribute is not a literal.
+    pub binding: Option<u32>,
+    /// e.g. `uniform` or `storage`, `None` for textures and samplers.
+    pub address_space: Option<String>,
+    pub access_mode: Option<String>,
+    /// The type, with mangled names.
+    pub ty: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryPoint {
+    pub name: ReflectedName,
+    pub stage: ShaderStage,
+    /// The `@workgroup_size` of a compute entry point, missing dimensions are `1`. `None`
+    /// if there is none, or if it is not made of literals.
+    pub workgroup_size: Option<[u32; 3]>,
+    pub inputs: Vec<InterfaceVariable>,
+    pub outputs: Vec<InterfaceVariable>,
+}
+
+/// A parameter or return value of an entry point with a `@location` or `@builtin`
+/// attribute. Parameters and return values of a struct type are reported member by member.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceVariable {
+    /// The name of the parameter or struct member, `None` for a return value.
+    pub name: Option<String>,
+    pub location: Option<u32>,
+    pub builtin: Option<String>,
+    /// The type, with mangled names.
+    pub ty: String,
+}
+
+/// Reflects `translation_unit`, a mangled translation unit that is not flattened yet.
+/// Original names are read from `source_map` at the spans of the mangled names.
+pub(crate) fn reflect(translation_unit: &TranslationUnit, source_map: &SourceMap) -> Reflection {
+    let mut collector = Collector {
+        source_map,
+        module_path: vec![],
+        structs: HashMap::new(),
+        functions: vec![],
+        reflection: Reflection::default(),
+    };
+    collector.visit_translation_unit(translation_unit);
+
+    let mut reflection = std::mem::take(&mut collector.reflection);
+    for (name, function) in &collector.functions {
+        let Some(stage) = function.attributes.iter().find_map(|attr| stage(attr)) else {
+            continue;
+        };
+        let workgroup_size = attribute(&function.attributes, "workgroup_size").and_then(|args| {
+            let mut size = [1; 3];
+            for (dimension, arg) in size.iter_mut().zip(args) {
+                *dimension = literal_u32(arg)?;
+            }
+            Some(size)
+        });
+        let mut inputs = vec![];
+        for param in &function.parameters {
+            collector.interface(
+                &mut inputs,
+                Some(&param.name.value),
+                &param.attributes,
+                &param.typ,
+            );
+        }
+        let mut outputs = vec![];
+        if let Some(return_type) = &function.return_type {
+            collector.interface(&mut outputs, None, &function.return_attributes, return_type);
+        }
+        reflection.entry_points.push(EntryPoint {
+            name: name.clone(),
+            stage,
+            workgroup_size,
+            inputs,
+            outputs,
+        });
+    }
+    reflection
+}
+
+struct Collector<'a> {
+    source_map: &'a SourceMap,
+    module_path: Vec<String>,
+    /// Every struct, by mangled name, to report the members of interface structs.
+    structs: HashMap<String, Struct>,
+    /// Every function, reflected once all structs are known.
+    functions: Vec<(ReflectedName, Function)>,
+    reflection: Reflection,
+}
+
+impl Collector<'_> {
+    fn name(&self, name: &Spanned<String>) -> ReflectedName {
+        let span = name.span();
+        let original = match self.source_map.lookup(&span) {
+            Some(file) => {
+                let file = self.source_map.file(file);
+                file.text[file.local_span(&span)].to_string()
+            }
+            // synthesized declarations have no source
+            None => name.value.clone(),
+        };
+        let mut path = self.module_path.clone();
+        path.push(original);
+        ReflectedName {
+            mangled: name.value.clone(),
+            path,
+        }
+    }
+
+    fn interface(
+        &self,
+        variables: &mut Vec<InterfaceVariable>,
+        name: Option<&String>,
+        attributes: &[Spanned<Attribute>],
+        ty: &TypeExpression,
+    ) {
+        let location =
+            attribute(attributes, "location").and_then(|args| literal_u32(args.first()?));
+        let builtin = attribute(attributes, "builtin")
+            .and_then(|args| args.first())
+            .map(|arg| format!("{arg}"));
+        if location.is_some() || builtin.is_some() {
+            variables.push(InterfaceVariable {
+                name: name.cloned(),
+                location,
+                builtin,
+                ty: format!("{ty}"),
+            });
+            return;
+        }
+        let interface_struct = match ty.path.as_slice() {
+            [part] if part.template_args.is_none() => self.structs.get(&part.name.value),
+            _ => None,
+        };
+        for member in interface_struct.iter().flat_map(|s| &s.members) {
+            self.interface(
+                variables,
+                Some(&member.name.value),
+                &member.attributes,
+                &member.typ,
+            );
+        }
+    }
+}
+
+impl Visit for Collector<'_> {
+    fn visit_module(&mut self, node: &Module) {
+        self.module_path.push(node.name.value.clone());
+        visit::walk_module(self, node);
+        self.module_path.pop();
+    }
+
+    fn visit_struct(&mut self, node: &Struct) {
+        self.structs.insert(node.name.value.clone(), node.clone());
+    }
+
+    fn visit_function(&mut self, node: &Function) {
+        let name = self.name(&node.name);
+        self.functions.push((name, node.clone()));
+    }
+
+    fn visit_declaration(&mut self, node: &Declaration) {
+        if node.kind.value != DeclarationKind::Var {
+            return;
+        }
+        let (Some(group), Some(binding)) = (
+            attribute(&node.attributes, "group"),
+            attribute(&node.attributes, "binding"),
+        ) else {
+            return;
+        };
+        let mut template_args = node.template_args.iter().flatten();
+        let mut template_arg = || {
+            template_args
+                .next()
+                .map(|arg| format!("{}", arg.expression))
+        };
+        let binding = Binding {
+            name: self.name(&node.name),
+            group: group.first().and_then(literal_u32),
+            binding: binding.first().and_then(literal_u32),
+            address_space: template_arg(),
+            access_mode: template_arg(),
+            ty: node
+                .typ
+                .as_ref()
+                .map(|ty| format!("{ty}"))
+                .unwrap_or_default(),
+        };
+        self.reflection.bindings.push(binding);
+    }
+}
+
+/// The arguments of the attribute called `name`.
+fn attribute<'a>(
+    attributes: &'a [Spanned<Attribute>],
+    name: &str,
+) -> Option<&'a [Spanned<Expression>]> {
+    attributes
+        .iter()
+        .find(|attr| attr.name.value == name)
+        .map(|attr| attr.arguments.as_deref().unwrap_or_default())
+}
+
+fn stage(attribute: &Attribute) -> Option<ShaderStage> {
+    match attribute.name.value.as_str() {
+        "vertex" => Some(ShaderStage::Vertex),
+        "fragment" => Some(ShaderStage::Fragment),
+        "compute" => Some(ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+fn literal_u32(expression: &Spanned<Expression>) -> Option<u32> {
+    match &expression.value {
+        Expression::Literal(literal) => match &literal.value {
+            LiteralExpression::AbstractInt(value) => value.parse().ok(),
+            LiteralExpression::I32(value) => u32::try_from(*value).ok(),
+            LiteralExpression::U32(value) => Some(*value),
+            _ => None,
+        },
+        Expression::Parenthesized(expression) => literal_u32(expression),
+        _ => None,
+    }
+}
@@ -0,0 +1,254 @@
+//! Renames a declaration, a module or a template parameter along with every reference to
+//! it, see [`MewApi::rename`]. The sources are edited in place, so that their formatting
+//! and comments are kept.
+
+use std::collections::{BTreeMap, HashSet};
+
+use mew_parse::{
+    span::{FileId, Span, Spanned},
+    syntax::{
+        visit_mut::{self, VisitMut},
+        Alias, Declaration, FormalTemplateParameter, Function, Module, PathPart, Struct,
+        TemplateArg, UseItem,
+    },
+};
+use mew_resolve::{index::Symbol, Resolver, SymbolIndex};
+
+use crate::{MewApi, MewError, MewErrorInner, Path};
+
+type Result<T> = std::result::Result<T, MewError>;
+
+/// The text of a source file after a rename.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamedSource {
+    pub file: FileId,
+    pub text: String,
+}
+
+/// Renames the names at `spans`, which keep their span.
+struct Rename<'a> {
+    spans: &'a HashSet<Span>,
+    new_name: &'a str,
+}
+
+impl Rename<'_> {
+    fn rename(&self, name: &mut Spanned<String>) {
+        if self.spans.contains(&name.span()) {
+            name.value = self.new_name.to_string();
+        }
+    }
+}
+
+impl VisitMut for Rename<'_> {
+    fn visit_module(&mut self, module: &mut Module) {
+        self.rename(&mut module.name);
+        visit_mut::walk_module(self, module);
+    }
+
+    fn visit_declaration(&mut self, declaration: &mut Declaration) {
+        self.rename(&mut declaration.name);
+        visit_mut::walk_declaration(self, declaration);
+    }
+
+    fn visit_alias(&mut self, alias: &mut Alias) {
+        self.rename(&mut alias.name);
+        visit_mut::walk_alias(self, alias);
+    }
+
+    fn visit_struct(&mut self, s: &mut Struct) {
+        self.rename(&mut s.name);
+        visit_mut::walk_struct(self, s);
+    }
+
+    fn visit_function(&mut self, function: &mut Function) {
+        self.rename(&mut function.name);
+        visit_mut::walk_function(self, function);
+    }
+
+    fn visit_formal_template_parameter(&mut self, parameter: &mut FormalTemplateParameter) {
+        self.rename(&mut parameter.name);
+        visit_mut::walk_formal_template_parameter(self, parameter);
+    }
+
+    fn visit_template_arg(&mut self, arg: &mut TemplateArg) {
+        if let Some(name) = arg.arg_name.as_mut() {
+            self.rename(name);
+        }
+        visit_mut::walk_template_arg(self, arg);
+    }
+
+    fn visit_use_item(&mut self, item: &mut UseItem) {
+        self.rename(&mut item.name);
+        visit_mut::walk_use_item(self, item);
+    }
+
+    fn visit_path_part(&mut self, part: &mut PathPart) {
+        self.rename(&mut part.name);
+        visit_mut::walk_path_part(self, part);
+    }
+}
+
+fn conflict(new_name: &str, span: Span) -> MewError {
+    MewError {
+        span: Some(span),
+        module_name: None,
+        error: MewErrorInner::NameConflict(new_name.to_string()),
+    }
+}
+
+fn invalid(reason: String) -> MewError {
+    MewError {
+        span: None,
+        module_name: None,
+        error: MewErrorInner::InvalidRename(reason),
+    }
+}
+
+impl MewApi {
+    /// Renames the declaration, module or template parameter at `path`, e.g.
+    /// `lighting::Light` or `lighting::Tinted::factor` for `mod Tinted<factor>`, to
+    /// `new_name`. Returns the new text of every file that changes; the translation unit is
+    /// left as it is.
+    ///
+    /// The paths, `use` items and members of `with { }` blocks that refer to the declaration
+    /// are renamed, but not the names that `use ... as` items import it as. The rename is
+    /// rejected when `new_name` is already declared next to the declaration, or when it
+    /// would change what another path refers to, e.g. a local variable of that name, and
+    /// when the declaration is used in a module added as a [`crate::Source::Ast`], which has
+    /// no source text to edit.
+    pub fn rename(&self, path: &Path, new_name: &str) -> Result<Vec<RenamedSource>> {
+        let path = path
+            .parts()?
+            .into_iter()
+            .map(|part| part.name.value)
+            .collect::<Vec<_>>();
+        let is_identifier = mew_parse::Parser::parse_path(new_name).is_ok_and(
+            |parsed| matches!(parsed.path.as_slice(), [part] if part.name.value == new_name),
+        );
+        if !is_identifier {
+            return Err(invalid(format!("`{new_name}` is not an identifier")));
+        }
+
+        let (index, result) = Resolver.index(&self.translation_unit);
+        result.map_err(|err| self.attribute_to_module(err.into()))?;
+        let Some(symbol) = index.get(&path) else {
+            let parts = path
+                .iter()
+                .map(|name| PathPart {
+                    name: Spanned::new(name.clone(), 0..0),
+                    template_args: None,
+                    inline_template_args: None,
+                })
+                .collect();
+            return Err(MewError {
+                span: None,
+                module_name: None,
+                error: MewErrorInner::SymbolNotFound(parts),
+            });
+        };
+        if symbol.definition.is_empty() {
+            return Err(invalid(format!(
+                "`{}` is the module of a file, rename the file instead",
+                path.join("::")
+            )));
+        }
+        let Some((old_name, parent)) = path.split_last() else {
+            return Ok(vec![]);
+        };
+        if old_name == new_name {
+            return Ok(vec![]);
+        }
+        let mut renamed_path = parent.to_vec();
+        renamed_path.push(new_name.to_string());
+        if let Some(existing) = index.get(&renamed_path) {
+            return Err(self.attribute_to_module(conflict(new_name, existing.definition.clone())));
+        }
+
+        let spans = std::iter::once(symbol.definition.clone())
+            .chain(
+                symbol
+                    .references
+                    .iter()
+                    .filter(|reference| !reference.renamed)
+                    .map(|reference| reference.span.clone()),
+            )
+            .collect::<HashSet<_>>();
+        self.check_rename(&index, &path, &spans, new_name)?;
+
+        // modules added as syntax trees have no file, and their spans may fall in another
+        // file, where the text at the span is then not the old name
+        let mut edits: BTreeMap<FileId, Vec<Span>> = BTreeMap::new();
+        for span in spans {
+            let file = self.source_map.lookup(&span).filter(|file| {
+                let source = self.source_map.file(*file);
+                source.text.get(source.local_span(&span)) == Some(old_name.as_str())
+            });
+            let Some(file) = file else {
+                return Err(invalid(format!(
+                    "`{}` is used in a module that has no source text, e.g. one added as a \
+                     syntax tree",
+                    path.join("::")
+                )));
+            };
+            edits.entry(file).or_default().push(span);
+        }
+        let renamed = edits
+            .into_iter()
+            .map(|(file, mut spans)| {
+                let source = self.source_map.file(file);
+                let mut text = source.text.clone();
+                spans.sort_by_key(|span| std::cmp::Reverse(span.start));
+                for span in spans {
+                    text.replace_range(source.local_span(&span), new_name);
+                }
+                RenamedSource { file, text }
+            })
+            .collect();
+        Ok(renamed)
+    }
+
+    /// Renames the names at `spans` in a copy of the translation unit, and checks that every
+    /// declaration is still referenced from the same spans.
+    fn check_rename(
+        &self,
+        index: &SymbolIndex,
+        path: &[String],
+        spans: &HashSet<Span>,
+        new_name: &str,
+    ) -> Result<()> {
+        let mut translation_unit = self.translation_unit.clone();
+        Rename { spans, new_name }.visit_translation_unit(&mut translation_unit);
+        let (renamed_index, result) = Resolver.index(&translation_unit);
+        if let Err(err) = result {
+            // e.g. a `use` item that now imports the same name as another one
+            let errors = MewError::from(err).into_errors();
+            let span = errors.into_iter().find_map(|err| err.span);
+            return Err(self.attribute_to_module(conflict(new_name, span.unwrap_or_default())));
+        }
+        for (declared, symbol) in index.iter() {
+            let mut declared = declared.to_vec();
+            if declared.starts_with(path) {
+                declared[path.len() - 1] = new_name.to_string();
+            }
+            let Some(renamed) = renamed_index.get(&declared) else {
+                return Err(self.attribute_to_module(conflict(new_name, symbol.definition.clone())));
+            };
+            let spans = |symbol: &Symbol| {
+                symbol
+                    .references
+                    .iter()
+                    .map(|reference| reference.span.clone())
+                    .collect::<HashSet<_>>()
+            };
+            let (before, after) = (spans(symbol), spans(renamed));
+            // the spans of the references that now point somewhere else
+            if let Some(span) = before
+                .symmetric_difference(&after)
+                .min_by_key(|span| span.start)
+            {
+                return Err(self.attribute_to_module(conflict(new_name, span.clone())));
+            }
+        }
+        Ok(())
+    }
+}
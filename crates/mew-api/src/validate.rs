@@ -0,0 +1,144 @@
+//! Validates compiled WGSL with naga, see [`crate::MewApi::validation`].
+
+use std::{collections::HashMap, ops::Range};
+
+use mew_parse::{span::Span, syntax::TranslationUnit};
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+
+use crate::{MewError, MewErrorInner};
+
+/// An error reported by naga, with the ranges of the WGSL it points to.
+pub(crate) struct ValidationFailure {
+    ranges: Vec<Range<usize>>,
+    message: String,
+}
+
+/// Parses `wgsl` with naga and validates it.
+pub(crate) fn parse_and_validate(
+    wgsl: &str,
+    capabilities: Capabilities,
+) -> Result<(naga::Module, ModuleInfo), ValidationFailure> {
+    let module = naga::front::wgsl::parse_str(wgsl).map_err(|err| ValidationFailure {
+        ranges: err
+            .labels()
+            .filter_map(|(span, _)| span.to_range())
+            .collect(),
+        message: err.message().to_string(),
+    })?;
+    let info = Validator::new(ValidationFlags::all(), capabilities)
+        .validate(&module)
+        .map_err(|err| {
+            let mut message = err.as_inner().to_string();
+            let mut source = std::error::Error::source(err.as_inner());
+            while let Some(err) = source {
+                message = format!("{message}: {err}");
+                source = err.source();
+            }
+            ValidationFailure {
+                ranges: err
+                    .spans()
+                    .filter_map(|(span, _)| span.to_range())
+                    .collect(),
+                message,
+            }
+        })?;
+    Ok((module, info))
+}
+
+impl ValidationFailure {
+    /// Maps the ranges of `wgsl`, the text of `translation_unit`, that naga points to back
+    /// to the mew source, through the mangled names of the declarations. Names keep their
+    /// span through mangling and flattening. A range points to the first declaration it
+    /// names, or else to the declaration it is in. Every range that maps is reported, naga
+    /// reports e.g. an expression and the function it is in.
+    pub(crate) fn into_error(self, translation_unit: &TranslationUnit, wgsl: &str) -> MewError {
+        let names = declaration_names(translation_unit);
+        let words: Vec<_> = words(wgsl).collect();
+        // the names of the declarations, where they are declared in `wgsl`
+        let declared: Vec<_> = words
+            .windows(2)
+            .filter(|pair| DECLARATION_KEYWORDS.contains(&pair[0].1))
+            .filter_map(|pair| Some((pair[1].0, names.get(pair[1].1)?)))
+            .collect();
+        let mut spans: Vec<&Span> = vec![];
+        for range in &self.ranges {
+            let named = words
+                .iter()
+                .filter(|(offset, _)| range.contains(offset))
+                .find_map(|(_, word)| names.get(*word));
+            let enclosing = || {
+                declared
+                    .iter()
+                    .rev()
+                    .find(|(offset, _)| *offset <= range.start)
+                    .map(|(_, span)| *span)
+            };
+            if let Some(span) = named
+                .or_else(enclosing)
+                .filter(|span| !spans.contains(span))
+            {
+                spans.push(span);
+            }
+        }
+        let error = |span: Option<&Span>| MewError {
+            span: span.cloned(),
+            module_name: None,
+            error: MewErrorInner::ValidationError(self.message.clone()),
+        };
+        match spans.as_slice() {
+            [] => error(None),
+            [span] => error(Some(span)),
+            spans => MewError {
+                span: None,
+                module_name: None,
+                error: MewErrorInner::Many(spans.iter().map(|span| error(Some(span))).collect()),
+            },
+        }
+    }
+}
+
+/// The words that precede the name of a global declaration, including the address spaces
+/// and access modes of `var<...>`.
+const DECLARATION_KEYWORDS: &[&str] = &[
+    "fn",
+    "struct",
+    "alias",
+    "const",
+    "override",
+    "var",
+    "private",
+    "workgroup",
+    "uniform",
+    "storage",
+    "read",
+    "read_write",
+    "handle",
+];
+
+/// The span in the mew source of the name of every global declaration of
+/// `translation_unit`, by mangled name.
+fn declaration_names(translation_unit: &TranslationUnit) -> HashMap<String, Span> {
+    translation_unit
+        .global_declarations
+        .iter()
+        .filter_map(|declaration| declaration.name())
+        // synthesized declarations have no source
+        .filter(|name| name.span().start > 0)
+        .map(|name| (name.value.clone(), name.span()))
+        .collect()
+}
+
+/// The identifiers and keywords of `text`, with their offsets.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    text.char_indices()
+        .filter(move |(offset, c)| {
+            is_word(*c) && !text[..*offset].ends_with(is_word) && !c.is_ascii_digit()
+        })
+        .map(move |(start, _)| {
+            let end = text[start..]
+                .find(|c: char| !is_word(c))
+                .map_or(text.len(), |end| start + end);
+            (start, &text[start..end])
+        })
+}
@@ -0,0 +1,216 @@
+//! Compiles mew shaders from a `build.rs`, so that shader errors fail the build instead of
+//! showing up at runtime:
+//!
+//! ```no_run
+//! mew_build::Build {
+//!     modules: vec!["shaders/main.mew".into()],
+//!     entries: vec![mew_build::Entry::new("main::fs_main", "fs_main.wgsl")],
+//!     ..Default::default()
+//! }
+//! .compile();
+//! ```
+//!
+//! The application then includes the WGSL with
+//! `include_str!(concat!(env!("OUT_DIR"), "/fs_main.wgsl"))`. Rust mirrors of the structs
+//! of an entry can also be generated, see [`Entry::with_host_types`] and [`crate::host`].
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use mew_api::{FileSystemLoader, MewApi, ModuleDescriptor, Source};
+
+use crate::rust_host_types;
+
+/// A set of root modules and the entry points to compile from them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Build {
+    /// Root files. Each is added as a module named after its file, with `-` replaced by `_`.
+    pub modules: Vec<PathBuf>,
+    /// The directory in which modules referenced by `use` and `extend` are looked up.
+    /// Defaults to the directory of the first root file.
+    pub root: Option<PathBuf>,
+    pub entries: Vec<Entry>,
+    /// The directory the WGSL files are written to. Defaults to `OUT_DIR`.
+    pub out_dir: Option<PathBuf>,
+}
+
+/// A module member to compile, e.g. `My_Lib<A, B>::main`, and the file its WGSL is written
+/// to, relative to [`Build::out_dir`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub path: String,
+    pub output: PathBuf,
+    /// The file the Rust types of the structs and bindings of the entry are written to,
+    /// relative to [`Build::out_dir`], see [`crate::rust_host_types`].
+    pub host_types: Option<PathBuf>,
+}
+
+impl Entry {
+    pub fn new(path: impl Into<String>, output: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            output: output.into(),
+            host_types: None,
+        }
+    }
+
+    /// Also writes the Rust types of the entry to `output`.
+    pub fn with_host_types(mut self, output: impl Into<PathBuf>) -> Self {
+        self.host_types = Some(output.into());
+        self
+    }
+}
+
+/// The result of [`Build::try_compile`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Compiled {
+    /// The written WGSL files, and Rust files if any, in the order of [`Build::entries`].
+    pub outputs: Vec<PathBuf>,
+    /// The root files and every file loaded by the module loader.
+    pub dependencies: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    /// Neither [`Build::out_dir`] nor `OUT_DIR` is set, i.e. not running in a build script.
+    MissingOutDir,
+    Io(PathBuf, std::io::Error),
+    /// The shaders have errors, rendered with their source and without colors.
+    Mew(Vec<String>),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingOutDir => write!(f, "`OUT_DIR` is not set"),
+            BuildError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            BuildError::Mew(errors) => write!(f, "{}", errors.join("\n")),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Build {
+    /// Like [`Build::try_compile`], but panics with the errors, which fails the build script.
+    pub fn compile(&self) -> Compiled {
+        match self.try_compile() {
+            Ok(compiled) => compiled,
+            Err(err) => panic!("failed to compile mew shaders:\n{err}"),
+        }
+    }
+
+    /// Compiles every entry and writes its WGSL to the output directory. Prints
+    /// `cargo:rerun-if-changed` for every dependency, also if compilation fails, so that
+    /// the build script reruns when a shader changes.
+    pub fn try_compile(&self) -> Result<Compiled, BuildError> {
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(BuildError::MissingOutDir)?,
+        };
+
+        let mut compiled = Compiled::default();
+        let result = self.compile_entries(&out_dir, &mut compiled);
+        for dependency in &compiled.dependencies {
+            println!("cargo:rerun-if-changed={}", dependency.display());
+        }
+        result.map(|()| compiled)
+    }
+
+    fn compile_entries(&self, out_dir: &Path, compiled: &mut Compiled) -> Result<(), BuildError> {
+        let mut api = MewApi::default();
+        let mut errors = vec![];
+        for path in &self.modules {
+            compiled.dependencies.push(path.clone());
+            let source =
+                std::fs::read_to_string(path).map_err(|err| BuildError::Io(path.clone(), err))?;
+            let module_name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().replace('-', "_"))
+                .unwrap_or_default();
+            let module = ModuleDescriptor {
+                module_name: &module_name,
+                source: Source::Text(&source),
+            };
+            if let Err(err) = api.add_module(module) {
+                errors.extend(
+                    err.into_errors()
+                        .iter()
+                        .map(|err| api.format_error_plain(err)),
+                );
+            }
+        }
+        if !errors.is_empty() {
+            return Err(BuildError::Mew(errors));
+        }
+
+        // the loader is set after all roots are added, so that roots using each other are
+        // not loaded a second time.
+        let root = self.root.clone().unwrap_or_else(|| {
+            match self.modules.first().and_then(|path| path.parent()) {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            }
+        });
+        api.loader = Some(Box::new(FileSystemLoader::new(root)));
+        let loaded = api.load_missing_modules();
+        compiled
+            .dependencies
+            .extend(api.loaded_files.iter().cloned());
+        if let Err(err) = loaded {
+            let errors = err.into_errors();
+            return Err(BuildError::Mew(
+                errors
+                    .iter()
+                    .map(|err| api.format_error_plain(err))
+                    .collect(),
+            ));
+        }
+
+        for entry in &self.entries {
+            let path = mew_api::Path::Text(entry.path.clone());
+            match api.compile_with_reflection(std::slice::from_ref(&path)) {
+                Ok((wgsl, reflection)) => {
+                    let output = out_dir.join(&entry.output);
+                    write(&output, &wgsl)?;
+                    compiled.outputs.push(output);
+                    if let Some(host_types) = &entry.host_types {
+                        let output = out_dir.join(host_types);
+                        write(&output, &rust_host_types(&reflection))?;
+                        compiled.outputs.push(output);
+                    }
+                }
+                Err(err) => {
+                    errors.extend(
+                        err.into_errors()
+                            .iter()
+                            .map(|err| api.format_error_plain(err)),
+                    );
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(BuildError::Mew(errors))
+        }
+    }
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), BuildError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| BuildError::Io(parent.to_path_buf(), err))?;
+    }
+    std::fs::write(path, contents).map_err(|err| BuildError::Io(path.to_path_buf(), err))
+}
@@ -0,0 +1,210 @@
+//! Generates Rust mirrors of the structs of a compiled module, so that buffers can be
+//! written from the host without keeping hand-written copies in sync with the shaders:
+//!
+//! ```ignore
+//! mod shaders {
+//!     include!(concat!(env!("OUT_DIR"), "/main.rs"));
+//! }
+//!
+//! let camera = shaders::main_Camera { ..bytemuck::Zeroable::zeroed() };
+//! queue.write_buffer(&buffer, 0, bytemuck::bytes_of(&camera));
+//! ```
+//!
+//! Every struct is `#[repr(C)]`, with padding fields where WGSL aligns its members, and
+//! derives `bytemuck::Pod` and `bytemuck::Zeroable`: the crate that includes the generated
+//! code depends on `bytemuck` with its `derive` feature. Structs and bindings keep their
+//! names in the compiled WGSL.
+
+use std::fmt::Write;
+
+use mew_api::Reflection;
+use mew_types::layout::{HostType, Scalar, StructLayout};
+
+/// The Rust source of the structs and bindings of `reflection`:
+/// * a struct for every struct that can be shared with the host, `f16` is represented by
+///   its bits as `u16`, vectors as arrays and matrices as arrays of columns,
+/// * for structs ending with a runtime-sized array, which only contain the members before
+///   it, `{MEMBER}_OFFSET` and `{MEMBER}_STRIDE` constants locating its elements,
+/// * `{NAME}_GROUP` and `{NAME}_BINDING` constants for every binding.
+pub fn rust_host_types(reflection: &Reflection) -> String {
+    let mut rust = String::new();
+    for binding in &reflection.bindings {
+        let name = binding.name.mangled.to_uppercase();
+        let _ = writeln!(rust, "/// `{}`", binding.name.path.join("::"));
+        if let Some(group) = binding.group {
+            let _ = writeln!(rust, "pub const {name}_GROUP: u32 = {group};");
+        }
+        if let Some(index) = binding.binding {
+            let _ = writeln!(rust, "pub const {name}_BINDING: u32 = {index};");
+        }
+        rust.push('\n');
+    }
+    for layout in &reflection.structs {
+        write_struct(&mut rust, layout);
+    }
+    rust
+}
+
+fn write_struct(rust: &mut String, layout: &StructLayout) {
+    let _ = writeln!(
+        rust,
+        "/// `{}`, {} bytes aligned to {}.",
+        layout.name, layout.layout.size, layout.layout.align
+    );
+    let _ = writeln!(rust, "#[repr(C)]");
+    let _ = writeln!(
+        rust,
+        "#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]"
+    );
+    let _ = writeln!(rust, "#[allow(non_camel_case_types)]");
+    let _ = writeln!(rust, "pub struct {} {{", layout.name);
+    let mut end = 0;
+    let mut paddings = 0;
+    let mut pad = |rust: &mut String, end: u32, offset: u32| {
+        if offset > end {
+            let _ = writeln!(rust, "    pub _pad{paddings}: [u8; {}],", offset - end);
+            paddings += 1;
+        }
+    };
+    let mut runtime_sized = None;
+    for member in &layout.members {
+        if let HostType::Array {
+            element,
+            count: None,
+        } = &member.host_type
+        {
+            runtime_sized = Some((member, element));
+            break;
+        }
+        pad(rust, end, member.offset);
+        let _ = writeln!(
+            rust,
+            "    pub {}: {},",
+            identifier(&member.name),
+            rust_type(&member.host_type)
+        );
+        end = member.offset + member.layout.size;
+    }
+    match runtime_sized {
+        // the elements are not part of the struct, they follow it
+        Some((member, _)) => pad(rust, end, round_up(rust_align(layout), member.offset)),
+        None => pad(rust, end, layout.layout.size),
+    }
+    let _ = writeln!(rust, "}}\n");
+
+    if let Some((member, element)) = runtime_sized {
+        let name = member.name.to_uppercase();
+        let _ = writeln!(rust, "impl {} {{", layout.name);
+        let _ = writeln!(
+            rust,
+            "    /// The offset of the elements of `{}`, of type `{}`.",
+            member.name,
+            element_type(element)
+        );
+        let _ = writeln!(
+            rust,
+            "    pub const {name}_OFFSET: u64 = {};",
+            member.offset
+        );
+        let _ = writeln!(
+            rust,
+            "    /// The distance between the elements of `{}`.",
+            member.name
+        );
+        let _ = writeln!(
+            rust,
+            "    pub const {name}_STRIDE: u64 = {};",
+            member.layout.stride.unwrap_or_default()
+        );
+        let _ = writeln!(rust, "}}\n");
+    }
+}
+
+fn rust_type(ty: &HostType) -> String {
+    match ty {
+        HostType::Scalar(scalar) | HostType::Atomic(scalar) => scalar_type(*scalar).to_string(),
+        HostType::Vector { size, scalar } => format!("[{}; {size}]", scalar_type(*scalar)),
+        HostType::Matrix {
+            columns,
+            rows,
+            scalar,
+        } => {
+            // columns are aligned like vectors, a `vec3` column takes the space of a `vec4`
+            let column = HostType::Vector {
+                size: if *rows == 3 { 4 } else { *rows },
+                scalar: *scalar,
+            };
+            format!("[{}; {columns}]", rust_type(&column))
+        }
+        HostType::Array { element, count } => {
+            format!("[{}; {}]", element_type(element), count.unwrap_or_default())
+        }
+        HostType::Struct(name) => name.clone(),
+    }
+}
+
+/// The type of the elements of an array, which are as far apart as they are aligned: a
+/// `vec3` takes the space of a `vec4`.
+fn element_type(element: &HostType) -> String {
+    match element {
+        HostType::Vector { size: 3, scalar } => rust_type(&HostType::Vector {
+            size: 4,
+            scalar: *scalar,
+        }),
+        element => rust_type(element),
+    }
+}
+
+fn scalar_type(scalar: Scalar) -> &'static str {
+    match scalar {
+        Scalar::I32 => "i32",
+        Scalar::U32 => "u32",
+        Scalar::F32 => "f32",
+        // the bits of the `f16`
+        Scalar::F16 => "u16",
+    }
+}
+
+/// The alignment of the generated struct, the largest scalar size of its members.
+fn rust_align(layout: &StructLayout) -> u32 {
+    fn align(ty: &HostType) -> u32 {
+        match ty {
+            HostType::Scalar(scalar)
+            | HostType::Atomic(scalar)
+            | HostType::Vector { scalar, .. }
+            | HostType::Matrix { scalar, .. } => scalar.size(),
+            HostType::Array { element, .. } => align(element),
+            // the largest scalar size
+            HostType::Struct(_) => 4,
+        }
+    }
+    layout
+        .members
+        .iter()
+        .map(|member| align(&member.host_type))
+        .max()
+        .unwrap_or(1)
+}
+
+/// A Rust identifier for the WGSL identifier `name`.
+fn identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+        "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+        "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+        "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+        "where", "while", "yield",
+    ];
+    match name {
+        // cannot be raw identifiers
+        "crate" | "self" | "Self" | "super" => format!("{name}_"),
+        _ if KEYWORDS.contains(&name) => format!("r#{name}"),
+        _ => name.to_string(),
+    }
+}
+
+/// `value` rounded up to a multiple of `align`, or `value` itself if `align` is 0 or the
+/// result does not fit in a `u32`, which the valid layouts of a [`Reflection`] never need.
+fn round_up(align: u32, value: u32) -> u32 {
+    value.checked_next_multiple_of(align).unwrap_or(value)
+}
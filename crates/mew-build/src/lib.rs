@@ -0,0 +1,5 @@
+pub mod build;
+pub mod host;
+
+pub use build::*;
+pub use host::*;
@@ -2,12 +2,16 @@ use mew_parse::{
     span::Spanned,
     syntax::{self, Module, ModuleDirective, TranslationUnit},
 };
-use mew_types::CompilerPass;
+use mew_types::{CompilerPass, ErrorCollector};
 
 #[derive(Debug, Default)]
 pub struct Bundler<'a> {
     pub sources: Vec<&'a str>,
     pub enclosing_module_name: Option<String>,
+    /// Offset of the first source. Each following source starts one byte past the end of
+    /// the previous one, the same layout as files added in order to a
+    /// [`mew_parse::span::SourceMap`].
+    pub offset: usize,
 }
 
 impl<'a> CompilerPass for Bundler<'a> {
@@ -17,17 +21,20 @@ impl<'a> CompilerPass for Bundler<'a> {
     ) -> Result<(), mew_types::CompilerPassError> {
         let mut result: TranslationUnit = TranslationUnit::default();
 
-        let mut ws: String = String::new();
+        let mut offset = self.offset;
+
+        let mut errors = ErrorCollector::default();
 
         for file in self.sources.iter() {
-            let file_len = file.len();
-            let mut file_with_starting_ws = ws.clone();
-            file_with_starting_ws.push_str(file);
-            ws.extend((0..file_len).map(|_| ' '));
-            let mut local_translation_unit = mew_parse::Parser::parse_str(&file_with_starting_ws)
-                .map_err(|err| {
-                    mew_types::CompilerPassError::ParseError(format!("{}", err), err.span())
-                })?;
+            let (mut local_translation_unit, parse_errors) =
+                mew_parse::Parser::parse_str_recovering_at(file, offset);
+            for err in parse_errors {
+                errors.push(mew_types::CompilerPassError::ParseError(
+                    format!("{}", err),
+                    err.span(),
+                ));
+            }
+            offset += file.len() + 1;
             result
                 .global_declarations
                 .append(&mut local_translation_unit.global_declarations);
@@ -36,12 +43,15 @@ impl<'a> CompilerPass for Bundler<'a> {
                 .append(&mut local_translation_unit.global_directives);
         }
 
+        // report the syntax errors of every source at once
+        errors.finish()?;
+
         if let Some(module_name) = &self.enclosing_module_name {
             let mut module = Module {
                 name: Spanned::new(module_name.to_owned(), 0..0),
                 ..Module::default()
             };
-            let mut module_span = 0..0;
+            let mut module_span = self.offset..self.offset;
             for declaration in result.global_declarations {
                 let span = declaration.span();
                 module_span.start = usize::min(span.start, module_span.start);
@@ -0,0 +1,230 @@
+//! Removes the declarations of a specialized translation unit that no entry point uses.
+//!
+//! The `Specializer` only instantiates what the entry points use, but it keeps every
+//! `const_assert` along with everything the assertion references. This pass keeps the
+//! declarations that an entry point references, directly or transitively, and the functions
+//! and declarations marked `@keep`, e.g. an `override` that the host sets but that no
+//! shader reads. A `const_assert` does not keep anything alive: it is removed when it
+//! references a removed declaration.
+
+use std::collections::{HashMap, HashSet};
+
+use mew_parse::{
+    span::Spanned,
+    syntax::{
+        visit::{self, Visit},
+        visit_mut::VisitMut,
+        Attribute, ConstAssert, Declaration, Function, GlobalDeclaration, Module,
+        ModuleMemberDeclaration, PathPart, Struct, TranslationUnit,
+    },
+};
+use mew_types::{CompilerPass, CompilerPassError, KEEP_ATTRIBUTE};
+
+/// Eliminates dead code after the `Dealiaser` pass, once every path is absolute and free
+/// of aliases and template arguments. Strips the `@keep` attributes, which are not WGSL.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct DeadCodeEliminator {
+    /// The dealiased paths of the entry points.
+    pub entrypoints: Vec<Vec<PathPart>>,
+}
+
+type SymbolPath = Vec<String>;
+
+/// A named declaration: the paths it references, and whether it is marked `@keep`.
+struct Symbol {
+    references: Vec<SymbolPath>,
+    keep: bool,
+}
+
+enum Item<'a> {
+    Declaration(&'a Declaration),
+    Function(&'a Function),
+    Struct(&'a Struct),
+    ConstAssert(&'a ConstAssert),
+    Module(&'a Module),
+    Void,
+}
+
+impl<'a> From<&'a GlobalDeclaration> for Item<'a> {
+    fn from(declaration: &'a GlobalDeclaration) -> Self {
+        match declaration {
+            GlobalDeclaration::Declaration(declaration) => Item::Declaration(declaration),
+            GlobalDeclaration::Function(function) => Item::Function(function),
+            GlobalDeclaration::Struct(s) => Item::Struct(s),
+            GlobalDeclaration::ConstAssert(assert) => Item::ConstAssert(assert),
+            GlobalDeclaration::Module(module) => Item::Module(module),
+            // aliases are gone once dealiased
+            GlobalDeclaration::Alias(_) | GlobalDeclaration::Void => Item::Void,
+        }
+    }
+}
+
+impl<'a> From<&'a ModuleMemberDeclaration> for Item<'a> {
+    fn from(declaration: &'a ModuleMemberDeclaration) -> Self {
+        match declaration {
+            ModuleMemberDeclaration::Declaration(declaration) => Item::Declaration(declaration),
+            ModuleMemberDeclaration::Function(function) => Item::Function(function),
+            ModuleMemberDeclaration::Struct(s) => Item::Struct(s),
+            ModuleMemberDeclaration::ConstAssert(assert) => Item::ConstAssert(assert),
+            ModuleMemberDeclaration::Module(module) => Item::Module(module),
+            ModuleMemberDeclaration::Alias(_) | ModuleMemberDeclaration::Void => Item::Void,
+        }
+    }
+}
+
+/// The paths referenced by a node, including the paths of local variables, which match
+/// no declaration.
+#[derive(Default)]
+struct References(Vec<SymbolPath>);
+
+impl Visit for References {
+    fn visit_path(&mut self, path: &Spanned<Vec<PathPart>>) {
+        self.0.push(symbol_path(path));
+        visit::walk_path(self, path);
+    }
+}
+
+fn symbol_path(path: &[PathPart]) -> SymbolPath {
+    path.iter().map(|part| part.name.value.clone()).collect()
+}
+
+fn is_kept(attributes: &[Spanned<Attribute>]) -> bool {
+    attributes
+        .iter()
+        .any(|attribute| attribute.name.value == KEEP_ATTRIBUTE)
+}
+
+#[derive(Default)]
+struct Reachability {
+    symbols: HashMap<SymbolPath, Symbol>,
+    live: HashSet<SymbolPath>,
+}
+
+impl Reachability {
+    fn index(&mut self, mut path: SymbolPath, item: Item) {
+        let mut references = References::default();
+        let (name, keep) = match item {
+            Item::Declaration(declaration) => {
+                references.visit_declaration(declaration);
+                (&declaration.name, is_kept(&declaration.attributes))
+            }
+            Item::Function(function) => {
+                references.visit_function(function);
+                (&function.name, is_kept(&function.attributes))
+            }
+            Item::Struct(s) => {
+                references.visit_struct(s);
+                (&s.name, false)
+            }
+            Item::Module(module) => {
+                path.push(module.name.value.clone());
+                for member in &module.members {
+                    self.index(path.clone(), (&member.value).into());
+                }
+                return;
+            }
+            Item::ConstAssert(_) | Item::Void => return,
+        };
+        path.push(name.value.clone());
+        let symbol = Symbol {
+            references: references.0,
+            keep,
+        };
+        self.symbols.insert(path, symbol);
+    }
+
+    fn propagate(&mut self, roots: impl IntoIterator<Item = SymbolPath>) {
+        let mut queue: Vec<SymbolPath> = roots.into_iter().collect();
+        while let Some(path) = queue.pop() {
+            if self.live.contains(&path) {
+                continue;
+            }
+            let Some(symbol) = self.symbols.get(&path) else {
+                continue;
+            };
+            queue.extend(symbol.references.iter().cloned());
+            self.live.insert(path);
+        }
+    }
+
+    fn is_live(&self, path: &SymbolPath, item: Item) -> bool {
+        let name = match item {
+            Item::Declaration(declaration) => &declaration.name,
+            Item::Function(function) => &function.name,
+            Item::Struct(s) => &s.name,
+            Item::ConstAssert(assert) => {
+                let mut references = References::default();
+                references.visit_const_assert(assert);
+                return references
+                    .0
+                    .iter()
+                    .all(|path| self.live.contains(path) || !self.symbols.contains_key(path));
+            }
+            // pruned member by member
+            Item::Module(_) | Item::Void => return true,
+        };
+        let mut path = path.clone();
+        path.push(name.value.clone());
+        self.live.contains(&path)
+    }
+
+    fn prune_module(&self, mut path: SymbolPath, module: &mut Module) {
+        path.push(module.name.value.clone());
+        module
+            .members
+            .retain(|member| self.is_live(&path, (&member.value).into()));
+        for member in module.members.iter_mut() {
+            if let ModuleMemberDeclaration::Module(module) = &mut member.value {
+                self.prune_module(path.clone(), module);
+            }
+        }
+    }
+}
+
+/// Removes the `@keep` attributes of the remaining declarations.
+struct StripKeep;
+
+impl VisitMut for StripKeep {
+    fn visit_declaration(&mut self, declaration: &mut Declaration) {
+        declaration
+            .attributes
+            .retain(|attribute| attribute.name.value != KEEP_ATTRIBUTE);
+    }
+
+    fn visit_function(&mut self, function: &mut Function) {
+        function
+            .attributes
+            .retain(|attribute| attribute.name.value != KEEP_ATTRIBUTE);
+    }
+}
+
+impl CompilerPass for DeadCodeEliminator {
+    fn apply_mut(
+        &mut self,
+        translation_unit: &mut TranslationUnit,
+    ) -> Result<(), CompilerPassError> {
+        let mut reachability = Reachability::default();
+        for declaration in &translation_unit.global_declarations {
+            reachability.index(vec![], (&declaration.value).into());
+        }
+        let kept = reachability
+            .symbols
+            .iter()
+            .filter(|(_, symbol)| symbol.keep)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        let entrypoints = self.entrypoints.iter().map(|path| symbol_path(path));
+        reachability.propagate(entrypoints.chain(kept));
+
+        translation_unit
+            .global_declarations
+            .retain(|declaration| reachability.is_live(&vec![], (&declaration.value).into()));
+        for declaration in translation_unit.global_declarations.iter_mut() {
+            if let GlobalDeclaration::Module(module) = &mut declaration.value {
+                reachability.prune_module(vec![], module);
+            }
+        }
+        StripKeep.visit_translation_unit(translation_unit);
+        Ok(())
+    }
+}
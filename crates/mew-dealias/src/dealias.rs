@@ -14,7 +14,13 @@ use mew_types::{builtins, mangling::maybe_mangle_template_args_if_needed, Compil
 struct AliasPath(im::Vector<PathPart>);
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
-pub struct Dealiaser;
+pub struct Dealiaser {
+    /// The paths of the entry points, which point into the translation unit from outside of
+    /// it. They are dealiased along with it, for the passes that follow.
+    ///
+    /// Breaking: `Dealiaser` was a unit struct, `Dealiaser::default()` has no entry points.
+    pub entrypoints: Vec<Vec<PathPart>>,
+}
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct ModulePath(im::Vector<PathPart>);
@@ -540,6 +546,11 @@ impl CompilerPass for Dealiaser {
         let mut tree = AliasTree::default();
         Self::populate_aliases_from_translation_unit(translation_unit, &mut tree)?;
         Self::replace_alias_usages_from_translation_unit(translation_unit, &tree)?;
+        for path in self.entrypoints.iter_mut() {
+            let mut spanned = Spanned::new(std::mem::take(path), 0..0);
+            Self::replace_path_with_alias(&mut spanned, &tree)?;
+            *path = spanned.value;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,743 @@
+//! Formats a translation unit with a consistent style. Unlike the `Display` implementation
+//! of the syntax tree, the formatter keeps comments and blank lines, wraps long lines and
+//! is idempotent: formatting its own output does not change it.
+
+use itertools::Itertools;
+use mew_parse::{
+    error::SpannedError,
+    span::{Span, Spanned},
+    syntax::*,
+    trivia::{Comment, TriviaMap},
+    Parser,
+};
+
+/// Where the attributes of functions, declarations and modules go.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AttributePlacement {
+    /// `@vertex\nfn main() {}`
+    #[default]
+    OwnLine,
+    /// `@vertex fn main() {}`
+    SameLine,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level.
+    pub indent_width: usize,
+    /// Lines longer than this are wrapped where possible: function parameters, call
+    /// arguments, `use` collections and struct member attributes.
+    pub max_width: usize,
+    pub attributes: AttributePlacement,
+    /// Sorts consecutive `use` directives, and the items of `use` collections.
+    pub sort_uses: bool,
+    /// Merges consecutive `use` directives with the same path into one collection, e.g.
+    /// `use a::b; use a::c;` becomes `use a::{b, c}`.
+    pub group_uses: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            max_width: 100,
+            attributes: AttributePlacement::default(),
+            sort_uses: false,
+            group_uses: false,
+        }
+    }
+}
+
+/// Parses and formats `source`, keeping its comments.
+pub fn format_str<'s>(
+    source: &'s str,
+    options: &FormatOptions,
+) -> Result<String, SpannedError<'s>> {
+    let (translation_unit, trivia) = Parser::parse_str_lossless(source)?;
+    Ok(format_translation_unit(
+        &translation_unit,
+        &trivia,
+        source,
+        options,
+    ))
+}
+
+/// Formats a translation unit parsed from `source` with [`Parser::parse_str_lossless`].
+/// `source` is used to keep blank lines between statements, directives and members.
+pub fn format_translation_unit(
+    translation_unit: &TranslationUnit,
+    trivia: &TriviaMap,
+    source: &str,
+    options: &FormatOptions,
+) -> String {
+    let formatter = Formatter {
+        options,
+        trivia,
+        source,
+    };
+    formatter.translation_unit(translation_unit)
+}
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+    trivia: &'a TriviaMap,
+    source: &'a str,
+}
+
+/// A `use` directive with the comments of the directives it was merged from.
+struct UseEntry<'a> {
+    usage: Use,
+    leading: Vec<&'a Comment>,
+    trailing: Vec<&'a Comment>,
+}
+
+impl<'a> Formatter<'a> {
+    fn indent(&self, text: &str) -> String {
+        let indent = " ".repeat(self.options.indent_width);
+        text.lines()
+            .map(|line| {
+                if line.is_empty() {
+                    line.to_string()
+                } else {
+                    format!("{indent}{line}")
+                }
+            })
+            .join("\n")
+    }
+
+    fn fits(&self, depth: usize, text: &str) -> bool {
+        let width = depth * self.options.indent_width;
+        text.lines()
+            .all(|line| width + line.len() <= self.options.max_width)
+    }
+
+    /// The column after `text`, which starts at `col` on a line at `depth`.
+    fn end_col(&self, text: &str, depth: usize, col: usize) -> usize {
+        match text.rsplit_once('\n') {
+            Some((_, last)) => depth * self.options.indent_width + last.len(),
+            None => col + text.len(),
+        }
+    }
+
+    /// Wraps `body` with the comments attached to `span`. `suffix` goes before trailing
+    /// comments.
+    fn commented(&self, span: &Span, body: &str, suffix: &str) -> String {
+        let mut result = String::new();
+        for comment in self.trivia.leading(span) {
+            result.push_str(&comment.text);
+            result.push('\n');
+        }
+        result.push_str(body);
+        result.push_str(suffix);
+        for comment in self.trivia.trailing(span) {
+            result.push(if comment.own_line { '\n' } else { ' ' });
+            result.push_str(&comment.text);
+        }
+        result
+    }
+
+    /// Whether the source has a blank line between the node at `previous` and the one at
+    /// `next`, ignoring comments.
+    fn blank_line_between(&self, previous: &Span, next: &Span) -> bool {
+        let start = self
+            .trivia
+            .trailing(previous)
+            .iter()
+            .map(|c| c.span.end)
+            .fold(previous.end, usize::max);
+        let end = self
+            .trivia
+            .leading(next)
+            .iter()
+            .map(|c| c.span.start)
+            .fold(next.start, usize::min);
+        start < end
+            && self
+                .source
+                .get(start..end)
+                .is_some_and(|gap| gap.matches('\n').count() >= 2)
+    }
+
+    fn attributes(&self, attrs: &[Spanned<Attribute>]) -> String {
+        attrs.iter().format(" ").to_string()
+    }
+
+    /// Attributes of a function, declaration or module, followed by a separator.
+    fn outer_attributes(&self, attrs: &[Spanned<Attribute>]) -> String {
+        if attrs.is_empty() {
+            return String::new();
+        }
+        let sep = match self.options.attributes {
+            AttributePlacement::OwnLine => "\n",
+            AttributePlacement::SameLine => " ",
+        };
+        format!("{}{sep}", self.attributes(attrs))
+    }
+
+    /// Attributes of a statement or parameter, followed by a space.
+    fn inline_attributes(&self, attrs: &[Spanned<Attribute>]) -> String {
+        if attrs.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", self.attributes(attrs))
+        }
+    }
+
+    fn translation_unit(&self, translation_unit: &TranslationUnit) -> String {
+        let directives = self.directives(
+            &translation_unit.global_directives,
+            0,
+            |directive| match directive {
+                GlobalDirective::Use(usage) => Some(usage),
+                _ => None,
+            },
+            |directive| directive.to_string(),
+        );
+        let declarations = translation_unit
+            .global_declarations
+            .iter()
+            .map(|decl| {
+                let body = match &decl.value {
+                    GlobalDeclaration::Void => ";".to_string(),
+                    GlobalDeclaration::Declaration(d) => self.declaration(d, 0),
+                    GlobalDeclaration::Alias(a) => a.to_string(),
+                    GlobalDeclaration::Struct(s) => self.structure(s, 0),
+                    GlobalDeclaration::Function(f) => self.function(f, 0),
+                    GlobalDeclaration::ConstAssert(c) => c.to_string(),
+                    GlobalDeclaration::Module(m) => self.module(m, 0),
+                };
+                self.commented(&decl.span, &body, "")
+            })
+            .join("\n\n");
+
+        let mut result = directives.join("\n");
+        if !result.is_empty() && !declarations.is_empty() {
+            result.push_str("\n\n");
+        }
+        result.push_str(&declarations);
+        for comment in self.trivia.dangling.iter() {
+            if !result.is_empty() {
+                result.push('\n');
+            }
+            result.push_str(&comment.text);
+        }
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Formats a list of directives, sorting and grouping `use` directives according to the
+    /// options. Returns one entry per line, blank lines are empty entries.
+    fn directives<D>(
+        &self,
+        directives: &'a [Spanned<D>],
+        depth: usize,
+        as_use: impl Fn(&D) -> Option<&Use>,
+        display: impl Fn(&D) -> String,
+    ) -> Vec<String> {
+        let mut lines = vec![];
+        let mut uses: Vec<UseEntry> = vec![];
+        let mut previous: Option<&Span> = None;
+        for directive in directives {
+            let blank_line = previous.is_some_and(|p| self.blank_line_between(p, &directive.span));
+            if blank_line || as_use(&directive.value).is_none() {
+                lines.extend(self.use_entries(std::mem::take(&mut uses), depth));
+            }
+            if blank_line {
+                lines.push(String::new());
+            }
+            match as_use(&directive.value) {
+                Some(usage) => uses.push(UseEntry {
+                    usage: usage.clone(),
+                    leading: self.trivia.leading(&directive.span).iter().collect(),
+                    trailing: self.trivia.trailing(&directive.span).iter().collect(),
+                }),
+                None => {
+                    let body = display(&directive.value);
+                    lines.push(self.commented(&directive.span, body.trim_end(), ""));
+                }
+            }
+            previous = Some(&directive.span);
+        }
+        lines.extend(self.use_entries(uses, depth));
+        lines
+    }
+
+    fn use_entries(&self, entries: Vec<UseEntry<'a>>, depth: usize) -> Vec<String> {
+        let mut entries = if self.options.group_uses {
+            group_uses(entries)
+        } else {
+            entries
+        };
+        if self.options.sort_uses {
+            for entry in entries.iter_mut() {
+                sort_use(&mut entry.usage);
+            }
+            entries.sort_by_cached_key(|entry| use_tree(&entry.usage));
+        }
+        entries
+            .into_iter()
+            .map(|entry| {
+                let mut result = String::new();
+                for comment in entry.leading {
+                    result.push_str(&comment.text);
+                    result.push('\n');
+                }
+                result.push_str(&self.use_directive(&entry.usage, depth));
+                for comment in entry.trailing {
+                    result.push(if comment.own_line { '\n' } else { ' ' });
+                    result.push_str(&comment.text);
+                }
+                result
+            })
+            .collect()
+    }
+
+    fn use_directive(&self, usage: &Use, depth: usize) -> String {
+        let attrs = self.inline_attributes(&usage.attributes);
+        let flat = format!("{attrs}use {}", use_tree(usage));
+        match &usage.content.value {
+            UseContent::Item(_) => format!("{flat};"),
+            UseContent::Collection(items) if !self.fits(depth, &flat) => {
+                let path = use_path(usage);
+                let items = items.iter().map(|item| format!("{},", use_tree(item)));
+                format!(
+                    "{attrs}use {path}{{\n{}\n}}",
+                    self.indent(&items.format("\n").to_string())
+                )
+            }
+            UseContent::Collection(_) => flat,
+        }
+    }
+
+    fn template_parameters(&self, params: &[Spanned<FormalTemplateParameter>]) -> String {
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", params.iter().format(", "))
+        }
+    }
+
+    fn template_args(&self, args: &Option<Vec<Spanned<TemplateArg>>>) -> String {
+        match args {
+            Some(args) if !args.is_empty() => format!("<{}>", args.iter().format(", ")),
+            _ => String::new(),
+        }
+    }
+
+    fn declaration(&self, decl: &Declaration, depth: usize) -> String {
+        let attrs = self.outer_attributes(&decl.attributes);
+        let head = format!(
+            "{}{} {}{}",
+            decl.kind,
+            self.template_args(&decl.template_args),
+            decl.name,
+            self.template_parameters(&decl.template_parameters)
+        );
+        let typ = decl
+            .typ
+            .as_ref()
+            .map(|typ| format!(": {typ}"))
+            .unwrap_or_default();
+        let start = format!("{attrs}{head}{typ}");
+        let init = decl
+            .initializer
+            .as_ref()
+            .map(|init| {
+                let col = self.end_col(&start, depth, depth * self.options.indent_width) + 3;
+                format!(" = {}", self.expression(init, depth, col))
+            })
+            .unwrap_or_default();
+        format!("{start}{init};")
+    }
+
+    fn structure(&self, strct: &Struct, depth: usize) -> String {
+        let attrs = self.outer_attributes(&strct.attributes);
+        let head = format!(
+            "{attrs}struct {}{}",
+            strct.name,
+            self.template_parameters(&strct.template_parameters)
+        );
+        if strct.members.is_empty() {
+            return format!("{head} {{}}");
+        }
+        let mut lines = vec![];
+        for (i, member) in strct.members.iter().enumerate() {
+            if i > 0 && self.blank_line_between(&strct.members[i - 1].span, &member.span) {
+                lines.push(String::new());
+            }
+            let attrs = self.inline_attributes(&member.attributes);
+            let mut body = format!("{attrs}{}: {}", member.name, member.typ);
+            // long attribute lists go on their own line.
+            if !attrs.is_empty() && !self.fits(depth + 1, &format!("{body},")) {
+                body = format!(
+                    "{}\n{}: {}",
+                    self.attributes(&member.attributes),
+                    member.name,
+                    member.typ
+                );
+            }
+            lines.push(self.commented(&member.span, &body, ","));
+        }
+        format!("{head} {{\n{}\n}}", self.indent(&lines.join("\n")))
+    }
+
+    fn function(&self, function: &Function, depth: usize) -> String {
+        let attrs = self.outer_attributes(&function.attributes);
+        let name = format!(
+            "fn {}{}",
+            function.name,
+            self.template_parameters(&function.template_parameters)
+        );
+        let params = function
+            .parameters
+            .iter()
+            .map(|param| {
+                let attrs = self.inline_attributes(&param.attributes);
+                format!("{attrs}{}: {}", param.name, param.typ)
+            })
+            .collect_vec();
+        let ret = function
+            .return_type
+            .as_ref()
+            .map(|typ| {
+                let attrs = self.inline_attributes(&function.return_attributes);
+                format!(" -> {attrs}{typ}")
+            })
+            .unwrap_or_default();
+        let flat = format!("{attrs}{name}({}){ret} {{", params.iter().format(", "));
+        let head = if params.is_empty() || self.fits(depth, &flat) {
+            format!("{attrs}{name}({}){ret}", params.iter().format(", "))
+        } else {
+            let params = params.iter().map(|param| format!("{param},")).join("\n");
+            format!("{attrs}{name}(\n{}\n){ret}", self.indent(&params))
+        };
+        format!("{head} {}", self.compound(&function.body, depth))
+    }
+
+    fn module(&self, module: &Module, depth: usize) -> String {
+        let attrs = self.outer_attributes(&module.attributes);
+        let head = format!(
+            "{attrs}mod {}{}",
+            module.name,
+            self.template_parameters(&module.template_parameters)
+        );
+        let directives = self.directives(
+            &module.directives,
+            depth + 1,
+            |directive| match directive {
+                ModuleDirective::Use(usage) => Some(usage),
+                ModuleDirective::Extend(_) => None,
+            },
+            |directive| directive.to_string(),
+        );
+        let members = module
+            .members
+            .iter()
+            .map(|member| {
+                let body = match &member.value {
+                    ModuleMemberDeclaration::Void => ";".to_string(),
+                    ModuleMemberDeclaration::Declaration(d) => self.declaration(d, depth + 1),
+                    ModuleMemberDeclaration::Alias(a) => a.to_string(),
+                    ModuleMemberDeclaration::Struct(s) => self.structure(s, depth + 1),
+                    ModuleMemberDeclaration::Function(f) => self.function(f, depth + 1),
+                    ModuleMemberDeclaration::ConstAssert(c) => c.to_string(),
+                    ModuleMemberDeclaration::Module(m) => self.module(m, depth + 1),
+                };
+                self.commented(&member.span, &body, "")
+            })
+            .join("\n\n");
+
+        let mut body = directives.join("\n");
+        if !body.is_empty() && !members.is_empty() {
+            body.push_str("\n\n");
+        }
+        body.push_str(&members);
+        if body.is_empty() {
+            format!("{head} {{}}")
+        } else {
+            format!("{head} {{\n{}\n}}", self.indent(&body))
+        }
+    }
+
+    fn compound(&self, compound: &CompoundStatement, depth: usize) -> String {
+        let attrs = self.inline_attributes(&compound.attributes);
+        let lines = self.block(&compound.directives, &compound.statements, depth + 1);
+        if lines.is_empty() {
+            format!("{attrs}{{}}")
+        } else {
+            format!("{attrs}{{\n{}\n}}", self.indent(&lines.join("\n")))
+        }
+    }
+
+    /// The lines of the directives and statements of a block.
+    fn block(
+        &self,
+        directives: &'a [Spanned<CompoundDirective>],
+        statements: &[Spanned<Statement>],
+        depth: usize,
+    ) -> Vec<String> {
+        let mut lines = self.directives(
+            directives,
+            depth,
+            |directive| match directive {
+                CompoundDirective::Use(usage) => Some(usage),
+            },
+            |directive| directive.to_string(),
+        );
+        let mut previous = directives.last().map(|directive| &directive.span);
+        // declarations contain the statements of their scope, they are printed after them.
+        let mut queue = statements.iter().collect::<std::collections::VecDeque<_>>();
+        while let Some(stmt) = queue.pop_front() {
+            if previous.is_some_and(|p| self.blank_line_between(p, &stmt.span)) {
+                lines.push(String::new());
+            }
+            if let Statement::Declaration(decl) = &stmt.value {
+                for (i, nested) in decl.statements.iter().enumerate() {
+                    queue.insert(i, nested);
+                }
+            }
+            lines.push(self.commented(&stmt.span, &self.statement(stmt, depth), ""));
+            previous = Some(&stmt.span);
+        }
+        lines
+    }
+
+    fn statement(&self, stmt: &Statement, depth: usize) -> String {
+        let col = depth * self.options.indent_width;
+        match stmt {
+            Statement::Void => ";".to_string(),
+            Statement::Compound(compound) => self.compound(compound, depth),
+            Statement::Assignment(assignment) => {
+                let start = format!("{} {} ", assignment.lhs, assignment.operator);
+                let rhs = self.expression(&assignment.rhs, depth, col + start.len());
+                format!("{start}{rhs};")
+            }
+            Statement::Increment(expr) => format!("{expr}++;"),
+            Statement::Decrement(expr) => format!("{expr}--;"),
+            Statement::If(iff) => {
+                let attrs = self.inline_attributes(&iff.attributes);
+                let (cond, body) = &iff.if_clause;
+                let mut result = format!("{attrs}if {cond} {}", self.compound(body, depth));
+                for (cond, body) in iff.else_if_clauses.iter() {
+                    result.push_str(&format!(" else if {cond} {}", self.compound(body, depth)));
+                }
+                if let Some(body) = &iff.else_clause {
+                    result.push_str(&format!(" else {}", self.compound(body, depth)));
+                }
+                result
+            }
+            Statement::Switch(switch) => {
+                let attrs = self.inline_attributes(&switch.attributes);
+                let body_attrs = self.inline_attributes(&switch.body_attributes);
+                let clauses = switch
+                    .clauses
+                    .iter()
+                    .map(|clause| {
+                        let body = self.compound(&clause.body, depth + 1);
+                        match clause.case_selectors.as_slice() {
+                            [selector] if selector.value == CaseSelector::Default => {
+                                format!("default {body}")
+                            }
+                            selectors => format!("case {} {body}", selectors.iter().format(", ")),
+                        }
+                    })
+                    .join("\n");
+                format!(
+                    "{attrs}switch {} {body_attrs}{{\n{}\n}}",
+                    switch.expression,
+                    self.indent(&clauses)
+                )
+            }
+            Statement::Loop(lop) => {
+                let attrs = self.inline_attributes(&lop.attributes);
+                let body_attrs = self.inline_attributes(&lop.body.attributes);
+                let mut lines = self.block(&lop.body.directives, &lop.body.statements, depth + 1);
+                if let Some(continuing) = &lop.continuing {
+                    let mut inner = self.block(
+                        &continuing.body.directives,
+                        &continuing.body.statements,
+                        depth + 2,
+                    );
+                    if let Some(break_if) = &continuing.break_if {
+                        inner.push(format!("break if {break_if};"));
+                    }
+                    let inner_attrs = self.inline_attributes(&continuing.body.attributes);
+                    lines.push(if inner.is_empty() {
+                        format!("continuing {inner_attrs}{{}}")
+                    } else {
+                        format!(
+                            "continuing {inner_attrs}{{\n{}\n}}",
+                            self.indent(&inner.join("\n"))
+                        )
+                    });
+                }
+                if lines.is_empty() {
+                    format!("{attrs}loop {body_attrs}{{}}")
+                } else {
+                    format!(
+                        "{attrs}loop {body_attrs}{{\n{}\n}}",
+                        self.indent(&lines.join("\n"))
+                    )
+                }
+            }
+            Statement::For(fr) => {
+                let attrs = self.inline_attributes(&fr.attributes);
+                let clause = |stmt: &Option<Box<Spanned<Statement>>>| {
+                    stmt.as_ref()
+                        .map(|stmt| stmt.to_string().trim_end_matches(';').to_string())
+                        .unwrap_or_default()
+                };
+                let cond = fr
+                    .condition
+                    .as_ref()
+                    .map(|cond| cond.to_string())
+                    .unwrap_or_default();
+                format!(
+                    "{attrs}for ({}; {cond}; {}) {}",
+                    clause(&fr.initializer),
+                    clause(&fr.update),
+                    self.compound(&fr.body, depth)
+                )
+            }
+            Statement::While(whl) => {
+                let attrs = self.inline_attributes(&whl.attributes);
+                format!(
+                    "{attrs}while {} {}",
+                    whl.condition,
+                    self.compound(&whl.body, depth)
+                )
+            }
+            Statement::Break => "break;".to_string(),
+            Statement::Continue => "continue;".to_string(),
+            Statement::Return(expr) => match expr {
+                Some(expr) => format!("return {};", self.expression(expr, depth, col + 7)),
+                None => "return;".to_string(),
+            },
+            Statement::Discard => "discard;".to_string(),
+            Statement::FunctionCall(call) => format!("{};", self.call(call, depth, col)),
+            Statement::ConstAssert(const_assert) => const_assert.to_string(),
+            Statement::Declaration(decl) => self.declaration(&decl.declaration, depth),
+        }
+    }
+
+    /// Formats an expression that starts at column `col` of a line at `depth`. Function
+    /// calls that do not fit are broken with one argument per line.
+    fn expression(&self, expr: &Expression, depth: usize, col: usize) -> String {
+        let flat = expr.to_string();
+        if col + flat.len() <= self.options.max_width {
+            return flat;
+        }
+        match expr {
+            Expression::Parenthesized(inner) => {
+                format!("({})", self.expression(inner, depth, col + 1))
+            }
+            Expression::NamedComponent(named) => {
+                let base = self.expression(&named.base, depth, col);
+                format!("{base}.{}", named.component)
+            }
+            Expression::Indexing(indexing) => {
+                let base = self.expression(&indexing.base, depth, col);
+                format!("{base}[{}]", indexing.index)
+            }
+            Expression::Unary(unary) => {
+                let operator = unary.operator.to_string();
+                let operand = self.expression(&unary.operand, depth, col + operator.len());
+                format!("{operator}{operand}")
+            }
+            Expression::Binary(binary) => {
+                let left = self.expression(&binary.left, depth, col);
+                let operator = format!(" {} ", binary.operator);
+                let col = self.end_col(&left, depth, col) + operator.len();
+                let right = self.expression(&binary.right, depth, col);
+                format!("{left}{operator}{right}")
+            }
+            Expression::FunctionCall(call) => self.call(call, depth, col),
+            _ => flat,
+        }
+    }
+
+    fn call(&self, call: &FunctionCallExpression, depth: usize, col: usize) -> String {
+        let flat = call.to_string();
+        if call.arguments.is_empty() || col + flat.len() <= self.options.max_width {
+            return flat;
+        }
+        let indent = (depth + 1) * self.options.indent_width;
+        let args = call
+            .arguments
+            .iter()
+            .map(|arg| format!("{},", self.expression(arg, depth + 1, indent)))
+            .join("\n");
+        format!(
+            "{}(\n{}\n)",
+            call.path.iter().format("::"),
+            self.indent(&args)
+        )
+    }
+}
+
+/// The path of a use, with the trailing `::`.
+fn use_path(usage: &Use) -> String {
+    usage.path.iter().map(|part| format!("{part}::")).collect()
+}
+
+fn use_tree(usage: &Use) -> String {
+    let path = use_path(usage);
+    match &usage.content.value {
+        UseContent::Item(_) => format!("{path}{}", usage.content.value),
+        UseContent::Collection(items) => {
+            format!("{path}{{{}}}", items.iter().map(|x| use_tree(x)).join(", "))
+        }
+    }
+}
+
+fn sort_use(usage: &mut Use) {
+    if let UseContent::Collection(items) = &mut usage.content.value {
+        for item in items.iter_mut() {
+            sort_use(item);
+        }
+        items.sort_by_cached_key(|item| use_tree(item));
+    }
+}
+
+/// Merges the entries with the same path into a collection.
+fn group_uses(entries: Vec<UseEntry>) -> Vec<UseEntry> {
+    let mut grouped: Vec<UseEntry> = vec![];
+    for entry in entries {
+        let path = use_path(&entry.usage);
+        let group = grouped.iter_mut().find(|group| {
+            !path.is_empty()
+                && group.usage.attributes.is_empty()
+                && entry.usage.attributes.is_empty()
+                && use_path(&group.usage) == path
+        });
+        match group {
+            Some(group) => {
+                let mut items = collection_items(&mut group.usage);
+                items.extend(collection_items(&mut entry.usage.clone()));
+                group.usage.content.value = UseContent::Collection(items);
+                group.leading.extend(entry.leading);
+                group.trailing.extend(entry.trailing);
+            }
+            None => grouped.push(entry),
+        }
+    }
+    grouped
+}
+
+/// The items of a use, relative to its path.
+fn collection_items(usage: &mut Use) -> Vec<Spanned<Use>> {
+    let content = std::mem::replace(&mut usage.content.value, UseContent::Collection(vec![]));
+    match content {
+        UseContent::Collection(items) => items,
+        UseContent::Item(item) => vec![Spanned::new(
+            Use {
+                attributes: vec![],
+                path: Spanned::new(vec![], usage.path.span()),
+                content: Spanned::new(UseContent::Item(item), usage.content.span()),
+            },
+            usage.content.span(),
+        )],
+    }
+}
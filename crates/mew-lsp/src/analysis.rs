@@ -0,0 +1,489 @@
+//! What the language server knows about a document: its diagnostics, and what the paths
+//! it contains refer to. Offsets and ranges are in bytes, local to the file they are in.
+//!
+//! The document is a root module named after its file, like the roots of `mewc`, and the
+//! modules it uses are loaded from the workspace.
+
+use std::path::{Path, PathBuf};
+
+use mew_api::{FileSystemLoader, MewApi, MewError, ModuleDescriptor, Source};
+use mew_parse::{
+    span::{FileId, Span, Spanned},
+    syntax::{
+        DeclarationKind, GlobalDeclaration, GlobalDirective, Module, ModuleDirective,
+        ModuleMemberDeclaration, PathPart, Use, UseContent,
+    },
+};
+use mew_resolve::{Resolutions, Resolver};
+
+/// An error of the document, or of a module it uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The range of the error in the document. Errors in other files, and errors without a
+    /// span, are reported at the start of the document.
+    pub range: Span,
+    pub message: String,
+}
+
+/// A file of an [`Analysis`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum File {
+    Document,
+    /// A module loaded from the workspace.
+    Path(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub file: File,
+    pub range: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hover {
+    /// The range of the hovered path segment in the document.
+    pub range: Span,
+    /// The absolute path of the declaration, e.g. `shaders::lighting::Light`.
+    pub path: String,
+    /// The source of the declaration, without the body of functions and modules.
+    pub declaration: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Module,
+    Function,
+    Struct,
+    Alias,
+    Const,
+    Override,
+    Var,
+}
+
+/// A member of a module, completed after `::`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub name: String,
+    pub kind: SymbolKind,
+}
+
+/// A document and the modules it uses, parsed and resolved.
+#[derive(Debug)]
+pub struct Analysis {
+    api: MewApi,
+    module_name: String,
+    resolutions: Resolutions,
+    parsed: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A declaration of the translation unit of an [`Analysis`].
+#[derive(Clone, Copy)]
+enum Declared<'a> {
+    Global(&'a Spanned<GlobalDeclaration>),
+    Member(&'a Spanned<ModuleMemberDeclaration>),
+}
+
+impl<'a> Declared<'a> {
+    fn name(self) -> Option<Spanned<String>> {
+        match self {
+            Declared::Global(declaration) => declaration.name(),
+            Declared::Member(declaration) => declaration.name(),
+        }
+    }
+
+    fn span(self) -> Span {
+        match self {
+            Declared::Global(declaration) => declaration.span(),
+            Declared::Member(declaration) => declaration.span(),
+        }
+    }
+
+    fn module(self) -> Option<&'a Module> {
+        match self {
+            Declared::Global(Spanned {
+                value: GlobalDeclaration::Module(module),
+                ..
+            })
+            | Declared::Member(Spanned {
+                value: ModuleMemberDeclaration::Module(module),
+                ..
+            }) => Some(module),
+            _ => None,
+        }
+    }
+
+    /// The kind of the declaration, and where its body starts.
+    fn kind(self) -> Option<(SymbolKind, Option<usize>)> {
+        let member = match self {
+            Declared::Global(declaration) => match &declaration.value {
+                GlobalDeclaration::Declaration(declaration) => {
+                    return Some((declaration_kind(&declaration.kind.value), None))
+                }
+                GlobalDeclaration::Function(function) => {
+                    return Some((SymbolKind::Function, Some(function.body.span().start)))
+                }
+                GlobalDeclaration::Alias(_) => return Some((SymbolKind::Alias, None)),
+                GlobalDeclaration::Struct(_) => return Some((SymbolKind::Struct, None)),
+                GlobalDeclaration::Module(_) => return Some((SymbolKind::Module, None)),
+                GlobalDeclaration::ConstAssert(_) | GlobalDeclaration::Void => return None,
+            },
+            Declared::Member(declaration) => &declaration.value,
+        };
+        match member {
+            ModuleMemberDeclaration::Declaration(declaration) => {
+                Some((declaration_kind(&declaration.kind.value), None))
+            }
+            ModuleMemberDeclaration::Function(function) => {
+                Some((SymbolKind::Function, Some(function.body.span().start)))
+            }
+            ModuleMemberDeclaration::Alias(_) => Some((SymbolKind::Alias, None)),
+            ModuleMemberDeclaration::Struct(_) => Some((SymbolKind::Struct, None)),
+            ModuleMemberDeclaration::Module(_) => Some((SymbolKind::Module, None)),
+            ModuleMemberDeclaration::ConstAssert(_) | ModuleMemberDeclaration::Void => None,
+        }
+    }
+}
+
+fn declaration_kind(kind: &DeclarationKind) -> SymbolKind {
+    match kind {
+        DeclarationKind::Const | DeclarationKind::Let => SymbolKind::Const,
+        DeclarationKind::Override => SymbolKind::Override,
+        DeclarationKind::Var => SymbolKind::Var,
+    }
+}
+
+fn names(path: &[PathPart]) -> Vec<String> {
+    path.iter().map(|part| part.name.value.clone()).collect()
+}
+
+impl Analysis {
+    /// Parses `text` as the module `module_name`, loads the modules it uses from `root`,
+    /// if any, and resolves every path.
+    pub fn new(module_name: &str, text: &str, root: Option<&Path>) -> Analysis {
+        let mut api = match root {
+            Some(root) => MewApi::with_loader(FileSystemLoader::new(root)),
+            None => MewApi::default(),
+        };
+        let added = api.add_module(ModuleDescriptor {
+            module_name,
+            source: Source::Text(text),
+        });
+        let mut analysis = Analysis {
+            parsed: api.modules.contains_key(module_name),
+            api,
+            module_name: module_name.to_string(),
+            resolutions: Resolutions::default(),
+            diagnostics: vec![],
+        };
+        if let Err(err) = added {
+            analysis.report(err);
+        }
+        if analysis.parsed {
+            let (resolutions, result) = Resolver.resolutions(&analysis.api.translation_unit);
+            analysis.resolutions = resolutions;
+            if let Err(err) = result {
+                analysis.report(err.into());
+            }
+        }
+        analysis
+    }
+
+    /// Whether the document parsed. Otherwise, the analysis only has diagnostics.
+    pub fn is_parsed(&self) -> bool {
+        self.parsed
+    }
+
+    /// The text of `file`.
+    pub fn text(&self, file: &File) -> Option<&str> {
+        let name = match file {
+            File::Document => self.module_name.clone(),
+            File::Path(path) => path.display().to_string(),
+        };
+        self.api
+            .source_map
+            .files()
+            .find(|(_, source)| source.name == name)
+            .map(|(_, source)| source.text.as_str())
+    }
+
+    /// The declaration that the path segment at `offset` refers to.
+    pub fn definition(&self, offset: usize) -> Option<Location> {
+        let (_, path) = self.path_at(offset)?;
+        self.location(self.find(&names(&path))?, &path)
+    }
+
+    /// The absolute path and the declaration of the path segment at `offset`.
+    pub fn hover(&self, offset: usize) -> Option<Hover> {
+        let (range, path) = self.path_at(offset)?;
+        let declared = self.find(&names(&path))?;
+        let (kind, body) = declared.kind()?;
+        let declaration = match (kind, body) {
+            // the module of a file spans the whole file
+            (SymbolKind::Module, _) => format!("mod {}", declared.name()?.value),
+            (_, body) => {
+                let span = declared.span();
+                let span = span.start..body.unwrap_or(span.end);
+                let source = self.api.source_map.file(self.api.source_map.lookup(&span)?);
+                source.text[source.local_span(&span)].trim().to_string()
+            }
+        };
+        Some(Hover {
+            range,
+            path: names(&path).join("::"),
+            declaration,
+        })
+    }
+
+    /// The members of the module named before the `::` that precedes `offset` in `text`,
+    /// the current text of the document. The analysis may be of an earlier version of the
+    /// text, since the document does not parse while a path is being typed.
+    pub fn completions(&self, text: &str, offset: usize) -> Vec<Completion> {
+        let Some(segments) = module_path_before(text, offset) else {
+            return vec![];
+        };
+        let Some(module) = self.resolve_segments(&segments, offset) else {
+            return vec![];
+        };
+        module
+            .members
+            .iter()
+            .filter_map(|member| {
+                let declared = Declared::Member(member);
+                Some(Completion {
+                    name: declared.name()?.value,
+                    kind: declared.kind()?.0,
+                })
+            })
+            .collect()
+    }
+
+    fn report(&mut self, err: MewError) {
+        let document = self.document();
+        for err in err.into_errors() {
+            let message = err.message();
+            let located = err.span.as_ref().and_then(|span| {
+                let file = self.api.source_map.lookup(span)?;
+                Some((file, span))
+            });
+            let diagnostic = match located {
+                Some((file, span)) if Some(file) == document => Diagnostic {
+                    range: self.api.source_map.file(file).local_span(span),
+                    message,
+                },
+                Some((file, _)) => Diagnostic {
+                    range: 0..0,
+                    message: format!("{}: {message}", self.api.source_map.file(file).name),
+                },
+                None => Diagnostic {
+                    range: 0..0,
+                    message,
+                },
+            };
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn document(&self) -> Option<FileId> {
+        self.api
+            .source_map
+            .files()
+            .find(|(_, source)| source.name == self.module_name)
+            .map(|(file, _)| file)
+    }
+
+    fn global_offset(&self, offset: usize) -> Option<usize> {
+        Some(self.api.source_map.file(self.document()?).start + offset)
+    }
+
+    /// The range in the document of the path segment at `offset`, and the absolute path
+    /// of that segment: `lib::Light` for the `Light` of `lib::Light::new`.
+    fn path_at(&self, offset: usize) -> Option<(Span, Vec<PathPart>)> {
+        let offset = self.global_offset(offset)?;
+        let (span, path) = self.resolutions.at(offset)?;
+        // the segments after the one at `offset` keep the spans they have in the source,
+        // the segments before it may come from a `use` or from the enclosing modules
+        let mut end = path.len();
+        while end > 1 {
+            let part = path[end - 1].name.span();
+            if part.start >= span.start && part.end <= span.end && part.start > offset {
+                end -= 1;
+            } else {
+                break;
+            }
+        }
+        let path = path[..end].to_vec();
+        let segment = path.last()?.name.span();
+        let range = if segment.start >= span.start && segment.end <= span.end {
+            segment
+        } else {
+            span.clone()
+        };
+        let document = self.api.source_map.file(self.document()?);
+        Some((document.local_span(&range), path))
+    }
+
+    fn find(&self, path: &[String]) -> Option<Declared<'_>> {
+        let (first, rest) = path.split_first()?;
+        let is_named =
+            |declared: Declared| declared.name().is_some_and(|name| &name.value == first);
+        let mut current = self
+            .api
+            .translation_unit
+            .global_declarations
+            .iter()
+            .map(Declared::Global)
+            .find(|declared| is_named(*declared))?;
+        for name in rest {
+            current = current
+                .module()?
+                .members
+                .iter()
+                .map(Declared::Member)
+                .find(|declared| declared.name().is_some_and(|n| &n.value == name))?;
+        }
+        Some(current)
+    }
+
+    fn location(&self, declared: Declared, path: &[PathPart]) -> Option<Location> {
+        let name = declared.name()?;
+        let span = match name.span() {
+            // modules of whole files are named after the file
+            span if span.is_empty() => {
+                let module_name = names(path).join("::");
+                let file = self.api.modules.get(&module_name)?;
+                let start = self.api.source_map.file(*file).start;
+                start..start
+            }
+            span => span,
+        };
+        let file = self.api.source_map.lookup(&span)?;
+        let source = self.api.source_map.file(file);
+        Some(Location {
+            file: if Some(file) == self.document() {
+                File::Document
+            } else {
+                File::Path(PathBuf::from(&source.name))
+            },
+            range: source.local_span(&span),
+        })
+    }
+
+    /// The module that `segments` refer to from `offset`: the first segment is looked up in
+    /// the `use` directives and members of the enclosing modules, then at the root.
+    fn resolve_segments(&self, segments: &[String], offset: usize) -> Option<&Module> {
+        let (first, rest) = segments.split_first()?;
+        let offset = self.global_offset(offset)?;
+        let mut scopes: Vec<(Vec<String>, &Module)> = vec![];
+        let mut scope = vec![self.module_name.clone()];
+        let mut module = self.find(&scope)?.module()?;
+        loop {
+            scopes.push((scope.clone(), module));
+            let inner = module
+                .members
+                .iter()
+                .find_map(|member| match &member.value {
+                    ModuleMemberDeclaration::Module(inner) if member.span().contains(&offset) => {
+                        Some(inner)
+                    }
+                    _ => None,
+                });
+            let Some(inner) = inner else {
+                break;
+            };
+            scope.push(inner.name.value.clone());
+            module = inner;
+        }
+
+        let global_uses = self
+            .api
+            .translation_unit
+            .global_directives
+            .iter()
+            .filter_map(|directive| match &directive.value {
+                GlobalDirective::Use(usage) => Some(usage),
+                _ => None,
+            });
+        let mut start = None;
+        for (scope, module) in scopes.iter().rev() {
+            let uses = module
+                .directives
+                .iter()
+                .filter_map(|directive| match &directive.value {
+                    ModuleDirective::Use(usage) => Some(usage),
+                    ModuleDirective::Extend(_) => None,
+                });
+            if let Some(path) = uses.filter_map(|usage| self.imported(usage, first)).next() {
+                start = Some(path);
+                break;
+            }
+            let mut path = scope.clone();
+            path.push(first.clone());
+            if self.find(&path).is_some() {
+                start = Some(path);
+                break;
+            }
+        }
+        let mut path = start
+            .or_else(|| {
+                global_uses
+                    .filter_map(|usage| self.imported(usage, first))
+                    .next()
+            })
+            .unwrap_or_else(|| vec![first.clone()]);
+        path.extend(rest.iter().cloned());
+        self.find(&path)?.module()
+    }
+
+    /// The absolute path of the item of `usage` that is imported as `name`.
+    fn imported(&self, usage: &Use, name: &str) -> Option<Vec<String>> {
+        match &usage.content.value {
+            UseContent::Item(item) => {
+                let imported_as = item.rename.as_ref().unwrap_or(&item.name);
+                if imported_as.value != name {
+                    return None;
+                }
+                Some(names(self.resolutions.get(&item.name.span())?))
+            }
+            UseContent::Collection(usages) => {
+                usages.iter().find_map(|usage| self.imported(usage, name))
+            }
+        }
+    }
+}
+
+/// The segments of the path that ends with `::` before `offset`, ignoring the identifier
+/// being typed and template arguments: `["lib", "Buffers"]` for `lib::Buffers<4>::ma`.
+fn module_path_before(text: &str, offset: usize) -> Option<Vec<String>> {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    let mut rest = text.get(..offset)?.trim_end_matches(is_identifier);
+    let mut segments = vec![];
+    while let Some(before) = rest.strip_suffix("::") {
+        rest = before;
+        if rest.ends_with('>') {
+            let mut depth = 0;
+            let mut start = None;
+            for (i, c) in rest.char_indices().rev() {
+                match c {
+                    '>' => depth += 1,
+                    '<' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    start = Some(i);
+                    break;
+                }
+            }
+            rest = &rest[..start?];
+        }
+        let segment = rest.trim_end_matches(is_identifier);
+        let name = &rest[segment.len()..];
+        if name.is_empty() {
+            return None;
+        }
+        segments.push(name.to_string());
+        rest = segment;
+    }
+    segments.reverse();
+    (!segments.is_empty()).then_some(segments)
+}
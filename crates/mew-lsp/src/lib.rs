@@ -0,0 +1,5 @@
+pub mod analysis;
+pub mod server;
+
+pub use analysis::Analysis;
+pub use server::*;
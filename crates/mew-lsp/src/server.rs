@@ -0,0 +1,325 @@
+//! Serves the requests of a client over a [`Connection`], see [`run`]. Documents are synced
+//! in full, and analyzed again on every change.
+
+use std::{collections::HashMap, error::Error, path::PathBuf};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as NotificationTrait, PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as RequestTrait},
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, InitializeParams, Location, MarkupContent,
+    MarkupKind, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use serde_json::Value;
+
+use mew_parse::span::Span;
+
+use crate::analysis::{Analysis, File, SymbolKind};
+
+type Result<T = ()> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// A document open in the client.
+struct Document {
+    text: String,
+    /// The analysis of the latest version that parsed, which still knows the declarations
+    /// while a path is being typed.
+    analysis: Option<Analysis>,
+    /// The text of that version. Offsets and ranges of the analysis are in this text.
+    analyzed_text: String,
+}
+
+impl Document {
+    /// Whether the analysis is of an earlier version than the current text.
+    fn is_stale(&self) -> bool {
+        self.text != self.analyzed_text
+    }
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    /// The directory in which the modules used by documents are looked up. Defaults to the
+    /// directory of each document.
+    root: Option<PathBuf>,
+    documents: HashMap<Url, Document>,
+}
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![":".to_string()]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Initializes `connection`, then serves it until the client shuts it down. The first
+/// workspace folder is the root of the modules used by documents.
+pub fn run(connection: &Connection) -> Result {
+    let params = connection.initialize(serde_json::to_value(capabilities())?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    let root = params
+        .workspace_folders
+        .and_then(|folders| folders.into_iter().next())
+        .and_then(|folder| folder.uri.to_file_path().ok());
+    let mut server = Server {
+        connection,
+        root,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => server.handle_notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+impl Server<'_> {
+    fn handle_request(&self, request: Request) -> Response {
+        let result = match request.method.as_str() {
+            GotoDefinition::METHOD => {
+                parse(request.params).map(|params| to_value(self.definition(params)))
+            }
+            HoverRequest::METHOD => {
+                parse(request.params).map(|params| to_value(self.hover(params)))
+            }
+            Completion::METHOD => {
+                parse(request.params).map(|params| to_value(self.completion(params)))
+            }
+            method => {
+                return Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request `{method}`"),
+                )
+            }
+        };
+        match result {
+            Ok(result) => Response::new_ok(request.id, result),
+            Err(err) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, err),
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: <DidOpenTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                self.update(params.text_document.uri, params.text_document.text)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: <DidChangeTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                // synced in full, the last change is the whole text
+                match params.content_changes.into_iter().last() {
+                    Some(change) => self.update(params.text_document.uri, change.text),
+                    None => Ok(()),
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: <DidCloseTextDocument as NotificationTrait>::Params =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                self.publish(params.text_document.uri, vec![])
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Analyzes the new text of the document at `uri`, and publishes its diagnostics.
+    fn update(&mut self, uri: Url, text: String) -> Result {
+        let path = uri.to_file_path().ok();
+        let module_name = path
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().replace('-', "_"))
+            .unwrap_or_else(|| "main".to_string());
+        let root = self.root.clone().or_else(|| {
+            path.as_ref()
+                .and_then(|path| path.parent())
+                .map(|parent| parent.to_path_buf())
+        });
+        let analysis = Analysis::new(&module_name, &text, root.as_deref());
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| Diagnostic {
+                range: range(&text, &diagnostic.range),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("mew".to_string()),
+                message: diagnostic.message.clone(),
+                ..Default::default()
+            })
+            .collect();
+
+        let document = self.documents.entry(uri.clone()).or_insert(Document {
+            text: String::new(),
+            analysis: None,
+            analyzed_text: String::new(),
+        });
+        if analysis.is_parsed() {
+            document.analysis = Some(analysis);
+            document.analyzed_text = text.clone();
+        }
+        document.text = text;
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> Result {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection
+            .sender
+            .send(Message::Notification(notification))?;
+        Ok(())
+    }
+
+    /// The document at `uri`, its analysis and the byte offset of `position` in its text.
+    /// The analysis may be of an earlier version of the text, see [`Document::analysis`].
+    fn locate(&self, uri: &Url, position: Position) -> Option<(&Document, &Analysis, usize)> {
+        let document = self.documents.get(uri)?;
+        let analysis = document.analysis.as_ref()?;
+        Some((document, analysis, offset(&document.text, position)))
+    }
+
+    /// Like [`Server::locate`], but `None` while the analysis is of an earlier version of
+    /// the text, whose offsets do not match the current one.
+    fn locate_analyzed(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Option<(&Document, &Analysis, usize)> {
+        self.locate(uri, position)
+            .filter(|(document, ..)| !document.is_stale())
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let uri = position.text_document.uri;
+        let (_, analysis, offset) = self.locate_analyzed(&uri, position.position)?;
+        let location = analysis.definition(offset)?;
+        let text = analysis.text(&location.file)?;
+        let uri = match &location.file {
+            File::Document => uri.clone(),
+            File::Path(path) => Url::from_file_path(path).ok()?,
+        };
+        Some(GotoDefinitionResponse::Scalar(Location {
+            uri,
+            range: range(text, &location.range),
+        }))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let (document, analysis, offset) =
+            self.locate_analyzed(&position.text_document.uri, position.position)?;
+        let hover = analysis.hover(offset)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("`{}`\n\n```wgsl\n{}\n```", hover.path, hover.declaration),
+            }),
+            range: Some(range(&document.text, &hover.range)),
+        })
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let (document, analysis, offset) =
+            self.locate(&position.text_document.uri, position.position)?;
+        let items = analysis
+            .completions(&document.text, offset)
+            .into_iter()
+            .map(|completion| CompletionItem {
+                label: completion.name,
+                kind: Some(completion_kind(completion.kind)),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(params: Value) -> std::result::Result<T, String> {
+    serde_json::from_value(params).map_err(|err| err.to_string())
+}
+
+/// `null` for `None`, which the protocol uses when there is no result.
+fn to_value<T: serde::Serialize>(result: Option<T>) -> Value {
+    serde_json::to_value(result).unwrap_or(Value::Null)
+}
+
+fn completion_kind(kind: SymbolKind) -> CompletionItemKind {
+    match kind {
+        SymbolKind::Module => CompletionItemKind::MODULE,
+        SymbolKind::Function => CompletionItemKind::FUNCTION,
+        SymbolKind::Struct => CompletionItemKind::STRUCT,
+        SymbolKind::Alias => CompletionItemKind::TYPE_PARAMETER,
+        SymbolKind::Const | SymbolKind::Override => CompletionItemKind::CONSTANT,
+        SymbolKind::Var => CompletionItemKind::VARIABLE,
+    }
+}
+
+/// The byte offset of `position` in `text`. Columns count UTF-16 code units, the default
+/// encoding of the protocol.
+pub fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(end) => line_start += end + 1,
+            None => return text.len(),
+        }
+    }
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= position.character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16() as u32;
+    }
+    text.len()
+}
+
+/// The position of the byte offset `offset` in `text`, or of the character that contains
+/// it.
+pub fn position(text: &str, offset: usize) -> Position {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |end| end + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn range(text: &str, span: &Span) -> Range {
+    Range {
+        start: position(text, span.start),
+        end: position(text, span.end),
+    }
+}
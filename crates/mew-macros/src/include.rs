@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use mew_api::{FileSystemLoader, MewApi, ModuleDescriptor, Path, Source};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::{
+    parse::{Parse, ParseStream},
+    LitStr, Token,
+};
+
+/// The arguments of [`crate::include_mew!`]: the path of the file and the entry path.
+pub(crate) struct IncludeMew {
+    path: LitStr,
+    entry: LitStr,
+}
+
+impl Parse for IncludeMew {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let entry = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Self { path, entry })
+    }
+}
+
+pub(crate) fn expand(input: &IncludeMew) -> TokenStream {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let path = manifest_dir.join(input.path.value());
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) => {
+            let message = format!("couldn't read `{}`: {err}", path.display());
+            let error = compile_error(input.path.span(), &message);
+            return quote!({
+                #error
+                ""
+            });
+        }
+    };
+    let module_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().replace('-', "_"))
+        .unwrap_or_default();
+
+    let root = path.parent().map(PathBuf::from).unwrap_or_default();
+    let mut api = MewApi::with_loader(FileSystemLoader::new(root));
+    let module = ModuleDescriptor {
+        module_name: &module_name,
+        source: Source::Text(&source),
+    };
+    let result = match api.add_module(module) {
+        Ok(()) => api.compile(&Path::Text(input.entry.value())),
+        Err(err) => Err(err),
+    };
+
+    // proc macros cannot tell cargo which files they read, but `include_bytes!` can: the
+    // crate is rebuilt when a shader changes.
+    let dependencies = std::iter::once(&path).chain(&api.loaded_files).map(|path| {
+        let path = path.display().to_string();
+        quote!(
+            const _: &[u8] = include_bytes!(#path);
+        )
+    });
+
+    match result {
+        Ok(wgsl) => quote!({
+            #(#dependencies)*
+            #wgsl
+        }),
+        Err(err) => {
+            // errors pointing into a shader file are reported on the file argument, the
+            // others (e.g. a malformed entry path) on the entry argument.
+            let errors = err.into_errors().into_iter().map(|err| {
+                let span = match err.span {
+                    Some(_) => input.path.span(),
+                    None => input.entry.span(),
+                };
+                // the root file is named after its module in the source map.
+                let message = api.format_error_plain(&err).replacen(
+                    &format!("--> {module_name}:"),
+                    &format!("--> {}:", input.path.value()),
+                    1,
+                );
+                compile_error(span, &message)
+            });
+            quote!({
+                #(#dependencies)*
+                #(#errors)*
+                ""
+            })
+        }
+    }
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    // `compile_error!` adds its own `error: `
+    let message = message.strip_prefix("error: ").unwrap_or(message);
+    quote_spanned!(span=> ::core::compile_error!(#message);)
+}
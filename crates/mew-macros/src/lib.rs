@@ -0,0 +1,22 @@
+//! Compiles mew shaders while compiling Rust code, see [`include_mew!`].
+
+mod include;
+
+use proc_macro::TokenStream;
+
+/// Compiles the module member at an entry path of a mew file to WGSL and expands to it as a
+/// `&'static str`:
+///
+/// ```ignore
+/// const SHADER: &str = include_mew!("shaders/root.mew", "root::My_Lib<A, B>::main");
+/// ```
+///
+/// The file is relative to the directory of the crate's `Cargo.toml` and is added as a
+/// module named after it, with `-` replaced by `_`. Modules it uses are loaded from its
+/// directory. Errors in the shaders are reported as compiler errors, with the part of the
+/// shader file they point to.
+#[proc_macro]
+pub fn include_mew(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as include::IncludeMew);
+    include::expand(&input).into()
+}
@@ -17,17 +17,22 @@ pub enum ParseError {
     ParseDiagnosticSeverity,
 }
 
-type LalrError = lalrpop_util::ParseError<usize, Token, (usize, ParseError, usize)>;
+pub(crate) type LalrError = lalrpop_util::ParseError<usize, Token, (usize, ParseError, usize)>;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct SpannedError<'s> {
     inner: LalrError,
     source: &'s str,
+    offset: usize,
 }
 
 impl<'s> SpannedError<'s> {
-    pub(crate) fn new(inner: LalrError, source: &'s str) -> Self {
-        Self { inner, source }
+    pub(crate) fn new(inner: LalrError, source: &'s str, offset: usize) -> Self {
+        Self {
+            inner,
+            source,
+            offset,
+        }
     }
 
     pub fn span(&self) -> Span {
@@ -45,7 +50,7 @@ impl<'s> std::error::Error for SpannedError<'s> {}
 
 impl<'s> Display for SpannedError<'s> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let err = &self.inner;
+        let err = &self.inner.clone().map_location(|l| l - self.offset);
         let source = &self.source;
         let renderer = Renderer::styled();
         match err {
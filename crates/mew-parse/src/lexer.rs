@@ -1,6 +1,11 @@
 //! Prefer using [`Parser::parse_str`]. You shouldn't need to manipulate the lexer.
 
-use crate::{error::ParseError, parser::Parser, span::Span};
+use crate::{
+    error::ParseError,
+    parser::Parser,
+    span::Span,
+    trivia::{scan_comments, Comment},
+};
 use logos::{Logos, SpannedIter};
 use std::{fmt::Display, num::NonZeroU8};
 
@@ -328,6 +333,12 @@ pub enum Token {
     KwAs,
     #[token("with")]
     KwWith,
+    #[token("self")]
+    KwSelf,
+    #[token("super")]
+    KwSuper,
+    #[token("package")]
+    KwPackage,
     // END MEW KEYWORDS
 
     // XXX: should we also register reserved words as tokens?
@@ -456,6 +467,9 @@ impl Token {
                 | Token::KwExtend
                 | Token::KwSig
                 | Token::KwAs
+                | Token::KwSelf
+                | Token::KwSuper
+                | Token::KwPackage
         )
     }
 
@@ -554,6 +568,9 @@ impl Display for Token {
             Token::KwExtend => f.write_str("extend"),
             Token::KwSig => f.write_str("sig"),
             Token::KwWith => f.write_str("with"),
+            Token::KwSelf => f.write_str("self"),
+            Token::KwSuper => f.write_str("super"),
+            Token::KwPackage => f.write_str("package"),
             Token::Ident(s) => write!(f, "identifier `{s}`"),
             Token::AbstractInt(n) => write!(f, "{n}"),
             Token::AbstractFloat(n) => write!(f, "{n}"),
@@ -574,28 +591,58 @@ pub type Spanned<Tok, Loc, ParseError> = Result<(Loc, Tok, Loc), (Loc, ParseErro
 #[derive(Clone)]
 pub struct Lexer<'s> {
     source: &'s str,
+    offset: usize,
     token_stream: SpannedIter<'s, Token>,
     next_token: Option<(Result<Token, ParseError>, Span)>,
     parsing_template: bool,
     opened_templates: u32,
+    comments: Option<Vec<Comment>>,
+    trivia_end: usize,
 }
 
 impl<'s> Lexer<'s> {
     pub fn new(source: &'s str) -> Self {
+        Self::with_offset(source, 0)
+    }
+
+    /// Creates a lexer whose token positions start at `offset` instead of 0.
+    pub fn with_offset(source: &'s str, offset: usize) -> Self {
         let mut token_stream = Token::lexer_with_extras(source, LexerState::default()).spanned();
         let next_token = token_stream.next();
         Self {
             source,
+            offset,
             token_stream,
             next_token,
             parsing_template: false,
             opened_templates: 0,
+            comments: None,
+            trivia_end: 0,
         }
     }
 
+    /// Records the comments between tokens instead of skipping them, see
+    /// [`Lexer::take_comments`].
+    pub fn with_comments(mut self) -> Self {
+        self.comments = Some(vec![]);
+        self
+    }
+
+    /// The comments found so far, if the lexer was created [`Lexer::with_comments`].
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        self.comments
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
     pub fn source(&self) -> &str {
         self.source
     }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 }
 
 /// Returns `true` if the source starts with a valid template list.
@@ -690,9 +737,21 @@ impl<'s> Iterator for Lexer<'s> {
 
         std::mem::swap(&mut self.next_token, &mut next_token);
 
+        if let Some(comments) = self.comments.as_mut() {
+            let (start, end) = match &next_token {
+                Some((_, span)) => (span.start, span.end),
+                None => (self.source.len(), self.source.len()),
+            };
+            if start > self.trivia_end {
+                scan_comments(self.source, self.trivia_end, start, self.offset, comments);
+            }
+            self.trivia_end = self.trivia_end.max(end);
+        }
+
+        let offset = self.offset;
         next_token.map(|(token, span)| match token {
-            Ok(tok) => Ok((span.start, tok, span.end)),
-            Err(err) => Err((span.start, err, span.end)),
+            Ok(tok) => Ok((span.start + offset, tok, span.end + offset)),
+            Err(err) => Err((span.start + offset, err, span.end + offset)),
         })
     }
 }
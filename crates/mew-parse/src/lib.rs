@@ -3,10 +3,13 @@ pub mod lexer;
 pub mod parser;
 pub mod span;
 pub mod syntax;
+pub mod trivia;
 
 mod parser_support;
 mod syntax_display;
 mod syntax_impl;
+mod syntax_spans;
+mod syntax_visit;
 
 pub use lexer::Lexer;
 pub use parser::Parser;
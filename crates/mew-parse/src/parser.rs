@@ -3,17 +3,20 @@
 //! [syntax tree]: syntax
 
 lalrpop_mod!(
-    #[allow(clippy::type_complexity)]
+    #[allow(clippy::type_complexity, clippy::ptr_arg)]
     wgsl
 );
-use lalrpop_util::lalrpop_mod;
+use lalrpop_util::{lalrpop_mod, ErrorRecovery};
 
 use crate::{
-    error::SpannedError,
-    lexer::Lexer,
+    error::{LalrError, ParseError, SpannedError},
+    lexer::{Lexer, Token},
     syntax::{self, IdentifierExpression},
+    trivia::TriviaMap,
 };
 
+type RecoveredErrors = Vec<ErrorRecovery<usize, Token, (usize, ParseError, usize)>>;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Parser;
 
@@ -21,30 +24,99 @@ impl Parser {
     pub fn parse_path(path: &str) -> Result<IdentifierExpression, SpannedError> {
         let lexer = Lexer::new(path);
         let parser = wgsl::EntryPointPathParser::new();
-        let res = parser.parse(lexer);
-        res.map_err(|e| SpannedError::new(e, path))
+        let mut errors = RecoveredErrors::new();
+        let res = parser.parse(&mut errors, lexer);
+        Self::first_error(res, errors).map_err(|e| SpannedError::new(e, path, 0))
     }
 
     pub fn parse_str(source: &str) -> Result<syntax::TranslationUnit, SpannedError> {
-        let lexer = Lexer::new(source);
+        Self::parse_str_at(source, 0)
+    }
+
+    /// Parses `source` with spans starting at `offset`, usually [`crate::span::SourceFile::start`].
+    pub fn parse_str_at(
+        source: &str,
+        offset: usize,
+    ) -> Result<syntax::TranslationUnit, SpannedError<'_>> {
+        let lexer = Lexer::with_offset(source, offset);
+        let parser = wgsl::TranslationUnitParser::new();
+        let mut errors = RecoveredErrors::new();
+        let res = parser.parse(&mut errors, lexer);
+        Self::first_error(res, errors).map_err(|e| SpannedError::new(e, source, offset))
+    }
+
+    /// Like [`Parser::parse_str`], but keeps going after syntax errors. Declarations,
+    /// module members and statements that fail to parse are skipped. Returns the
+    /// translation unit that could be parsed and every syntax error.
+    pub fn parse_str_recovering(source: &str) -> (syntax::TranslationUnit, Vec<SpannedError<'_>>) {
+        Self::parse_str_recovering_at(source, 0)
+    }
+
+    /// Like [`Parser::parse_str_at`], but keeps going after syntax errors, see
+    /// [`Parser::parse_str_recovering`].
+    pub fn parse_str_recovering_at(
+        source: &str,
+        offset: usize,
+    ) -> (syntax::TranslationUnit, Vec<SpannedError<'_>>) {
+        let lexer = Lexer::with_offset(source, offset);
+        let parser = wgsl::TranslationUnitParser::new();
+        let mut errors = RecoveredErrors::new();
+        let res = parser.parse(&mut errors, lexer);
+        let mut errors: Vec<SpannedError<'_>> = errors
+            .into_iter()
+            .map(|e| SpannedError::new(e.error, source, offset))
+            .collect();
+        match res {
+            Ok(translation_unit) => (translation_unit, errors),
+            Err(e) => {
+                errors.push(SpannedError::new(e, source, offset));
+                (syntax::TranslationUnit::default(), errors)
+            }
+        }
+    }
+
+    /// Like [`Parser::parse_str`], but also returns the comments of `source`, see
+    /// [`crate::trivia`].
+    pub fn parse_str_lossless(
+        source: &str,
+    ) -> Result<(syntax::TranslationUnit, TriviaMap), SpannedError<'_>> {
+        let mut lexer = Lexer::new(source).with_comments();
         let parser = wgsl::TranslationUnitParser::new();
-        let res = parser.parse(lexer);
-        res.map_err(|e| SpannedError::new(e, source))
+        let mut errors = RecoveredErrors::new();
+        let res = parser.parse(&mut errors, &mut lexer);
+        let translation_unit =
+            Self::first_error(res, errors).map_err(|e| SpannedError::new(e, source, 0))?;
+        let trivia = TriviaMap::new(&translation_unit, lexer.take_comments(), source, 0);
+        Ok((translation_unit, trivia))
     }
+
     pub fn parse<'s>(
         mut lexer: &'s mut Lexer,
     ) -> Result<syntax::TranslationUnit, SpannedError<'s>> {
         let parser = wgsl::TranslationUnitParser::new();
-        let res = parser.parse(&mut lexer);
-        res.map_err(|e| SpannedError::new(e, lexer.source()))
+        let mut errors = RecoveredErrors::new();
+        let res = parser.parse(&mut errors, &mut lexer);
+        Self::first_error(res, errors)
+            .map_err(|e| SpannedError::new(e, lexer.source(), lexer.offset()))
+    }
+
+    /// The non-recovering parsers fail on the first syntax error, even if the grammar
+    /// recovered from it.
+    fn first_error<T>(res: Result<T, LalrError>, errors: RecoveredErrors) -> Result<T, LalrError> {
+        match errors.into_iter().next() {
+            Some(recovered) => Err(recovered.error),
+            None => res,
+        }
     }
 }
 
 impl Parser {
     pub fn recognize_template_list<'s>(mut lexer: &'s mut Lexer) -> Result<(), SpannedError<'s>> {
         let parser = wgsl::TryTemplateListParser::new();
-        let res = parser.parse(&mut lexer);
-        res.map_err(|e| SpannedError::new(e, lexer.source()))
+        let mut errors = RecoveredErrors::new();
+        let res = parser.parse(&mut errors, &mut lexer);
+        let res = Self::first_error(res, errors);
+        res.map_err(|e| SpannedError::new(e, lexer.source(), lexer.offset()))
             .and(Ok(()))
     }
 }
@@ -1,4 +1,7 @@
 use std::ops::{Deref, DerefMut};
+
+/// A byte range in the offset space of a [`SourceMap`]. Spans of different files never
+/// overlap, so a span identifies the file it comes from.
 pub type Span = std::ops::Range<usize>;
 
 pub(crate) type S<T> = Spanned<T>;
@@ -76,3 +79,105 @@ impl<T: IntoIterator> IntoIterator for Spanned<T> {
         self.value.into_iter()
     }
 }
+
+/// Identifies a file in a [`SourceMap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub usize);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+    /// Offset of the first byte of the file in the [`SourceMap`].
+    pub start: usize,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// Converts a span of this file to a range in [`SourceFile::text`].
+    pub fn local_span(&self, span: &Span) -> Span {
+        (span.start - self.start)..(span.end - self.start)
+    }
+}
+
+/// A line and column in a file, both starting at 1. Columns are counted in chars.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub file: FileId,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Assigns every source file a distinct range of offsets, so that spans of any file can
+/// be mapped back to it. Offset `0` belongs to no file and is used for synthesized nodes,
+/// and so do the ranges of [`SourceMap::reserve`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    /// The end of the last file or reserved range.
+    end: usize,
+}
+
+impl SourceMap {
+    /// Adds a file. It starts one byte past the end of the previous one.
+    pub fn add_file(&mut self, name: impl Into<String>, text: impl Into<String>) -> FileId {
+        let text = text.into();
+        let start = self.reserve(text.len());
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        self.files.push(SourceFile {
+            name: name.into(),
+            text,
+            start,
+            line_starts,
+        });
+        FileId(self.files.len() - 1)
+    }
+
+    /// Reserves `len` bytes of offsets that belong to no file, for spans without source
+    /// text. They start one byte past the end of the previous file or range, and the start
+    /// is returned.
+    pub fn reserve(&mut self, len: usize) -> usize {
+        let start = self.end + 1;
+        self.end = start + len;
+        start
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (FileId(i), file))
+    }
+
+    /// Returns the file containing `span`, if any.
+    pub fn lookup(&self, span: &Span) -> Option<FileId> {
+        let index = self.files.partition_point(|file| file.start <= span.start);
+        let file = self.files.get(index.checked_sub(1)?)?;
+        (span.end <= file.end()).then_some(FileId(index - 1))
+    }
+
+    /// Returns the line and column of `offset`.
+    pub fn location(&self, offset: usize) -> Option<Location> {
+        let id = self.lookup(&(offset..offset))?;
+        let file = self.file(id);
+        let local = offset - file.start;
+        let line = file.line_starts.partition_point(|start| *start <= local);
+        let line_start = file.line_starts[line - 1];
+        let column = file.text[line_start..local].chars().count() + 1;
+        Some(Location {
+            file: id,
+            line,
+            column,
+        })
+    }
+}
@@ -22,11 +22,19 @@
 //! The parsing is not designed to be primarily efficient, but flexible and correct.
 //! It is made with the ultimate goal to implement spec-compliant language extensions.
 //! This is why this parser doesn't borrow strings.
+//!
+//! ## Traversal
+//!
+//! The [`Visit`] and [`VisitMut`] traits walk the tree, see [`visit`] and [`visit_mut`].
+//! [`SpansMut`] reaches every span of the tree.
 
 use std::{hash::Hash, ops::Deref};
 
 use crate::span::S;
 
+pub use crate::syntax_spans::SpansMut;
+pub use crate::syntax_visit::{visit, visit::Visit, visit_mut, visit_mut::VisitMut};
+
 pub struct WithSource<'s, T> {
     syntax: T,
     source: &'s str,
@@ -217,6 +225,7 @@ pub enum DeclarationKind {
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Alias {
+    pub attributes: Vec<S<Attribute>>,
     pub name: S<String>,
     pub typ: S<TypeExpression>,
     pub template_parameters: Vec<S<FormalTemplateParameter>>,
@@ -224,6 +233,7 @@ pub struct Alias {
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Struct {
+    pub attributes: Vec<S<Attribute>>,
     pub name: S<String>,
     pub members: Vec<S<StructMember>>,
     pub template_parameters: Vec<S<FormalTemplateParameter>>,
@@ -1,4 +1,8 @@
-use crate::{span::S, syntax::*};
+use crate::{
+    span::{Span, S},
+    syntax::*,
+    trivia::{CommentKind, TriviaMap},
+};
 use core::fmt;
 use std::fmt::{Display, Formatter, Write};
 
@@ -19,15 +23,156 @@ impl<T: Display> Display for Indent<T> {
     }
 }
 
-impl Display for TranslationUnit {
+/// Display with the comments of a [`TriviaMap`], for nodes that contain nodes with comments.
+trait FmtTrivia {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result;
+}
+
+struct WithTrivia<'a, T>(&'a T, Option<&'a TriviaMap>);
+
+impl<T: FmtTrivia> Display for WithTrivia<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_trivia(f, self.1)
+    }
+}
+
+/// A node with its leading and trailing comments. The suffix (a separator) is printed
+/// before the trailing comments.
+struct Commented<'a, T>(&'a S<T>, Option<&'a TriviaMap>, &'static str);
+
+impl<T: FmtTrivia> Display for Commented<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Commented(node, trivia, suffix) = self;
+        fmt_leading_comments(f, *trivia, &node.span)?;
+        node.value.fmt_trivia(f, *trivia)?;
+        f.write_str(suffix)?;
+        fmt_trailing_comments(f, *trivia, &node.span)
+    }
+}
+
+/// A statement with its comments. The trailing comments of a declaration statement
+/// follow the declaration, not the statements in its scope.
+struct CommentedStatement<'a>(&'a S<Statement>, Option<&'a TriviaMap>);
+
+impl Display for CommentedStatement<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let directives = self.global_directives.iter().format("\n");
+        let CommentedStatement(stmt, trivia) = self;
+        match &stmt.value {
+            Statement::Declaration(decl) => {
+                fmt_leading_comments(f, *trivia, &stmt.span)?;
+                write!(f, "{}", decl.declaration)?;
+                fmt_trailing_comments(f, *trivia, &stmt.span)?;
+                for stmt in decl.statements.iter() {
+                    write!(f, "\n{}", CommentedStatement(stmt, *trivia))?;
+                }
+                Ok(())
+            }
+            _ => write!(f, "{}", Commented(stmt, *trivia, "")),
+        }
+    }
+}
+
+fn fmt_leading_comments(
+    f: &mut Formatter<'_>,
+    trivia: Option<&TriviaMap>,
+    span: &Span,
+) -> fmt::Result {
+    for comment in trivia.map(|t| t.leading(span)).unwrap_or_default() {
+        if comment.kind == CommentKind::Block && !comment.own_line {
+            write!(f, "{} ", comment.text)?;
+        } else {
+            writeln!(f, "{}", comment.text)?;
+        }
+    }
+    Ok(())
+}
+
+fn fmt_trailing_comments(
+    f: &mut Formatter<'_>,
+    trivia: Option<&TriviaMap>,
+    span: &Span,
+) -> fmt::Result {
+    for comment in trivia.map(|t| t.trailing(span)).unwrap_or_default() {
+        if comment.own_line {
+            write!(f, "\n{}", comment.text)?;
+        } else {
+            write!(f, " {}", comment.text)?;
+        }
+    }
+    Ok(())
+}
+
+macro_rules! impl_fmt_trivia_with_display {
+    ($($ty:ty),*) => {
+        $(impl FmtTrivia for $ty {
+            fn fmt_trivia(&self, f: &mut Formatter<'_>, _: Option<&TriviaMap>) -> fmt::Result {
+                Display::fmt(self, f)
+            }
+        })*
+    };
+}
+
+impl_fmt_trivia_with_display!(
+    GlobalDirective,
+    ModuleDirective,
+    CompoundDirective,
+    StructMember
+);
+
+macro_rules! impl_display_with_fmt_trivia {
+    ($($ty:ty),*) => {
+        $(impl Display for $ty {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                self.fmt_trivia(f, None)
+            }
+        })*
+    };
+}
+
+impl_display_with_fmt_trivia!(
+    TranslationUnit,
+    GlobalDeclaration,
+    Struct,
+    Function,
+    Statement,
+    CompoundStatement,
+    DeclarationStatement,
+    IfStatement,
+    SwitchStatement,
+    SwitchClause,
+    LoopStatement,
+    ContinuingStatement,
+    ForStatement,
+    WhileStatement,
+    ModuleMemberDeclaration,
+    Module
+);
+
+impl TranslationUnit {
+    /// Prints the translation unit with the comments of `trivia`, see [`crate::trivia`].
+    pub fn display_with_trivia<'a>(&'a self, trivia: &'a TriviaMap) -> impl Display + 'a {
+        WithTrivia(self, Some(trivia))
+    }
+}
+
+impl FmtTrivia for TranslationUnit {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
+        let directives = self
+            .global_directives
+            .iter()
+            .map(|directive| Commented(directive, trivia, ""))
+            .format("\n");
         let declarations = self
             .global_declarations
             .iter()
             // .filter(|decl| !matches!(decl, GlobalDeclaration::Void))
+            .map(|decl| Commented(decl, trivia, ""))
             .format("\n\n");
-        writeln!(f, "{directives}\n\n{declarations}")
+        writeln!(f, "{directives}\n\n{declarations}")?;
+        for comment in trivia.map(|t| t.dangling.as_slice()).unwrap_or_default() {
+            writeln!(f, "{}", comment.text)?;
+        }
+        Ok(())
     }
 }
 
@@ -79,16 +224,16 @@ impl Display for RequiresDirective {
     }
 }
 
-impl Display for GlobalDeclaration {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for GlobalDeclaration {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         match self {
             GlobalDeclaration::Void => write!(f, ";"),
             GlobalDeclaration::Declaration(print) => write!(f, "{}", print),
             GlobalDeclaration::Alias(print) => write!(f, "{}", print),
-            GlobalDeclaration::Struct(print) => write!(f, "{}", print),
-            GlobalDeclaration::Function(print) => write!(f, "{}", print),
+            GlobalDeclaration::Struct(print) => print.fmt_trivia(f, trivia),
+            GlobalDeclaration::Function(print) => print.fmt_trivia(f, trivia),
             GlobalDeclaration::ConstAssert(print) => write!(f, "{}", print),
-            GlobalDeclaration::Module(print) => write!(f, "{}", print),
+            GlobalDeclaration::Module(print) => print.fmt_trivia(f, trivia),
         }
     }
 }
@@ -131,10 +276,11 @@ impl Display for DeclarationKind {
 
 impl Display for Alias {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let attrs = fmt_attrs(&self.attributes, false);
         let name = &self.name;
         let typ = &self.typ;
         let template_params = fmt_template_params(&self.template_parameters);
-        write!(f, "alias {name}{template_params} = {typ};")
+        write!(f, "{attrs}alias {name}{template_params} = {typ};")
     }
 }
 
@@ -148,12 +294,20 @@ fn fmt_template_params(params: &[S<FormalTemplateParameter>]) -> String {
     result
 }
 
-impl Display for Struct {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for Struct {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
+        let attrs = fmt_attrs(&self.attributes, false);
         let name = &self.name;
-        let members = Indent(self.members.iter().format(",\n"));
+        let last = self.members.len().saturating_sub(1);
+        let members = Indent(
+            self.members
+                .iter()
+                .enumerate()
+                .map(|(i, member)| Commented(member, trivia, if i < last { "," } else { "" }))
+                .format("\n"),
+        );
         let template_params = fmt_template_params(&self.template_parameters);
-        write!(f, "struct {name}{template_params} {{\n{members}\n}}")
+        write!(f, "{attrs}struct {name}{template_params} {{\n{members}\n}}")
     }
 }
 
@@ -166,8 +320,8 @@ impl Display for StructMember {
     }
 }
 
-impl Display for Function {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for Function {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, false);
         let name = &self.name;
         let params = self.parameters.iter().format(", ");
@@ -178,7 +332,7 @@ impl Display for Function {
             .map(|typ| format!("-> {ret_attrs}{} ", typ))
             .unwrap_or_default();
         let template_params = fmt_template_params(&self.template_parameters);
-        let body = &self.body;
+        let body = WithTrivia(&self.body.value, trivia);
         write!(
             f,
             "{attrs}fn {name}{template_params}({params}) {ret_typ}{body}"
@@ -393,19 +547,19 @@ fn fmt_inline_args(inline: &Option<InlineTemplateArgs>) -> String {
     }
 }
 
-impl Display for Statement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for Statement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         match self {
             Statement::Void => write!(f, ";"),
-            Statement::Compound(print) => write!(f, "{}", print),
+            Statement::Compound(print) => print.fmt_trivia(f, trivia),
             Statement::Assignment(print) => write!(f, "{}", print),
             Statement::Increment(expr) => write!(f, "{}++;", expr),
             Statement::Decrement(expr) => write!(f, "{}--;", expr),
-            Statement::If(print) => write!(f, "{}", print),
-            Statement::Switch(print) => write!(f, "{}", print),
-            Statement::Loop(print) => write!(f, "{}", print),
-            Statement::For(print) => write!(f, "{}", print),
-            Statement::While(print) => write!(f, "{}", print),
+            Statement::If(print) => print.fmt_trivia(f, trivia),
+            Statement::Switch(print) => print.fmt_trivia(f, trivia),
+            Statement::Loop(print) => print.fmt_trivia(f, trivia),
+            Statement::For(print) => print.fmt_trivia(f, trivia),
+            Statement::While(print) => print.fmt_trivia(f, trivia),
             Statement::Break => write!(f, "break;"),
             Statement::Continue => write!(f, "continue;"),
             Statement::Return(expr) => {
@@ -418,7 +572,7 @@ impl Display for Statement {
             Statement::Discard => write!(f, "discard;"),
             Statement::FunctionCall(expr) => write!(f, "{};", expr),
             Statement::ConstAssert(print) => write!(f, "{}", print),
-            Statement::Declaration(print) => write!(f, "{}", print),
+            Statement::Declaration(print) => print.fmt_trivia(f, trivia),
         }
     }
 }
@@ -462,15 +616,26 @@ impl Display for ExtendDirective {
     }
 }
 
-impl Display for CompoundStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for CompoundStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, true);
-        let mut directives = format!("{}", self.directives.iter().format("\n"));
+        let mut directives = format!(
+            "{}",
+            self.directives
+                .iter()
+                .map(|directive| Commented(directive, trivia, ""))
+                .format("\n")
+        );
         if !directives.is_empty() {
             directives = format!("{}", Indent(directives));
             directives.push('\n');
         }
-        let stmts = Indent(self.statements.iter().format("\n"));
+        let stmts = Indent(
+            self.statements
+                .iter()
+                .map(|stmt| CommentedStatement(stmt, trivia))
+                .format("\n"),
+        );
         write!(f, "{attrs}{{\n{}{stmts}\n}}", directives)
     }
 }
@@ -502,50 +667,60 @@ impl Display for AssignmentOperator {
     }
 }
 
-impl Display for DeclarationStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl FmtTrivia for DeclarationStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         write!(f, "{}", self.declaration)?;
         if !self.statements.is_empty() {
             f.write_char('\n')?;
-            let stmts = self.statements.iter().format("\n");
+            let stmts = self
+                .statements
+                .iter()
+                .map(|stmt| CommentedStatement(stmt, trivia))
+                .format("\n");
             write!(f, "{stmts}")?;
         };
         Ok(())
     }
 }
 
-impl Display for IfStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for IfStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, false);
         let expr = &self.if_clause.0;
-        let stmt = &self.if_clause.1;
+        let stmt = WithTrivia(&self.if_clause.1.value, trivia);
         write!(f, "{attrs}if {expr} {stmt}")?;
         for else_if_clause in self.else_if_clauses.iter() {
             let expr = &else_if_clause.0;
-            let stmt = &else_if_clause.1;
+            let stmt = WithTrivia(&else_if_clause.1.value, trivia);
             write!(f, "\nelse if {expr} {stmt}")?;
         }
         if let Some(ref else_stmt) = self.else_clause {
+            let else_stmt = WithTrivia(&else_stmt.value, trivia);
             write!(f, "\nelse {else_stmt}")?;
         }
         Ok(())
     }
 }
 
-impl Display for SwitchStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for SwitchStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, false);
         let expr = &self.expression;
         let body_attrs = fmt_attrs(&self.body_attributes, false);
-        let clauses = Indent(self.clauses.iter().format("\n"));
+        let clauses = Indent(
+            self.clauses
+                .iter()
+                .map(|clause| WithTrivia(&clause.value, trivia))
+                .format("\n"),
+        );
         write!(f, "{attrs}switch {expr} {body_attrs}{{\n{clauses}\n}}")
     }
 }
 
-impl Display for SwitchClause {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for SwitchClause {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let cases = self.case_selectors.iter().format(", ");
-        let body = &self.body;
+        let body = WithTrivia(&self.body.value, trivia);
         write!(f, "case {cases} {body}")
     }
 }
@@ -561,8 +736,8 @@ impl Display for CaseSelector {
     }
 }
 
-impl Display for LoopStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for LoopStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, false);
         let body_attrs = fmt_attrs(&self.body.attributes, false);
         let stmts = Indent(
@@ -570,25 +745,27 @@ impl Display for LoopStatement {
                 .statements
                 .iter()
                 // .filter(|stmt| !matches!(stmt, Statement::Void))
+                .map(|stmt| CommentedStatement(stmt, trivia))
                 .format("\n"),
         );
         let continuing = self
             .continuing
             .as_ref()
-            .map(|cont| format!("{}\n", Indent(cont)))
+            .map(|cont| format!("{}\n", Indent(WithTrivia(&cont.value, trivia))))
             .unwrap_or_default();
         write!(f, "{attrs}loop {body_attrs}{{\n{stmts}\n{continuing}}}")
     }
 }
 
-impl Display for ContinuingStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for ContinuingStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let body_attrs = fmt_attrs(&self.body.attributes, false);
         let stmts = Indent(
             self.body
                 .statements
                 .iter()
                 // .filter(|stmt| !matches!(stmt, Statement::Void))
+                .map(|stmt| CommentedStatement(stmt, trivia))
                 .format("\n"),
         );
         let break_if = self
@@ -606,8 +783,8 @@ impl Display for ContinuingStatement {
 //     }
 // }
 
-impl Display for ForStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for ForStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, false);
         let mut init = self
             .initializer
@@ -630,40 +807,48 @@ impl Display for ForStatement {
         if updt.ends_with(';') {
             updt.pop();
         }
-        let body = &self.body;
+        let body = WithTrivia(&self.body.value, trivia);
         write!(f, "{attrs}for ({init}; {cond}; {updt}) {body}")
     }
 }
 
-impl Display for WhileStatement {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for WhileStatement {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, false);
         let cond = &self.condition;
-        let body = &self.body;
+        let body = WithTrivia(&self.body.value, trivia);
         write!(f, "{attrs}while ({cond}) {body}")
     }
 }
 
 // BEGIN MEW ADDITIONS
-impl Display for ModuleMemberDeclaration {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl FmtTrivia for ModuleMemberDeclaration {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         match self {
             &ModuleMemberDeclaration::Void => write!(f, ";"),
             ModuleMemberDeclaration::Declaration(print) => write!(f, "{}", print),
             ModuleMemberDeclaration::Alias(print) => write!(f, "{}", print),
-            ModuleMemberDeclaration::Struct(print) => write!(f, "{}", print),
-            ModuleMemberDeclaration::Function(print) => write!(f, "{}", print),
+            ModuleMemberDeclaration::Struct(print) => print.fmt_trivia(f, trivia),
+            ModuleMemberDeclaration::Function(print) => print.fmt_trivia(f, trivia),
             ModuleMemberDeclaration::ConstAssert(print) => write!(f, "{}", print),
-            ModuleMemberDeclaration::Module(print) => write!(f, "{}", print),
+            ModuleMemberDeclaration::Module(print) => print.fmt_trivia(f, trivia),
         }
     }
 }
 
-impl Display for Module {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl FmtTrivia for Module {
+    fn fmt_trivia(&self, f: &mut Formatter<'_>, trivia: Option<&TriviaMap>) -> fmt::Result {
         let attrs = fmt_attrs(&self.attributes, false);
-        let members = self.members.iter().format("\n\n");
-        let directives = self.directives.iter().format("\n");
+        let members = self
+            .members
+            .iter()
+            .map(|member| Commented(member, trivia, ""))
+            .format("\n\n");
+        let directives = self
+            .directives
+            .iter()
+            .map(|directive| Commented(directive, trivia, ""))
+            .format("\n");
         let mut template_params = String::new();
         if !self.template_parameters.is_empty() {
             template_params.push('<');
@@ -100,6 +100,30 @@ impl ModuleMemberDeclaration {
         }
     }
 
+    pub fn attributes(&self) -> Option<&Vec<S<Attribute>>> {
+        match self {
+            ModuleMemberDeclaration::Declaration(d) => Some(&d.attributes),
+            ModuleMemberDeclaration::Alias(a) => Some(&a.attributes),
+            ModuleMemberDeclaration::Struct(s) => Some(&s.attributes),
+            ModuleMemberDeclaration::Function(f) => Some(&f.attributes),
+            ModuleMemberDeclaration::Module(m) => Some(&m.attributes),
+            ModuleMemberDeclaration::Void => None,
+            ModuleMemberDeclaration::ConstAssert(_) => None,
+        }
+    }
+
+    pub fn attributes_mut(&mut self) -> Option<&mut Vec<S<Attribute>>> {
+        match self {
+            ModuleMemberDeclaration::Declaration(d) => Some(&mut d.attributes),
+            ModuleMemberDeclaration::Alias(a) => Some(&mut a.attributes),
+            ModuleMemberDeclaration::Struct(s) => Some(&mut s.attributes),
+            ModuleMemberDeclaration::Function(f) => Some(&mut f.attributes),
+            ModuleMemberDeclaration::Module(m) => Some(&mut m.attributes),
+            ModuleMemberDeclaration::Void => None,
+            ModuleMemberDeclaration::ConstAssert(_) => None,
+        }
+    }
+
     pub fn template_parameters_mut(&mut self) -> Option<&mut Vec<S<FormalTemplateParameter>>> {
         match self {
             ModuleMemberDeclaration::Struct(decl) => Some(&mut decl.template_parameters),
@@ -151,6 +175,30 @@ impl GlobalDeclaration {
         }
     }
 
+    pub fn attributes(&self) -> Option<&Vec<S<Attribute>>> {
+        match self {
+            GlobalDeclaration::Declaration(d) => Some(&d.attributes),
+            GlobalDeclaration::Alias(a) => Some(&a.attributes),
+            GlobalDeclaration::Struct(s) => Some(&s.attributes),
+            GlobalDeclaration::Function(f) => Some(&f.attributes),
+            GlobalDeclaration::Module(m) => Some(&m.attributes),
+            GlobalDeclaration::Void => None,
+            GlobalDeclaration::ConstAssert(_) => None,
+        }
+    }
+
+    pub fn attributes_mut(&mut self) -> Option<&mut Vec<S<Attribute>>> {
+        match self {
+            GlobalDeclaration::Declaration(d) => Some(&mut d.attributes),
+            GlobalDeclaration::Alias(a) => Some(&mut a.attributes),
+            GlobalDeclaration::Struct(s) => Some(&mut s.attributes),
+            GlobalDeclaration::Function(f) => Some(&mut f.attributes),
+            GlobalDeclaration::Module(m) => Some(&mut m.attributes),
+            GlobalDeclaration::Void => None,
+            GlobalDeclaration::ConstAssert(_) => None,
+        }
+    }
+
     pub fn template_parameters_mut(&mut self) -> Option<&mut Vec<S<FormalTemplateParameter>>> {
         match self {
             GlobalDeclaration::Struct(s) => Some(&mut s.template_parameters),
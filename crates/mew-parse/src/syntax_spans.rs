@@ -0,0 +1,243 @@
+//! Access to every span of a [syntax tree](crate::syntax), see [`SpansMut`].
+
+use crate::{
+    span::{Span, Spanned},
+    syntax::*,
+};
+
+/// Calls a function on every span of a syntax tree, e.g. to move a tree parsed on its own
+/// into the offsets of a [`SourceMap`](crate::span::SourceMap).
+pub trait SpansMut {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span));
+}
+
+impl<T: SpansMut> SpansMut for Spanned<T> {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        f(&mut self.span);
+        self.value.spans_mut(f);
+    }
+}
+
+impl<T: SpansMut> SpansMut for Vec<T> {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        for item in self {
+            item.spans_mut(f);
+        }
+    }
+}
+
+impl<T: SpansMut> SpansMut for Option<T> {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        if let Some(item) = self {
+            item.spans_mut(f);
+        }
+    }
+}
+
+impl<T: SpansMut> SpansMut for Box<T> {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        self.as_mut().spans_mut(f);
+    }
+}
+
+impl<A: SpansMut, B: SpansMut> SpansMut for (A, B) {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        self.0.spans_mut(f);
+        self.1.spans_mut(f);
+    }
+}
+
+/// Nodes without spans inside.
+macro_rules! leaves {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl SpansMut for $ty {
+                fn spans_mut(&mut self, _f: &mut dyn FnMut(&mut Span)) {}
+            }
+        )*
+    };
+}
+
+leaves!(
+    String,
+    DiagnosticSeverity,
+    DeclarationKind,
+    LiteralExpression,
+    UnaryOperator,
+    BinaryOperator,
+    AssignmentOperator,
+);
+
+/// Structs, by the fields that hold spans.
+macro_rules! fields {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl SpansMut for $ty {
+                fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+                    $(self.$field.spans_mut(f);)*
+                }
+            }
+        )*
+    };
+}
+
+fields! {
+    TranslationUnit { global_directives, global_declarations }
+    ExtendDirective { attributes, path }
+    DiagnosticDirective { severity, rule_name }
+    EnableDirective { extensions }
+    RequiresDirective { extensions }
+    Module { attributes, name, directives, members, template_parameters }
+    FormalTemplateParameter { name, default_value }
+    Declaration { attributes, kind, template_args, name, typ, initializer, template_parameters }
+    Alias { attributes, name, typ, template_parameters }
+    Struct { attributes, name, members, template_parameters }
+    StructMember { attributes, name, typ }
+    Function {
+        attributes,
+        name,
+        parameters,
+        return_attributes,
+        return_type,
+        body,
+        template_parameters,
+    }
+    FormalParameter { attributes, name, typ }
+    ConstAssert { expression, template_parameters }
+    Attribute { name, arguments }
+    NamedComponentExpression { base, component }
+    IndexingExpression { base, index }
+    UnaryExpression { operator, operand }
+    BinaryExpression { operator, left, right }
+    FunctionCallExpression { path, arguments }
+    PathPart { name, template_args, inline_template_args }
+    InlineTemplateArgs { directives, members }
+    IdentifierExpression { path }
+    TypeExpression { path }
+    TemplateArg { expression, arg_name }
+    CompoundStatement { attributes, directives, statements }
+    AssignmentStatement { operator, lhs, rhs }
+    IfStatement { attributes, if_clause, else_if_clauses, else_clause }
+    SwitchStatement { attributes, expression, body_attributes, clauses }
+    SwitchClause { case_selectors, body }
+    LoopStatement { attributes, body, continuing }
+    ContinuingStatement { body, break_if }
+    ForStatement { attributes, initializer, condition, update, body }
+    WhileStatement { attributes, condition, body }
+    DeclarationStatement { declaration, statements }
+    Use { attributes, path, content }
+    UseItem { name, rename, template_args, inline_template_args }
+}
+
+impl SpansMut for GlobalDirective {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            GlobalDirective::Diagnostic(directive) => directive.spans_mut(f),
+            GlobalDirective::Enable(directive) => directive.spans_mut(f),
+            GlobalDirective::Requires(directive) => directive.spans_mut(f),
+            GlobalDirective::Use(usage) => usage.spans_mut(f),
+            GlobalDirective::Extend(extend) => extend.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for ModuleDirective {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            ModuleDirective::Use(usage) => usage.spans_mut(f),
+            ModuleDirective::Extend(extend) => extend.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for CompoundDirective {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            CompoundDirective::Use(usage) => usage.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for GlobalDeclaration {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            GlobalDeclaration::Void => {}
+            GlobalDeclaration::Declaration(declaration) => declaration.spans_mut(f),
+            GlobalDeclaration::Alias(alias) => alias.spans_mut(f),
+            GlobalDeclaration::Struct(strct) => strct.spans_mut(f),
+            GlobalDeclaration::Function(function) => function.spans_mut(f),
+            GlobalDeclaration::ConstAssert(assert) => assert.spans_mut(f),
+            GlobalDeclaration::Module(module) => module.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for ModuleMemberDeclaration {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            ModuleMemberDeclaration::Void => {}
+            ModuleMemberDeclaration::Declaration(declaration) => declaration.spans_mut(f),
+            ModuleMemberDeclaration::Alias(alias) => alias.spans_mut(f),
+            ModuleMemberDeclaration::Struct(strct) => strct.spans_mut(f),
+            ModuleMemberDeclaration::Function(function) => function.spans_mut(f),
+            ModuleMemberDeclaration::ConstAssert(assert) => assert.spans_mut(f),
+            ModuleMemberDeclaration::Module(module) => module.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for Expression {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            Expression::Literal(literal) => literal.spans_mut(f),
+            Expression::Parenthesized(expression) => expression.spans_mut(f),
+            Expression::NamedComponent(expression) => expression.spans_mut(f),
+            Expression::Indexing(expression) => expression.spans_mut(f),
+            Expression::Unary(expression) => expression.spans_mut(f),
+            Expression::Binary(expression) => expression.spans_mut(f),
+            Expression::FunctionCall(expression) => expression.spans_mut(f),
+            Expression::Identifier(expression) => expression.spans_mut(f),
+            Expression::Type(expression) => expression.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for Statement {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            Statement::Void | Statement::Break | Statement::Continue | Statement::Discard => {}
+            Statement::Compound(statement) => statement.spans_mut(f),
+            Statement::Assignment(statement) => statement.spans_mut(f),
+            Statement::Increment(expression) | Statement::Decrement(expression) => {
+                expression.spans_mut(f)
+            }
+            Statement::If(statement) => statement.spans_mut(f),
+            Statement::Switch(statement) => statement.spans_mut(f),
+            Statement::Loop(statement) => statement.spans_mut(f),
+            Statement::For(statement) => statement.spans_mut(f),
+            Statement::While(statement) => statement.spans_mut(f),
+            Statement::Return(expression) => expression.spans_mut(f),
+            Statement::FunctionCall(call) => call.spans_mut(f),
+            Statement::ConstAssert(assert) => assert.spans_mut(f),
+            Statement::Declaration(statement) => statement.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for CaseSelector {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            CaseSelector::Default => {}
+            CaseSelector::Expression(expression) => expression.spans_mut(f),
+        }
+    }
+}
+
+impl SpansMut for UseContent {
+    fn spans_mut(&mut self, f: &mut dyn FnMut(&mut Span)) {
+        match self {
+            UseContent::Item(item) => item.spans_mut(f),
+            UseContent::Collection(usages) => usages.spans_mut(f),
+        }
+    }
+}
@@ -0,0 +1,579 @@
+//! Traversal of the [syntax tree](crate::syntax).
+//!
+//! [`Visit`](visit::Visit) walks a tree by reference and [`VisitMut`](visit_mut::VisitMut)
+//! by mutable reference. Every method defaults to the matching `walk_*` function, which
+//! visits the children of the node. Override a method to handle a node, and call the
+//! `walk_*` function from it to keep descending.
+//!
+//! Nodes are passed without their span, except paths. Names keep theirs.
+
+macro_rules! visitor {
+    ($visit:ident, $($mut:tt)?) => {
+        use crate::syntax::*;
+        use crate::span::Spanned;
+
+        pub trait $visit {
+            fn visit_translation_unit(&mut self, node: &$($mut)? TranslationUnit) {
+                walk_translation_unit(self, node)
+            }
+            fn visit_global_directive(&mut self, node: &$($mut)? GlobalDirective) {
+                walk_global_directive(self, node)
+            }
+            fn visit_module_directive(&mut self, node: &$($mut)? ModuleDirective) {
+                walk_module_directive(self, node)
+            }
+            fn visit_compound_directive(&mut self, node: &$($mut)? CompoundDirective) {
+                walk_compound_directive(self, node)
+            }
+            fn visit_extend_directive(&mut self, node: &$($mut)? ExtendDirective) {
+                walk_extend_directive(self, node)
+            }
+            fn visit_use(&mut self, node: &$($mut)? Use) {
+                walk_use(self, node)
+            }
+            fn visit_use_item(&mut self, node: &$($mut)? UseItem) {
+                walk_use_item(self, node)
+            }
+            fn visit_global_declaration(&mut self, node: &$($mut)? GlobalDeclaration) {
+                walk_global_declaration(self, node)
+            }
+            fn visit_module_member_declaration(
+                &mut self,
+                node: &$($mut)? ModuleMemberDeclaration,
+            ) {
+                walk_module_member_declaration(self, node)
+            }
+            fn visit_module(&mut self, node: &$($mut)? Module) {
+                walk_module(self, node)
+            }
+            fn visit_declaration(&mut self, node: &$($mut)? Declaration) {
+                walk_declaration(self, node)
+            }
+            fn visit_alias(&mut self, node: &$($mut)? Alias) {
+                walk_alias(self, node)
+            }
+            fn visit_struct(&mut self, node: &$($mut)? Struct) {
+                walk_struct(self, node)
+            }
+            fn visit_struct_member(&mut self, node: &$($mut)? StructMember) {
+                walk_struct_member(self, node)
+            }
+            fn visit_function(&mut self, node: &$($mut)? Function) {
+                walk_function(self, node)
+            }
+            fn visit_formal_parameter(&mut self, node: &$($mut)? FormalParameter) {
+                walk_formal_parameter(self, node)
+            }
+            fn visit_formal_template_parameter(
+                &mut self,
+                node: &$($mut)? FormalTemplateParameter,
+            ) {
+                walk_formal_template_parameter(self, node)
+            }
+            fn visit_const_assert(&mut self, node: &$($mut)? ConstAssert) {
+                walk_const_assert(self, node)
+            }
+            fn visit_attribute(&mut self, node: &$($mut)? Attribute) {
+                walk_attribute(self, node)
+            }
+            fn visit_expression(&mut self, node: &$($mut)? Expression) {
+                walk_expression(self, node)
+            }
+            fn visit_literal_expression(&mut self, _node: &$($mut)? LiteralExpression) {}
+            fn visit_function_call_expression(
+                &mut self,
+                node: &$($mut)? FunctionCallExpression,
+            ) {
+                walk_function_call_expression(self, node)
+            }
+            fn visit_identifier_expression(&mut self, node: &$($mut)? IdentifierExpression) {
+                walk_identifier_expression(self, node)
+            }
+            fn visit_type_expression(&mut self, node: &$($mut)? TypeExpression) {
+                walk_type_expression(self, node)
+            }
+            fn visit_path(&mut self, node: &$($mut)? Spanned<Vec<PathPart>>) {
+                walk_path(self, node)
+            }
+            fn visit_path_part(&mut self, node: &$($mut)? PathPart) {
+                walk_path_part(self, node)
+            }
+            fn visit_template_arg(&mut self, node: &$($mut)? TemplateArg) {
+                walk_template_arg(self, node)
+            }
+            fn visit_inline_template_args(&mut self, node: &$($mut)? InlineTemplateArgs) {
+                walk_inline_template_args(self, node)
+            }
+            fn visit_statement(&mut self, node: &$($mut)? Statement) {
+                walk_statement(self, node)
+            }
+            fn visit_compound_statement(&mut self, node: &$($mut)? CompoundStatement) {
+                walk_compound_statement(self, node)
+            }
+            fn visit_assignment_statement(&mut self, node: &$($mut)? AssignmentStatement) {
+                walk_assignment_statement(self, node)
+            }
+            fn visit_if_statement(&mut self, node: &$($mut)? IfStatement) {
+                walk_if_statement(self, node)
+            }
+            fn visit_switch_statement(&mut self, node: &$($mut)? SwitchStatement) {
+                walk_switch_statement(self, node)
+            }
+            fn visit_switch_clause(&mut self, node: &$($mut)? SwitchClause) {
+                walk_switch_clause(self, node)
+            }
+            fn visit_loop_statement(&mut self, node: &$($mut)? LoopStatement) {
+                walk_loop_statement(self, node)
+            }
+            fn visit_continuing_statement(&mut self, node: &$($mut)? ContinuingStatement) {
+                walk_continuing_statement(self, node)
+            }
+            fn visit_for_statement(&mut self, node: &$($mut)? ForStatement) {
+                walk_for_statement(self, node)
+            }
+            fn visit_while_statement(&mut self, node: &$($mut)? WhileStatement) {
+                walk_while_statement(self, node)
+            }
+            fn visit_declaration_statement(&mut self, node: &$($mut)? DeclarationStatement) {
+                walk_declaration_statement(self, node)
+            }
+        }
+
+        pub fn walk_translation_unit<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? TranslationUnit) {
+            for directive in &$($mut)? node.global_directives {
+                v.visit_global_directive(&$($mut)? directive.value);
+            }
+            for declaration in &$($mut)? node.global_declarations {
+                v.visit_global_declaration(&$($mut)? declaration.value);
+            }
+        }
+
+        pub fn walk_global_directive<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? GlobalDirective) {
+            match node {
+                GlobalDirective::Diagnostic(_)
+                | GlobalDirective::Enable(_)
+                | GlobalDirective::Requires(_) => {}
+                GlobalDirective::Use(usage) => v.visit_use(usage),
+                GlobalDirective::Extend(extend) => v.visit_extend_directive(extend),
+            }
+        }
+
+        pub fn walk_module_directive<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? ModuleDirective) {
+            match node {
+                ModuleDirective::Use(usage) => v.visit_use(usage),
+                ModuleDirective::Extend(extend) => v.visit_extend_directive(extend),
+            }
+        }
+
+        pub fn walk_compound_directive<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? CompoundDirective,
+        ) {
+            match node {
+                CompoundDirective::Use(usage) => v.visit_use(usage),
+            }
+        }
+
+        pub fn walk_extend_directive<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? ExtendDirective) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            v.visit_path(&$($mut)? node.path);
+        }
+
+        pub fn walk_use<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Use) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            v.visit_path(&$($mut)? node.path);
+            match &$($mut)? node.content.value {
+                UseContent::Item(item) => v.visit_use_item(item),
+                UseContent::Collection(collection) => {
+                    for usage in collection {
+                        v.visit_use(&$($mut)? usage.value);
+                    }
+                }
+            }
+        }
+
+        pub fn walk_use_item<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? UseItem) {
+            if let Some(args) = &$($mut)? node.template_args {
+                for arg in args {
+                    v.visit_template_arg(&$($mut)? arg.value);
+                }
+            }
+            if let Some(args) = &$($mut)? node.inline_template_args {
+                v.visit_inline_template_args(args);
+            }
+        }
+
+        pub fn walk_global_declaration<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? GlobalDeclaration,
+        ) {
+            match node {
+                GlobalDeclaration::Void => {}
+                GlobalDeclaration::Declaration(declaration) => v.visit_declaration(declaration),
+                GlobalDeclaration::Alias(alias) => v.visit_alias(alias),
+                GlobalDeclaration::Struct(strct) => v.visit_struct(strct),
+                GlobalDeclaration::Function(function) => v.visit_function(function),
+                GlobalDeclaration::ConstAssert(const_assert) => v.visit_const_assert(const_assert),
+                GlobalDeclaration::Module(module) => v.visit_module(module),
+            }
+        }
+
+        pub fn walk_module_member_declaration<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? ModuleMemberDeclaration,
+        ) {
+            match node {
+                ModuleMemberDeclaration::Void => {}
+                ModuleMemberDeclaration::Declaration(declaration) => {
+                    v.visit_declaration(declaration)
+                }
+                ModuleMemberDeclaration::Alias(alias) => v.visit_alias(alias),
+                ModuleMemberDeclaration::Struct(strct) => v.visit_struct(strct),
+                ModuleMemberDeclaration::Function(function) => v.visit_function(function),
+                ModuleMemberDeclaration::ConstAssert(const_assert) => {
+                    v.visit_const_assert(const_assert)
+                }
+                ModuleMemberDeclaration::Module(module) => v.visit_module(module),
+            }
+        }
+
+        pub fn walk_module<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Module) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            for param in &$($mut)? node.template_parameters {
+                v.visit_formal_template_parameter(&$($mut)? param.value);
+            }
+            for directive in &$($mut)? node.directives {
+                v.visit_module_directive(&$($mut)? directive.value);
+            }
+            for member in &$($mut)? node.members {
+                v.visit_module_member_declaration(&$($mut)? member.value);
+            }
+        }
+
+        pub fn walk_declaration<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Declaration) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            for param in &$($mut)? node.template_parameters {
+                v.visit_formal_template_parameter(&$($mut)? param.value);
+            }
+            if let Some(args) = &$($mut)? node.template_args {
+                for arg in args {
+                    v.visit_template_arg(&$($mut)? arg.value);
+                }
+            }
+            if let Some(typ) = &$($mut)? node.typ {
+                v.visit_type_expression(&$($mut)? typ.value);
+            }
+            if let Some(initializer) = &$($mut)? node.initializer {
+                v.visit_expression(&$($mut)? initializer.value);
+            }
+        }
+
+        pub fn walk_alias<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Alias) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            for param in &$($mut)? node.template_parameters {
+                v.visit_formal_template_parameter(&$($mut)? param.value);
+            }
+            v.visit_type_expression(&$($mut)? node.typ.value);
+        }
+
+        pub fn walk_struct<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Struct) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            for param in &$($mut)? node.template_parameters {
+                v.visit_formal_template_parameter(&$($mut)? param.value);
+            }
+            for member in &$($mut)? node.members {
+                v.visit_struct_member(&$($mut)? member.value);
+            }
+        }
+
+        pub fn walk_struct_member<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? StructMember) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            v.visit_type_expression(&$($mut)? node.typ.value);
+        }
+
+        pub fn walk_function<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Function) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            for param in &$($mut)? node.template_parameters {
+                v.visit_formal_template_parameter(&$($mut)? param.value);
+            }
+            for param in &$($mut)? node.parameters {
+                v.visit_formal_parameter(&$($mut)? param.value);
+            }
+            for attribute in &$($mut)? node.return_attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            if let Some(typ) = &$($mut)? node.return_type {
+                v.visit_type_expression(&$($mut)? typ.value);
+            }
+            v.visit_compound_statement(&$($mut)? node.body.value);
+        }
+
+        pub fn walk_formal_parameter<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? FormalParameter) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            v.visit_type_expression(&$($mut)? node.typ.value);
+        }
+
+        pub fn walk_formal_template_parameter<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? FormalTemplateParameter,
+        ) {
+            if let Some(default_value) = &$($mut)? node.default_value {
+                v.visit_expression(&$($mut)? default_value.value);
+            }
+        }
+
+        pub fn walk_const_assert<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? ConstAssert) {
+            for param in &$($mut)? node.template_parameters {
+                v.visit_formal_template_parameter(&$($mut)? param.value);
+            }
+            v.visit_expression(&$($mut)? node.expression.value);
+        }
+
+        pub fn walk_attribute<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Attribute) {
+            if let Some(arguments) = &$($mut)? node.arguments {
+                for arg in arguments {
+                    v.visit_expression(&$($mut)? arg.value);
+                }
+            }
+        }
+
+        pub fn walk_expression<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Expression) {
+            match node {
+                Expression::Literal(literal) => v.visit_literal_expression(&$($mut)? literal.value),
+                Expression::Parenthesized(expr) => v.visit_expression(&$($mut)? expr.value),
+                Expression::NamedComponent(named) => v.visit_expression(&$($mut)? named.base.value),
+                Expression::Indexing(indexing) => {
+                    v.visit_expression(&$($mut)? indexing.base.value);
+                    v.visit_expression(&$($mut)? indexing.index.value);
+                }
+                Expression::Unary(unary) => v.visit_expression(&$($mut)? unary.operand.value),
+                Expression::Binary(binary) => {
+                    v.visit_expression(&$($mut)? binary.left.value);
+                    v.visit_expression(&$($mut)? binary.right.value);
+                }
+                Expression::FunctionCall(call) => v.visit_function_call_expression(call),
+                Expression::Identifier(ident) => v.visit_identifier_expression(ident),
+                Expression::Type(typ) => v.visit_type_expression(typ),
+            }
+        }
+
+        pub fn walk_function_call_expression<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? FunctionCallExpression,
+        ) {
+            v.visit_path(&$($mut)? node.path);
+            for arg in &$($mut)? node.arguments {
+                v.visit_expression(&$($mut)? arg.value);
+            }
+        }
+
+        pub fn walk_identifier_expression<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? IdentifierExpression,
+        ) {
+            v.visit_path(&$($mut)? node.path);
+        }
+
+        pub fn walk_type_expression<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? TypeExpression) {
+            v.visit_path(&$($mut)? node.path);
+        }
+
+        pub fn walk_path<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Spanned<Vec<PathPart>>) {
+            for part in &$($mut)? node.value {
+                v.visit_path_part(part);
+            }
+        }
+
+        pub fn walk_path_part<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? PathPart) {
+            if let Some(args) = &$($mut)? node.template_args {
+                for arg in args {
+                    v.visit_template_arg(&$($mut)? arg.value);
+                }
+            }
+            if let Some(args) = &$($mut)? node.inline_template_args {
+                v.visit_inline_template_args(args);
+            }
+        }
+
+        pub fn walk_template_arg<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? TemplateArg) {
+            v.visit_expression(&$($mut)? node.expression.value);
+        }
+
+        pub fn walk_inline_template_args<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? InlineTemplateArgs,
+        ) {
+            for directive in &$($mut)? node.directives {
+                v.visit_module_directive(&$($mut)? directive.value);
+            }
+            for member in &$($mut)? node.members {
+                v.visit_module_member_declaration(&$($mut)? member.value);
+            }
+        }
+
+        pub fn walk_statement<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? Statement) {
+            match node {
+                Statement::Void | Statement::Break | Statement::Continue | Statement::Discard => {}
+                Statement::Compound(compound) => v.visit_compound_statement(compound),
+                Statement::Assignment(assignment) => v.visit_assignment_statement(assignment),
+                Statement::Increment(expr) | Statement::Decrement(expr) => v.visit_expression(expr),
+                Statement::If(iff) => v.visit_if_statement(iff),
+                Statement::Switch(switch) => v.visit_switch_statement(switch),
+                Statement::Loop(lop) => v.visit_loop_statement(lop),
+                Statement::For(fr) => v.visit_for_statement(fr),
+                Statement::While(whl) => v.visit_while_statement(whl),
+                Statement::Return(expr) => {
+                    if let Some(expr) = expr {
+                        v.visit_expression(&$($mut)? expr.value);
+                    }
+                }
+                Statement::FunctionCall(call) => v.visit_function_call_expression(call),
+                Statement::ConstAssert(const_assert) => v.visit_const_assert(const_assert),
+                Statement::Declaration(declaration) => v.visit_declaration_statement(declaration),
+            }
+        }
+
+        pub fn walk_compound_statement<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? CompoundStatement,
+        ) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            for directive in &$($mut)? node.directives {
+                v.visit_compound_directive(&$($mut)? directive.value);
+            }
+            for statement in &$($mut)? node.statements {
+                v.visit_statement(&$($mut)? statement.value);
+            }
+        }
+
+        pub fn walk_assignment_statement<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? AssignmentStatement,
+        ) {
+            v.visit_expression(&$($mut)? node.lhs.value);
+            v.visit_expression(&$($mut)? node.rhs.value);
+        }
+
+        pub fn walk_if_statement<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? IfStatement) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            let (condition, body) = &$($mut)? node.if_clause;
+            v.visit_expression(&$($mut)? condition.value);
+            v.visit_compound_statement(&$($mut)? body.value);
+            for (condition, body) in &$($mut)? node.else_if_clauses {
+                v.visit_expression(&$($mut)? condition.value);
+                v.visit_compound_statement(&$($mut)? body.value);
+            }
+            if let Some(body) = &$($mut)? node.else_clause {
+                v.visit_compound_statement(&$($mut)? body.value);
+            }
+        }
+
+        pub fn walk_switch_statement<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? SwitchStatement) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            v.visit_expression(&$($mut)? node.expression.value);
+            for attribute in &$($mut)? node.body_attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            for clause in &$($mut)? node.clauses {
+                v.visit_switch_clause(&$($mut)? clause.value);
+            }
+        }
+
+        pub fn walk_switch_clause<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? SwitchClause) {
+            for selector in &$($mut)? node.case_selectors {
+                if let CaseSelector::Expression(expr) = &$($mut)? selector.value {
+                    v.visit_expression(expr);
+                }
+            }
+            v.visit_compound_statement(&$($mut)? node.body.value);
+        }
+
+        pub fn walk_loop_statement<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? LoopStatement) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            v.visit_compound_statement(&$($mut)? node.body.value);
+            if let Some(continuing) = &$($mut)? node.continuing {
+                v.visit_continuing_statement(&$($mut)? continuing.value);
+            }
+        }
+
+        pub fn walk_continuing_statement<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? ContinuingStatement,
+        ) {
+            v.visit_compound_statement(&$($mut)? node.body.value);
+            if let Some(break_if) = &$($mut)? node.break_if {
+                v.visit_expression(&$($mut)? break_if.value);
+            }
+        }
+
+        pub fn walk_for_statement<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? ForStatement) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            if let Some(initializer) = &$($mut)? node.initializer {
+                v.visit_statement(&$($mut)? initializer.value);
+            }
+            if let Some(condition) = &$($mut)? node.condition {
+                v.visit_expression(&$($mut)? condition.value);
+            }
+            if let Some(update) = &$($mut)? node.update {
+                v.visit_statement(&$($mut)? update.value);
+            }
+            v.visit_compound_statement(&$($mut)? node.body.value);
+        }
+
+        pub fn walk_while_statement<V: $visit + ?Sized>(v: &mut V, node: &$($mut)? WhileStatement) {
+            for attribute in &$($mut)? node.attributes {
+                v.visit_attribute(&$($mut)? attribute.value);
+            }
+            v.visit_expression(&$($mut)? node.condition.value);
+            v.visit_compound_statement(&$($mut)? node.body.value);
+        }
+
+        pub fn walk_declaration_statement<V: $visit + ?Sized>(
+            v: &mut V,
+            node: &$($mut)? DeclarationStatement,
+        ) {
+            v.visit_declaration(&$($mut)? node.declaration.value);
+            for statement in &$($mut)? node.statements {
+                v.visit_statement(&$($mut)? statement.value);
+            }
+        }
+    };
+}
+
+/// Traversal by reference, see [`Visit`](visit::Visit).
+pub mod visit {
+    visitor!(Visit,);
+}
+
+/// Traversal by mutable reference, see [`VisitMut`](visit_mut::VisitMut).
+pub mod visit_mut {
+    visitor!(VisitMut, mut);
+}
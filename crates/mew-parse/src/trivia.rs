@@ -0,0 +1,240 @@
+//! Comments are not part of the [syntax tree](crate::syntax). In lossless mode (see
+//! [`crate::Parser::parse_str_lossless`]) the lexer records them, and they are attached to
+//! the closest directive, declaration, module member, struct member or statement in a
+//! [`TriviaMap`]. [`crate::syntax::TranslationUnit::display_with_trivia`] prints them back.
+
+use std::collections::HashMap;
+
+use crate::{
+    span::{Span, Spanned},
+    syntax::{
+        visit, CompoundStatement, DeclarationStatement, InlineTemplateArgs, Module, Struct,
+        TranslationUnit, Use, Visit,
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommentKind {
+    /// `// ...`
+    Line,
+    /// `/* ... */`
+    Block,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    pub kind: CommentKind,
+    /// The comment, including the delimiters. Continuation lines of block comments are
+    /// stripped of the indentation of the first line.
+    pub text: String,
+    pub span: Span,
+    /// The comment is the first thing on its line.
+    pub own_line: bool,
+}
+
+/// The comments attached to a node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trivia {
+    /// Comments before the node.
+    pub leading: Vec<Comment>,
+    /// Comments after the node, before the next one.
+    pub trailing: Vec<Comment>,
+}
+
+/// The comments of a translation unit, attached to the span of the node they belong to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TriviaMap {
+    pub nodes: HashMap<Span, Trivia>,
+    /// Comments that could not be attached to a node, e.g. in a file without declarations.
+    pub dangling: Vec<Comment>,
+}
+
+impl TriviaMap {
+    pub fn get(&self, span: &Span) -> Option<&Trivia> {
+        self.nodes.get(span)
+    }
+
+    pub fn leading(&self, span: &Span) -> &[Comment] {
+        self.get(span)
+            .map(|t| t.leading.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn trailing(&self, span: &Span) -> &[Comment] {
+        self.get(span)
+            .map(|t| t.trailing.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Attaches each comment to a node of `translation_unit`:
+    /// * after a node on the same line: trailing comment of that node,
+    /// * before a node: leading comment of that node,
+    /// * after the last node of a block: trailing comment of that node,
+    /// * anywhere else: leading comment of the innermost node that contains it.
+    ///
+    /// `source` is the text that was parsed at `offset`.
+    pub(crate) fn new(
+        translation_unit: &TranslationUnit,
+        comments: Vec<Comment>,
+        source: &str,
+        offset: usize,
+    ) -> Self {
+        let mut anchors = Anchors(vec![]);
+        anchors.visit_translation_unit(translation_unit);
+        let anchors = anchors.0;
+
+        // the source without comments, to find what separates comments and nodes.
+        let mut blanked = source.to_string();
+        for comment in &comments {
+            let range = (comment.span.start - offset)..(comment.span.end - offset);
+            blanked.replace_range(range.clone(), &" ".repeat(range.len()));
+        }
+        let between = |start: usize, end: usize| -> &str {
+            if start <= end {
+                &blanked[start - offset..end - offset]
+            } else {
+                ""
+            }
+        };
+
+        let mut map = TriviaMap::default();
+        for comment in comments {
+            let previous = anchors
+                .iter()
+                .filter(|a| a.end <= comment.span.start)
+                .filter(|a| {
+                    between(a.end, comment.span.start)
+                        .chars()
+                        .all(|c| c.is_whitespace() || c == ',')
+                })
+                .max_by_key(|a| (a.end, a.start));
+            let next = anchors
+                .iter()
+                .filter(|a| a.start >= comment.span.end)
+                .filter(|a| between(comment.span.end, a.start).trim().is_empty())
+                .min_by_key(|a| (a.start, usize::MAX - a.end));
+            let containing = anchors
+                .iter()
+                .filter(|a| a.start <= comment.span.start && comment.span.end <= a.end)
+                .min_by_key(|a| a.len());
+
+            let same_line = previous.is_some_and(|a| {
+                !source[a.end - offset..comment.span.start - offset].contains(['\n', '\r'])
+            });
+            let (anchor, trailing) = match (previous, next) {
+                (Some(previous), _) if same_line => (previous, true),
+                (_, Some(next)) => (next, false),
+                (Some(previous), None) => (previous, true),
+                (None, None) => match containing {
+                    Some(containing) => (containing, false),
+                    None => {
+                        map.dangling.push(comment);
+                        continue;
+                    }
+                },
+            };
+            let trivia = map.nodes.entry(anchor.clone()).or_default();
+            if trailing {
+                trivia.trailing.push(comment);
+            } else {
+                trivia.leading.push(comment);
+            }
+        }
+        map
+    }
+}
+
+/// Collects the spans of the nodes that comments can be attached to.
+struct Anchors(Vec<Span>);
+
+impl Anchors {
+    fn extend<T>(&mut self, nodes: &[Spanned<T>]) {
+        self.0.extend(nodes.iter().map(|node| node.span()));
+    }
+}
+
+impl Visit for Anchors {
+    fn visit_translation_unit(&mut self, node: &TranslationUnit) {
+        self.extend(&node.global_directives);
+        self.extend(&node.global_declarations);
+        visit::walk_translation_unit(self, node);
+    }
+
+    fn visit_module(&mut self, node: &Module) {
+        self.extend(&node.directives);
+        self.extend(&node.members);
+        visit::walk_module(self, node);
+    }
+
+    fn visit_struct(&mut self, node: &Struct) {
+        self.extend(&node.members);
+        visit::walk_struct(self, node);
+    }
+
+    fn visit_compound_statement(&mut self, node: &CompoundStatement) {
+        self.extend(&node.directives);
+        self.extend(&node.statements);
+        visit::walk_compound_statement(self, node);
+    }
+
+    fn visit_declaration_statement(&mut self, node: &DeclarationStatement) {
+        self.extend(&node.statements);
+        visit::walk_declaration_statement(self, node);
+    }
+
+    // inline template arguments are printed without comments.
+    fn visit_inline_template_args(&mut self, _node: &InlineTemplateArgs) {}
+
+    fn visit_use(&mut self, _node: &Use) {}
+}
+
+/// Finds the comments in `source[start..end]`, the text between two tokens. `offset` is
+/// added to the spans.
+pub(crate) fn scan_comments(
+    source: &str,
+    start: usize,
+    end: usize,
+    offset: usize,
+    comments: &mut Vec<Comment>,
+) {
+    let mut pos = start;
+    while pos < end {
+        let rest = &source[pos..end];
+        let trimmed = rest.trim_start();
+        pos += rest.len() - trimmed.len();
+        let (kind, len) = if trimmed.starts_with("//") {
+            let len = trimmed.find(['\n', '\r']).unwrap_or(trimmed.len());
+            (CommentKind::Line, len)
+        } else if let Some(body) = trimmed.strip_prefix("/*") {
+            let len = body
+                .find("*/")
+                .map(|i| i + 4)
+                .unwrap_or(trimmed.len());
+            (CommentKind::Block, len)
+        } else {
+            return;
+        };
+        let line_start = source[..pos]
+            .rfind(['\n', '\r'])
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let indent = &source[line_start..pos];
+        let own_line = indent.trim().is_empty();
+        let text = trimmed[..len]
+            .lines()
+            .enumerate()
+            .map(|(i, line)| match line.strip_prefix(indent) {
+                Some(line) if i > 0 && own_line => line,
+                _ => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        comments.push(Comment {
+            kind,
+            text,
+            span: (pos + offset)..(pos + len + offset),
+            own_line,
+        });
+        pos += len;
+    }
+}
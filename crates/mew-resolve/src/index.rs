@@ -0,0 +1,430 @@
+//! An index of the declarations of a translation unit and of every reference to them, for
+//! tools that audit or refactor shaders without going through the compiler.
+
+use std::collections::{BTreeMap, HashMap};
+
+use mew_parse::{
+    span::{Span, Spanned},
+    syntax::{
+        visit, Alias, Declaration, FormalTemplateParameter, Function, GlobalDeclaration,
+        GlobalDirective, InlineTemplateArgs, Module, ModuleDirective, ModuleMemberDeclaration,
+        PathPart, Struct, TemplateArg, TranslationUnit, Use, UseContent, Visit,
+    },
+};
+use mew_types::{CompilerPass, CompilerPassError};
+
+use crate::{resolver::is_path_root, Resolutions, Resolver};
+
+/// A reference to a declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// The span of the path segment, or of the `use` item, that refers to the declaration.
+    pub span: Span,
+    /// Whether the segment names the declaration by another name, that of a `use ... as`
+    /// item. Renaming the declaration leaves these references as they are.
+    pub renamed: bool,
+}
+
+/// A declaration of a [`SymbolIndex`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The span of the name of the declaration, which is empty for the modules of whole
+    /// files, since they are named after their file.
+    pub definition: Span,
+    /// The references to the declaration, in the order of their spans.
+    pub references: Vec<Reference>,
+}
+
+/// The functions, declarations, structs, aliases and modules of a translation unit by
+/// absolute path, e.g. `["lighting", "Light"]`, with every reference to them. Template
+/// parameters are indexed under the path of their declaration, e.g. `["lighting", "Tinted",
+/// "factor"]` for `mod Tinted<factor>`, and are also referenced by the members of the
+/// `with { }` blocks that bind them.
+///
+/// The aliases that `extend` directives generate are not indexed: the paths that go through
+/// them are references to the extended declarations. Spans are those of the translation
+/// unit, so global to the source map of a bundled one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SymbolIndex(BTreeMap<Vec<String>, Symbol>);
+
+impl SymbolIndex {
+    pub fn get(&self, path: &[String]) -> Option<&Symbol> {
+        self.0.get(path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[String], &Symbol)> {
+        self.0
+            .iter()
+            .map(|(path, symbol)| (path.as_slice(), symbol))
+    }
+
+    /// The declaration whose name, or one of the references to it, contains `offset`,
+    /// including the offset just past the end of the name.
+    pub fn at(&self, offset: usize) -> Option<(&[String], &Symbol)> {
+        let contains = |span: &Span| span.start <= offset && offset <= span.end;
+        self.iter().find(|(_, symbol)| {
+            (!symbol.definition.is_empty() && contains(&symbol.definition))
+                || symbol
+                    .references
+                    .iter()
+                    .any(|reference| contains(&reference.span))
+        })
+    }
+}
+
+fn names(path: &[PathPart]) -> Vec<String> {
+    path.iter().map(|part| part.name.value.clone()).collect()
+}
+
+/// The spans of the `extend` directives, which are also the spans of the aliases they
+/// generate.
+fn extensions<'a>(directives: impl IntoIterator<Item = (&'a ModuleDirective, Span)>) -> Vec<Span> {
+    directives
+        .into_iter()
+        .filter_map(|(directive, span)| match directive {
+            ModuleDirective::Extend(_) => Some(span),
+            ModuleDirective::Use(_) => None,
+        })
+        .collect()
+}
+
+fn is_generated(member: &Spanned<ModuleMemberDeclaration>, extensions: &[Span]) -> bool {
+    matches!(member.value, ModuleMemberDeclaration::Alias(_)) && extensions.contains(&member.span())
+}
+
+/// The names of the template parameters of the source, by span, since the resolver gives
+/// them names unique to the translation unit.
+#[derive(Default)]
+struct ParameterNames(HashMap<Span, String>);
+
+impl Visit for ParameterNames {
+    fn visit_formal_template_parameter(&mut self, parameter: &FormalTemplateParameter) {
+        self.0
+            .insert(parameter.name.span(), parameter.name.value.clone());
+        visit::walk_formal_template_parameter(self, parameter);
+    }
+}
+
+/// Collects the declarations of the resolved translation unit, and where the aliases
+/// generated by `extend` directives lead.
+#[derive(Default)]
+struct Declarations {
+    module_path: Vec<String>,
+    parameter_names: ParameterNames,
+    index: SymbolIndex,
+    forwards: HashMap<Vec<String>, Vec<String>>,
+    /// The paths of the template parameters, by resolved name.
+    parameters: HashMap<String, Vec<String>>,
+}
+
+impl Declarations {
+    fn define(&mut self, name: &Spanned<String>) {
+        let mut path = self.module_path.clone();
+        path.push(name.value.clone());
+        let symbol = Symbol {
+            definition: name.span(),
+            references: vec![],
+        };
+        self.index.0.insert(path, symbol);
+    }
+
+    fn define_parameters(
+        &mut self,
+        owner: &Spanned<String>,
+        parameters: &[Spanned<FormalTemplateParameter>],
+    ) {
+        for parameter in parameters {
+            let Some(name) = self.parameter_names.0.get(&parameter.name.span()) else {
+                continue;
+            };
+            let mut path = self.module_path.clone();
+            path.push(owner.value.clone());
+            path.push(name.clone());
+            let symbol = Symbol {
+                definition: parameter.name.span(),
+                references: vec![],
+            };
+            self.parameters
+                .insert(parameter.name.value.clone(), path.clone());
+            self.index.0.insert(path, symbol);
+        }
+    }
+
+    fn forward(&mut self, member: &ModuleMemberDeclaration) {
+        if let ModuleMemberDeclaration::Alias(alias) = member {
+            let mut path = self.module_path.clone();
+            path.push(alias.name.value.clone());
+            self.forwards.insert(path, names(&alias.typ.path));
+        }
+    }
+
+    fn members(&mut self, members: &[Spanned<ModuleMemberDeclaration>], extensions: &[Span]) {
+        for member in members {
+            if is_generated(member, extensions) {
+                self.forward(member);
+            } else if !matches!(member.value, ModuleMemberDeclaration::Module(_)) {
+                if let Some(name) = member.name() {
+                    self.define(&name);
+                }
+            }
+        }
+    }
+}
+
+impl Visit for Declarations {
+    fn visit_translation_unit(&mut self, translation_unit: &TranslationUnit) {
+        let extensions = translation_unit
+            .global_directives
+            .iter()
+            .filter_map(|directive| match directive.value {
+                GlobalDirective::Extend(_) => Some(directive.span()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for declaration in &translation_unit.global_declarations {
+            match &declaration.value {
+                GlobalDeclaration::Alias(alias) if extensions.contains(&declaration.span()) => {
+                    let path = vec![alias.name.value.clone()];
+                    self.forwards.insert(path, names(&alias.typ.path));
+                }
+                GlobalDeclaration::Module(_) => {}
+                other => {
+                    if let Some(name) = other.name() {
+                        self.define(&name);
+                    }
+                }
+            }
+        }
+        visit::walk_translation_unit(self, translation_unit);
+    }
+
+    fn visit_module(&mut self, module: &Module) {
+        self.define(&module.name);
+        self.define_parameters(&module.name, &module.template_parameters);
+        self.module_path.push(module.name.value.clone());
+        let extensions = extensions(
+            module
+                .directives
+                .iter()
+                .map(|directive| (&directive.value, directive.span())),
+        );
+        self.members(&module.members, &extensions);
+        visit::walk_module(self, module);
+        self.module_path.pop();
+    }
+
+    fn visit_declaration(&mut self, declaration: &Declaration) {
+        self.define_parameters(&declaration.name, &declaration.template_parameters);
+        visit::walk_declaration(self, declaration);
+    }
+
+    fn visit_alias(&mut self, alias: &Alias) {
+        self.define_parameters(&alias.name, &alias.template_parameters);
+        visit::walk_alias(self, alias);
+    }
+
+    fn visit_struct(&mut self, s: &Struct) {
+        self.define_parameters(&s.name, &s.template_parameters);
+        visit::walk_struct(self, s);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.define_parameters(&function.name, &function.template_parameters);
+        visit::walk_function(self, function);
+    }
+
+    fn visit_inline_template_args(&mut self, args: &InlineTemplateArgs) {
+        // the other members are template arguments, not declarations
+        let extensions = extensions(
+            args.directives
+                .iter()
+                .map(|directive| (&directive.value, directive.span())),
+        );
+        for member in &args.members {
+            if is_generated(member, &extensions) {
+                self.forward(&member.value);
+            }
+        }
+        visit::walk_inline_template_args(self, args);
+    }
+}
+
+/// Collects the references of the resolved translation unit, by matching each of its paths
+/// with the path of the same span in the source.
+struct References<'a> {
+    source: &'a Resolutions,
+    forwards: &'a HashMap<Vec<String>, Vec<String>>,
+    parameters: &'a HashMap<String, Vec<String>>,
+    index: &'a mut SymbolIndex,
+}
+
+impl References<'_> {
+    /// `path` without the aliases generated by `extend` directives.
+    fn canonical(&self, mut path: Vec<String>) -> Vec<String> {
+        // bounded, in case of a cycle of extensions
+        for _ in 0..=self.forwards.len() {
+            let Some((len, target)) =
+                (1..=path.len()).find_map(|len| Some((len, self.forwards.get(&path[..len])?)))
+            else {
+                break;
+            };
+            let mut forwarded = target.clone();
+            forwarded.extend(path.drain(len..));
+            path = forwarded;
+        }
+        path
+    }
+
+    /// Adds a reference to the declaration at the resolved `path`, which the source names
+    /// `name` at `span`.
+    fn refer(&mut self, path: Vec<String>, name: &str, span: Span) {
+        let path = match path.as_slice() {
+            [parameter] if self.parameters.contains_key(parameter) => {
+                self.parameters[parameter].clone()
+            }
+            _ => self.canonical(path),
+        };
+        if span.is_empty() {
+            return;
+        }
+        let Some(symbol) = self.index.0.get_mut(&path) else {
+            return;
+        };
+        // the resolver passes the parameters of modules as arguments named after them
+        let is_known = symbol.definition == span
+            || symbol
+                .references
+                .iter()
+                .any(|reference| reference.span == span);
+        if !is_known {
+            let renamed = path.last().is_some_and(|last| last != name);
+            symbol.references.push(Reference { span, renamed });
+        }
+    }
+
+    /// The segments of `source` are the last segments of `resolved`: the resolver prepends
+    /// the path of the module or of the `use` item that the first segment names.
+    fn refer_path(&mut self, source: &[PathPart], resolved: &[PathPart]) {
+        // the resolver replaces the root of a path, e.g. `super::`, by the module it names
+        let roots = source
+            .iter()
+            .take_while(|part| is_path_root(&part.name.value))
+            .count();
+        let source = &source[roots..];
+        let Some(skipped) = resolved.len().checked_sub(source.len()) else {
+            return;
+        };
+        for (i, part) in source.iter().enumerate() {
+            if i > 0 && part.name.value != resolved[skipped + i].name.value {
+                // synthesized by the resolver, e.g. the arguments of inline template args
+                return;
+            }
+            let path = names(&resolved[..=skipped + i]);
+            self.refer(path, &part.name.value, part.name.span());
+        }
+    }
+}
+
+impl Visit for References<'_> {
+    fn visit_path(&mut self, path: &Spanned<Vec<PathPart>>) {
+        if let Some(source) = self.source.get(&path.span()) {
+            self.refer_path(source, &path.value);
+        }
+        visit::walk_path(self, path);
+    }
+
+    fn visit_use(&mut self, usage: &Use) {
+        visit::walk_use(self, usage);
+        if let UseContent::Item(item) = &usage.content.value {
+            let mut path = names(&usage.path);
+            path.push(item.name.value.clone());
+            self.refer(path, &item.name.value, item.name.span());
+        }
+    }
+
+    fn visit_template_arg(&mut self, arg: &TemplateArg) {
+        // the members of `with { }` blocks are arguments named after their parameter
+        if let Some(name) = &arg.arg_name {
+            if let Some(path) = self.parameters.get(&name.value) {
+                let parameter = path.last().cloned().unwrap_or_default();
+                self.refer(vec![name.value.clone()], &parameter, name.span());
+            }
+        }
+        visit::walk_template_arg(self, arg);
+    }
+
+    fn visit_module(&mut self, module: &Module) {
+        let extensions = extensions(
+            module
+                .directives
+                .iter()
+                .map(|directive| (&directive.value, directive.span())),
+        );
+        for attribute in &module.attributes {
+            self.visit_attribute(&attribute.value);
+        }
+        for param in &module.template_parameters {
+            self.visit_formal_template_parameter(&param.value);
+        }
+        for directive in &module.directives {
+            self.visit_module_directive(&directive.value);
+        }
+        for member in &module.members {
+            // the path of a generated alias is the path of its `extend` directive
+            if !is_generated(member, &extensions) {
+                self.visit_module_member_declaration(&member.value);
+            }
+        }
+    }
+
+    fn visit_inline_template_args(&mut self, args: &InlineTemplateArgs) {
+        let extensions = extensions(
+            args.directives
+                .iter()
+                .map(|directive| (&directive.value, directive.span())),
+        );
+        for directive in &args.directives {
+            self.visit_module_directive(&directive.value);
+        }
+        for member in &args.members {
+            if !is_generated(member, &extensions) {
+                self.visit_module_member_declaration(&member.value);
+            }
+        }
+    }
+}
+
+impl Resolver {
+    /// Resolves a copy of `translation_unit` and indexes its declarations and the
+    /// references to them, along with the errors of the resolution. The references in a
+    /// scope that fails to resolve are missing from the index.
+    pub fn index(
+        &self,
+        translation_unit: &TranslationUnit,
+    ) -> (SymbolIndex, Result<(), CompilerPassError>) {
+        let mut source = Resolutions::default();
+        source.visit_translation_unit(translation_unit);
+        let mut resolved = translation_unit.clone();
+        let result = Resolver.apply_mut(&mut resolved);
+
+        let mut declarations = Declarations::default();
+        declarations
+            .parameter_names
+            .visit_translation_unit(translation_unit);
+        declarations.visit_translation_unit(&resolved);
+        let mut index = declarations.index;
+        let mut references = References {
+            source: &source,
+            forwards: &declarations.forwards,
+            parameters: &declarations.parameters,
+            index: &mut index,
+        };
+        references.visit_translation_unit(&resolved);
+        for symbol in index.0.values_mut() {
+            symbol
+                .references
+                .sort_by_key(|reference| reference.span.start);
+        }
+        (index, result)
+    }
+}
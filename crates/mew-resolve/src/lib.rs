@@ -1,2 +1,7 @@
+pub mod index;
+pub mod resolutions;
 pub mod resolver;
+mod visibility;
+pub use index::SymbolIndex;
+pub use resolutions::Resolutions;
 pub use resolver::*;
@@ -0,0 +1,86 @@
+//! The absolute paths computed by the [`Resolver`], for tools that need to know what a path
+//! refers to without rewriting the translation unit, e.g. a language server.
+
+use std::collections::HashMap;
+
+use mew_parse::{
+    span::{Span, Spanned},
+    syntax::{visit, PathPart, TranslationUnit, Use, UseContent, Visit},
+};
+use mew_types::{CompilerPass, CompilerPassError};
+
+use crate::Resolver;
+
+/// The absolute path of every path of a translation unit, keyed by the span of the path.
+/// The items of `use` directives are keyed by the span of their name, and of their rename.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Resolutions(HashMap<Span, Vec<PathPart>>);
+
+impl Resolutions {
+    /// The absolute path of the path spanning `span`.
+    pub fn get(&self, span: &Span) -> Option<&[PathPart]> {
+        self.0.get(span).map(Vec::as_slice)
+    }
+
+    /// The innermost path containing `offset`, including the offset just past its end, and
+    /// its absolute path.
+    pub fn at(&self, offset: usize) -> Option<(&Span, &[PathPart])> {
+        self.0
+            .iter()
+            .filter(|(span, _)| span.start <= offset && offset <= span.end)
+            .min_by_key(|(span, _)| span.len())
+            .map(|(span, path)| (span, path.as_slice()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Span, &[PathPart])> {
+        self.0.iter().map(|(span, path)| (span, path.as_slice()))
+    }
+
+    fn insert(&mut self, span: Span, path: Vec<PathPart>) {
+        // synthesized nodes, e.g. the aliases of `extend` directives, reuse the spans of
+        // the paths they were made from
+        if !span.is_empty() {
+            self.0.entry(span).or_insert(path);
+        }
+    }
+}
+
+impl Visit for Resolutions {
+    fn visit_path(&mut self, path: &Spanned<Vec<PathPart>>) {
+        self.insert(path.span(), path.value.clone());
+        visit::walk_path(self, path);
+    }
+
+    fn visit_use(&mut self, usage: &Use) {
+        visit::walk_use(self, usage);
+        if let UseContent::Item(item) = &usage.content.value {
+            let mut path = usage.path.value.clone();
+            path.push(PathPart {
+                name: item.name.clone(),
+                template_args: item.template_args.clone(),
+                inline_template_args: item.inline_template_args.clone(),
+            });
+            if let Some(rename) = &item.rename {
+                self.insert(rename.span(), path.clone());
+            }
+            self.insert(item.name.span(), path);
+        }
+    }
+}
+
+impl Resolver {
+    /// Resolves a copy of `translation_unit` and returns the absolute path of every path it
+    /// contains, along with the errors of the resolution. Paths that are not resolved
+    /// because of an earlier error in their scope keep their relative path, and so do the
+    /// paths of local declarations and builtins.
+    pub fn resolutions(
+        &self,
+        translation_unit: &TranslationUnit,
+    ) -> (Resolutions, Result<(), CompilerPassError>) {
+        let mut resolved = translation_unit.clone();
+        let result = Resolver.apply_mut(&mut resolved);
+        let mut resolutions = Resolutions::default();
+        resolutions.visit_translation_unit(&resolved);
+        (resolutions, result)
+    }
+}
@@ -12,9 +12,21 @@ use mew_parse::{
 use mew_types::{
     builtins::{get_builtin_functions, get_builtin_tokens},
     mangling::mangle_inline_arg_name,
-    CompilerPass, CompilerPassError,
+    CompilerPass, CompilerPassError, ErrorCollector,
 };
 
+use crate::visibility::{check_visibility, is_private};
+
+/// The roots of paths: `self::` is the module of the path, `super::` its parent, and
+/// `package::` the translation unit.
+pub const SELF_ROOT: &str = "self";
+pub const SUPER_ROOT: &str = "super";
+pub const PACKAGE_ROOT: &str = "package";
+
+pub fn is_path_root(name: &str) -> bool {
+    matches!(name, SELF_ROOT | SUPER_ROOT | PACKAGE_ROOT)
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Resolver;
 
@@ -31,6 +43,9 @@ enum ScopeMember {
     FormalFunctionParameter,
     TemplateParam(String),
     Inline(ModulePath),
+    /// The declarations of the translation unit, in scope as `package`, which no local
+    /// declaration can shadow since it is a keyword.
+    Package(im::HashMap<String, ScopeMember>),
 }
 
 impl Resolver {
@@ -39,13 +54,22 @@ impl Resolver {
         module_path: ModulePath,
         mut scope: im::HashMap<String, ScopeMember>,
     ) -> Result<(), CompilerPassError> {
+        let mut errors = ErrorCollector::default();
         for CompoundDirective::Use(usage) in statement.directives.iter_mut().map(|x| &mut x.value) {
-            Self::add_usage_to_scope(usage, module_path.clone(), &mut scope)?;
+            errors.check(Self::add_usage_to_scope(
+                usage,
+                module_path.clone(),
+                &mut scope,
+            ));
         }
         for c in statement.statements.iter_mut() {
-            Self::statement_to_absolute_paths(c, module_path.clone(), scope.clone())?;
+            errors.check(Self::statement_to_absolute_paths(
+                c,
+                module_path.clone(),
+                scope.clone(),
+            ));
         }
-        Ok(())
+        Ok(errors.finish()?)
     }
 
     fn statement_to_absolute_paths(
@@ -232,17 +256,32 @@ impl Resolver {
                 )?;
             }
             Statement::Declaration(d) => {
+                // the rest of the block is nested in the declaration, so keep going after errors
+                let mut errors = ErrorCollector::default();
                 if let Some(init) = d.declaration.initializer.as_mut() {
-                    Self::expression_to_absolute_paths(init, module_path.clone(), scope.clone())?;
+                    errors.check(Self::expression_to_absolute_paths(
+                        init,
+                        module_path.clone(),
+                        scope.clone(),
+                    ));
                 }
                 if let Some(typ) = d.declaration.typ.as_mut() {
-                    Self::type_to_absolute_path(typ, module_path.clone(), scope.clone())?;
+                    errors.check(Self::type_to_absolute_path(
+                        typ,
+                        module_path.clone(),
+                        scope.clone(),
+                    ));
                 };
                 let name = d.declaration.name.value.clone();
                 scope.insert(name, ScopeMember::LocalDeclaration);
                 for s in d.statements.iter_mut() {
-                    Self::statement_to_absolute_paths(s, module_path.clone(), scope.clone())?;
+                    errors.check(Self::statement_to_absolute_paths(
+                        s,
+                        module_path.clone(),
+                        scope.clone(),
+                    ));
                 }
+                return Ok(errors.finish()?);
             }
         };
         Ok(())
@@ -308,32 +347,34 @@ impl Resolver {
             &mut scope,
         )?;
 
+        let mut errors = ErrorCollector::default();
         for decl in module.members.iter_mut() {
-            match decl.as_mut() {
+            errors.check(match decl.as_mut() {
                 ModuleMemberDeclaration::Void => {
                     // NO ACTION REQUIRED REQUIRED
+                    Ok(())
                 }
                 ModuleMemberDeclaration::Declaration(decl) => {
-                    Self::decl_to_absolute_path(decl, module_path.clone(), scope.clone())?;
+                    Self::decl_to_absolute_path(decl, module_path.clone(), scope.clone())
                 }
                 ModuleMemberDeclaration::Alias(a) => {
-                    Self::alias_to_absolute_path(a, module_path.clone(), scope.clone())?;
+                    Self::alias_to_absolute_path(a, module_path.clone(), scope.clone())
                 }
                 ModuleMemberDeclaration::Struct(s) => {
-                    Self::struct_to_absolute_path(s, module_path.clone(), scope.clone())?;
+                    Self::struct_to_absolute_path(s, module_path.clone(), scope.clone())
                 }
                 ModuleMemberDeclaration::Function(f) => {
-                    Self::func_to_absolute_path(f, module_path.clone(), scope.clone())?;
+                    Self::func_to_absolute_path(f, module_path.clone(), scope.clone())
                 }
                 ModuleMemberDeclaration::ConstAssert(assrt) => {
-                    Self::const_assert_to_absolute_path(assrt, module_path.clone(), scope.clone())?;
+                    Self::const_assert_to_absolute_path(assrt, module_path.clone(), scope.clone())
                 }
                 ModuleMemberDeclaration::Module(m) => {
-                    Self::module_to_absolute_path(m, module_path.clone(), scope.clone())?;
+                    Self::module_to_absolute_path(m, module_path.clone(), scope.clone())
                 }
-            }
+            });
         }
-        Ok(())
+        Ok(errors.finish()?)
     }
 
     fn append_from_scope(
@@ -382,6 +423,9 @@ impl Resolver {
                     new_path.extend(path.iter().skip(1).cloned());
                     path.value = new_path;
                 }
+                ScopeMember::Package(_) => {
+                    // Resolved by `path_root_to_absolute_path`
+                }
             }
         } else {
             return Err(CompilerPassError::SymbolNotFound(
@@ -392,11 +436,133 @@ impl Resolver {
         Ok(())
     }
 
+    /// Rewrites the `self::` and `super::` roots of `path` as a `package::` root followed by
+    /// the path of the module they name. Returns whether `path` has a root, or `None`, leaving
+    /// `path` as it is, if its root is above the translation unit.
+    fn path_root_to_package(
+        module_path: &ModulePath,
+        path: &mut Spanned<Vec<PathPart>>,
+    ) -> Option<bool> {
+        let Some(first) = path.first() else {
+            return Some(false);
+        };
+        let span = first.name.span();
+        let levels = match first.name.as_str() {
+            PACKAGE_ROOT => return Some(true),
+            SELF_ROOT => 0,
+            SUPER_ROOT => path
+                .iter()
+                .take_while(|part| part.name.as_str() == SUPER_ROOT)
+                .count(),
+            _ => return Some(false),
+        };
+        let depth = module_path.0.len().checked_sub(levels)?;
+        let mut rooted = vec![PathPart {
+            name: Spanned::new(PACKAGE_ROOT.to_string(), span),
+            template_args: None,
+            inline_template_args: None,
+        }];
+        rooted.extend(module_path.0.iter().take(depth).cloned());
+        rooted.extend(path.value.drain(levels.max(1)..));
+        path.value = rooted;
+        Some(true)
+    }
+
+    /// The member `name` of the module at `parent`, or the declaration `name` of the
+    /// translation unit if `parent` is empty, whatever shadows it in `scope`.
+    fn package_member(
+        scope: &im::HashMap<String, ScopeMember>,
+        parent: &ModulePath,
+        name: &str,
+    ) -> Option<ScopeMember> {
+        let Some(ScopeMember::Package(package)) = scope.get(PACKAGE_ROOT) else {
+            return None;
+        };
+        let mut parts = parent.0.iter();
+        let Some(first) = parts.next() else {
+            return package.get(name).cloned();
+        };
+        let Some(ScopeMember::GlobalDeclaration(GlobalDeclaration::Module(module))) =
+            package.get(first.name.as_str())
+        else {
+            return None;
+        };
+        let mut module = module;
+        for part in parts {
+            module = module
+                .members
+                .iter()
+                .find_map(|member| match &member.value {
+                    ModuleMemberDeclaration::Module(m) if m.name.value == part.name.value => {
+                        Some(m)
+                    }
+                    _ => None,
+                })?;
+        }
+        module
+            .members
+            .iter()
+            .find(|member| {
+                member
+                    .name()
+                    .is_some_and(|member_name| member_name.value == name)
+            })
+            .map(|member| {
+                ScopeMember::ModuleMemberDeclaration(parent.clone(), member.value.clone())
+            })
+    }
+
+    /// Removes the root of `path`, if it has one. The rest of the path is made relative to
+    /// the innermost module that contains both the module it names and `module_path`, and
+    /// its first segment is brought into `scope` as a member of that module, in place of
+    /// any local declaration of the same name. Returns `false`, leaving `path` as it is, if
+    /// it names nothing.
+    fn path_root_to_absolute_path(
+        scope: &mut im::HashMap<String, ScopeMember>,
+        module_path: &ModulePath,
+        path: &mut Spanned<Vec<PathPart>>,
+    ) -> bool {
+        if !path.first().is_some_and(|first| is_path_root(&first.name)) {
+            return true;
+        }
+        let source = path.value.clone();
+        if Self::path_root_to_package(module_path, path).is_none() {
+            return false;
+        }
+        path.remove(0);
+        // the modules on the way are not resolved again, which would resolve the directive
+        // of the path again if it is inside one of them
+        let common = path
+            .iter()
+            .zip(module_path.0.iter())
+            .take_while(|(part, module)| part.name.value == module.name.value)
+            .count()
+            .min(path.len().saturating_sub(1));
+        let parent = ModulePath(module_path.0.iter().take(common).cloned().collect());
+        path.value.drain(..common);
+        let member = path.first().and_then(|first| {
+            let member = Self::package_member(scope, &parent, first.name.as_str())?;
+            Some((first.name.value.clone(), member))
+        });
+        let Some((name, member)) = member else {
+            path.value = source;
+            return false;
+        };
+        scope.insert(name, member);
+        true
+    }
+
     fn relative_path_to_absolute_path(
         mut scope: im::HashMap<String, ScopeMember>,
         module_path: ModulePath,
         path: &mut Spanned<Vec<PathPart>>,
     ) -> Result<(), CompilerPassError> {
+        if !Self::path_root_to_absolute_path(&mut scope, &module_path, path) {
+            return Err(CompilerPassError::SymbolNotFound(
+                path.value.clone(),
+                path.span(),
+            ));
+        }
         Self::inline_template_args_to_absolute_path(&module_path, path, &mut scope)?;
         Self::append_from_scope(scope, path)?;
         Ok(())
@@ -812,7 +978,15 @@ impl Resolver {
         module_path: ModulePath,
         scope: &mut im::HashMap<String, ScopeMember>,
     ) -> Result<(), CompilerPassError> {
-        if !usage.path.is_empty() {
+        // a rooted path keeps its root until its items are resolved, since they are resolved
+        // from the start of the path again
+        let Some(is_rooted) = Self::path_root_to_package(&module_path, &mut usage.path) else {
+            return Err(CompilerPassError::SymbolNotFound(
+                usage.path.value.clone(),
+                usage.path.span(),
+            ));
+        };
+        if !usage.path.is_empty() && !is_rooted {
             Self::relative_path_to_absolute_path(
                 scope.clone(),
                 module_path.clone(),
@@ -827,11 +1001,16 @@ impl Resolver {
                     template_args: item.template_args.clone(),
                     inline_template_args: item.inline_template_args.clone(),
                 });
-                Self::relative_path_to_absolute_path(
+                if let Err(err) = Self::relative_path_to_absolute_path(
                     scope.clone(),
                     module_path.clone(),
                     &mut usage_path,
-                )?;
+                ) {
+                    // keep the name in scope so that its usages are not reported again
+                    let name = item.rename.as_ref().unwrap_or(&item.name);
+                    scope.insert(name.value.clone(), ScopeMember::LocalDeclaration);
+                    return Err(err);
+                }
                 if let Some(rename) = item.rename.as_ref() {
                     scope.insert(
                         rename.value.clone(),
@@ -857,6 +1036,9 @@ impl Resolver {
                 }
             }
         }
+        if is_rooted {
+            usage.path.remove(0);
+        }
         Ok(())
     }
 
@@ -884,16 +1066,29 @@ impl Resolver {
 
     fn find_module_and_scope(
         mut scope: im::HashMap<String, ScopeMember>,
+        module_path: &ModulePath,
         path: &Spanned<Vec<PathPart>>,
     ) -> Result<(Module, im::HashMap<String, ScopeMember>), CompilerPassError> {
+        let mut path = path.clone();
+        if !Self::path_root_to_absolute_path(&mut scope, module_path, &mut path) {
+            return Err(CompilerPassError::SymbolNotFound(
+                path.value.clone(),
+                path.span(),
+            ));
+        }
         assert!(!path.is_empty());
-        let mut module_path = ModulePath(im::Vector::new());
         let mut remaining_path: im::Vector<PathPart> = path.value.clone().into();
         let fst: PathPart = remaining_path.pop_front().unwrap();
         if let Some(scope_member) = scope.get(fst.name.as_ref()).cloned() {
-            let m = match scope_member {
-                ScopeMember::ModuleMemberDeclaration(_, ModuleMemberDeclaration::Module(m)) => m,
-                ScopeMember::GlobalDeclaration(GlobalDeclaration::Module(m)) => m,
+            // the path of the parent of the module
+            let (mut module_path, m) = match scope_member {
+                ScopeMember::ModuleMemberDeclaration(
+                    module_path,
+                    ModuleMemberDeclaration::Module(m),
+                ) => (module_path, m),
+                ScopeMember::GlobalDeclaration(GlobalDeclaration::Module(m)) => {
+                    (ModulePath(im::Vector::new()), m)
+                }
                 _ => {
                     panic!(
                         "INVARIANT FAILURE: UNEXPECTED SCOPE MEMBER IN THIS STAGE OF PROCESSING"
@@ -955,13 +1150,18 @@ impl Resolver {
         members: &mut Vec<Spanned<ModuleMemberDeclaration>>,
         scope: &mut im::HashMap<String, ScopeMember>,
     ) -> Result<(), CompilerPassError> {
+        let mut errors = ErrorCollector::default();
         let mut other_dirs: Vec<Spanned<ModuleDirective>> = vec![];
         let mut extend_dirs = vec![];
         for dir in directives.drain(..) {
             let span = dir.span();
             match dir.into_inner() {
                 ModuleDirective::Use(mut usage) => {
-                    Self::add_usage_to_scope(&mut usage, module_path.clone(), scope)?;
+                    errors.check(Self::add_usage_to_scope(
+                        &mut usage,
+                        module_path.clone(),
+                        scope,
+                    ));
                     other_dirs.push(Spanned::new(ModuleDirective::Use(usage), span));
                 }
                 ModuleDirective::Extend(extend) => {
@@ -974,7 +1174,13 @@ impl Resolver {
 
         // let prior_scope = scope.clone();
         for mut extension in extend_dirs {
-            let aliases = Self::add_extension_to_scope(&mut extension, module_path, scope)?;
+            let Some(aliases) = errors.check(Self::add_extension_to_scope(
+                &mut extension,
+                module_path,
+                scope,
+            )) else {
+                continue;
+            };
 
             for alias in aliases {
                 members.push(Spanned::new(
@@ -984,17 +1190,17 @@ impl Resolver {
             }
 
             let span: std::ops::Range<usize> = extension.span();
-            Self::relative_path_to_absolute_path(
+            errors.check(Self::relative_path_to_absolute_path(
                 scope.clone(),
                 module_path.clone(),
                 &mut extension.value.path,
-            )?;
+            ));
             directives.push(Spanned::new(ModuleDirective::Extend(extension.value), span));
         }
 
         directives.append(&mut other_dirs);
 
-        Ok(())
+        Ok(errors.finish()?)
     }
 
     fn add_extension_to_scope(
@@ -1002,10 +1208,13 @@ impl Resolver {
         module_path: &ModulePath,
         scope: &mut im::HashMap<String, ScopeMember>,
     ) -> Result<Vec<Alias>, CompilerPassError> {
-        let (mut module, module_scope) = Self::find_module_and_scope(scope.clone(), &extend.path)?;
+        let (mut module, module_scope) =
+            Self::find_module_and_scope(scope.clone(), module_path, &extend.path)?;
 
         let mut extend_path = extend.path.clone();
         Self::relative_path_to_absolute_path(scope.clone(), module_path.clone(), &mut extend_path)?;
+        // the path of the parent of the module, to which it adds its own name
+        extend_path.value.pop();
         Self::module_to_absolute_path(
             &mut module,
             ModulePath(extend_path.value.into()),
@@ -1020,7 +1229,28 @@ impl Resolver {
             p.inline_template_args = None;
         }
 
+        let is_inside = module_path
+            .0
+            .iter()
+            .map(|part| &part.name.value)
+            .take(path.len())
+            .eq(path.iter().map(|part| &part.name.value));
+        // the aliases are resolved again among the members of the module of the directive,
+        // which may shadow the first segment of the path
+        let start = extend.span().start;
+        path.insert(
+            0,
+            PathPart {
+                name: Spanned::new(PACKAGE_ROOT.to_string(), start..start),
+                template_args: None,
+                inline_template_args: None,
+            },
+        );
         for member in module.members.iter() {
+            // private members are only brought into the scope of the modules inside
+            if !is_inside && is_private(member.attributes()) {
+                continue;
+            }
             if let Some(name) = member.name() {
                 let mut path = path.clone();
                 path.push(PathPart {
@@ -1030,6 +1260,7 @@ impl Resolver {
                 });
 
                 let alias = Alias {
+                    attributes: vec![],
                     name: name.clone(),
                     typ: Spanned::new(TypeExpression { path }, extend.span()),
                     template_parameters: member
@@ -1082,20 +1313,29 @@ impl Resolver {
                 .collect(),
         );
 
+        let mut package = im::HashMap::new();
         for decl in translation_unit.global_declarations.iter() {
             if let Some(name) = decl.name().as_ref() {
-                scope.insert(
+                package.insert(
                     name.value.clone(),
                     ScopeMember::GlobalDeclaration(decl.as_ref().clone()),
                 );
             }
         }
+        // the declarations shadow the builtins of the same name
+        scope = package.clone().union(scope);
+        scope.insert(PACKAGE_ROOT.to_string(), ScopeMember::Package(package));
 
+        let mut errors = ErrorCollector::default();
         for dir in translation_unit.global_directives.drain(..) {
             let span = dir.span();
             match dir.value {
                 GlobalDirective::Use(mut usage) => {
-                    Self::add_usage_to_scope(&mut usage, module_path.clone(), &mut scope)?;
+                    errors.check(Self::add_usage_to_scope(
+                        &mut usage,
+                        module_path.clone(),
+                        &mut scope,
+                    ));
                     other_directives.push(Spanned::new(GlobalDirective::Use(usage), span));
                 }
                 GlobalDirective::Extend(extend) => {
@@ -1109,7 +1349,13 @@ impl Resolver {
             .append(&mut other_directives);
 
         for mut extend in extend_directives {
-            let aliases = Self::add_extension_to_scope(&mut extend, &module_path, &mut scope)?;
+            let Some(aliases) = errors.check(Self::add_extension_to_scope(
+                &mut extend,
+                &module_path,
+                &mut scope,
+            )) else {
+                continue;
+            };
 
             for alias in aliases {
                 translation_unit
@@ -1117,43 +1363,44 @@ impl Resolver {
                     .push(Spanned::new(GlobalDeclaration::Alias(alias), extend.span()));
             }
             let span = extend.span();
-            Self::relative_path_to_absolute_path(
+            errors.check(Self::relative_path_to_absolute_path(
                 scope.clone(),
                 module_path.clone(),
                 &mut extend.value.path,
-            )?;
+            ));
             translation_unit
                 .global_directives
                 .push(Spanned::new(GlobalDirective::Extend(extend.value), span));
         }
 
         for decl in translation_unit.global_declarations.iter_mut() {
-            match decl.as_mut() {
+            errors.check(match decl.as_mut() {
                 GlobalDeclaration::Void => {
                     // NO ACTION REQUIRED
+                    Ok(())
                 }
                 GlobalDeclaration::Declaration(decl) => {
-                    Self::decl_to_absolute_path(decl, module_path.clone(), scope.clone())?;
+                    Self::decl_to_absolute_path(decl, module_path.clone(), scope.clone())
                 }
                 GlobalDeclaration::Alias(a) => {
-                    Self::alias_to_absolute_path(a, module_path.clone(), scope.clone())?;
+                    Self::alias_to_absolute_path(a, module_path.clone(), scope.clone())
                 }
                 GlobalDeclaration::Struct(s) => {
-                    Self::struct_to_absolute_path(s, module_path.clone(), scope.clone())?;
+                    Self::struct_to_absolute_path(s, module_path.clone(), scope.clone())
                 }
                 GlobalDeclaration::Function(f) => {
-                    Self::func_to_absolute_path(f, module_path.clone(), scope.clone())?;
+                    Self::func_to_absolute_path(f, module_path.clone(), scope.clone())
                 }
                 GlobalDeclaration::ConstAssert(assrt) => {
-                    Self::const_assert_to_absolute_path(assrt, module_path.clone(), scope.clone())?;
+                    Self::const_assert_to_absolute_path(assrt, module_path.clone(), scope.clone())
                 }
                 GlobalDeclaration::Module(m) => {
-                    Self::module_to_absolute_path(m, module_path.clone(), scope.clone())?;
+                    Self::module_to_absolute_path(m, module_path.clone(), scope.clone())
                 }
-            }
+            });
         }
 
-        Ok(())
+        Ok(errors.finish()?)
     }
 
     pub fn resolve_mut(
@@ -1161,7 +1408,9 @@ impl Resolver {
         translation_unit: &mut TranslationUnit,
     ) -> Result<(), CompilerPassError> {
         Self::translation_unit_to_absolute_path(translation_unit)?;
-        Ok(())
+        let mut errors = ErrorCollector::default();
+        check_visibility(translation_unit, &mut errors);
+        Ok(errors.finish()?)
     }
 }
 
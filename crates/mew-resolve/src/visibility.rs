@@ -0,0 +1,153 @@
+//! The members of a module marked `@private` can only be referred to from within the module,
+//! and the modules inside it. The resolver checks the paths once they are absolute, then
+//! drops the attribute, which is not WGSL.
+
+use std::collections::HashMap;
+
+use mew_parse::{
+    span::{Span, Spanned},
+    syntax::{
+        visit,
+        visit_mut::{self, VisitMut},
+        Attribute, GlobalDeclaration, Module, ModuleMemberDeclaration, PathPart, TranslationUnit,
+        Use, UseContent, Visit,
+    },
+};
+use mew_types::{CompilerPassError, ErrorCollector, PRIVATE_ATTRIBUTE};
+
+pub(crate) fn is_private(attributes: Option<&Vec<Spanned<Attribute>>>) -> bool {
+    attributes.is_some_and(|attributes| {
+        attributes
+            .iter()
+            .any(|attribute| attribute.name.value == PRIVATE_ATTRIBUTE)
+    })
+}
+
+fn names(path: &[PathPart]) -> Vec<String> {
+    path.iter().map(|part| part.name.value.clone()).collect()
+}
+
+/// The spans of the names of the private members, by absolute path.
+#[derive(Default)]
+struct PrivateMembers {
+    module_path: Vec<String>,
+    members: HashMap<Vec<String>, Span>,
+}
+
+impl Visit for PrivateMembers {
+    fn visit_module(&mut self, module: &Module) {
+        self.module_path.push(module.name.value.clone());
+        for member in &module.members {
+            if let Some(name) = member.name().filter(|_| is_private(member.attributes())) {
+                let mut path = self.module_path.clone();
+                path.push(name.value);
+                self.members.insert(path, name.span);
+            }
+        }
+        visit::walk_module(self, module);
+        self.module_path.pop();
+    }
+}
+
+/// Reports the paths that refer to a private member from outside its module.
+struct Accesses<'a> {
+    private: &'a HashMap<Vec<String>, Span>,
+    module_path: Vec<String>,
+    errors: &'a mut ErrorCollector,
+}
+
+impl Accesses<'_> {
+    /// Checks the members that `path` goes through, at the segments that `is_written` in the
+    /// source.
+    fn check(&mut self, path: &[PathPart], is_written: impl Fn(&Span) -> bool) {
+        let names = names(path);
+        for (i, part) in path.iter().enumerate().skip(1) {
+            let span = part.name.span();
+            if !is_written(&span) {
+                continue;
+            }
+            let Some(declaration) = self.private.get(&names[..=i]) else {
+                continue;
+            };
+            if !self.module_path.starts_with(&names[..i]) {
+                self.errors.push(CompilerPassError::PrivateMember(
+                    path[..=i].to_vec(),
+                    span,
+                    declaration.clone(),
+                ));
+            }
+        }
+    }
+}
+
+impl Visit for Accesses<'_> {
+    fn visit_module(&mut self, module: &Module) {
+        self.module_path.push(module.name.value.clone());
+        visit::walk_module(self, module);
+        self.module_path.pop();
+    }
+
+    fn visit_path(&mut self, path: &Spanned<Vec<PathPart>>) {
+        // the segments that the resolver prepended come from the module or from a `use` item
+        let outer = path.span();
+        self.check(&path.value, |span| {
+            !span.is_empty() && outer.start <= span.start && span.end <= outer.end
+        });
+        visit::walk_path(self, path);
+    }
+
+    fn visit_use(&mut self, usage: &Use) {
+        if let UseContent::Item(item) = &usage.content.value {
+            let mut path = usage.path.value.clone();
+            path.push(PathPart {
+                name: item.name.clone(),
+                template_args: None,
+                inline_template_args: None,
+            });
+            self.check(&path, |span| !span.is_empty());
+        }
+        visit::walk_use(self, usage);
+    }
+}
+
+/// Drops the `@private` attributes.
+struct StripPrivate;
+
+impl StripPrivate {
+    fn strip(attributes: Option<&mut Vec<Spanned<Attribute>>>) {
+        if let Some(attributes) = attributes {
+            attributes.retain(|attribute| attribute.name.value != PRIVATE_ATTRIBUTE);
+        }
+    }
+}
+
+impl VisitMut for StripPrivate {
+    fn visit_global_declaration(&mut self, declaration: &mut GlobalDeclaration) {
+        Self::strip(declaration.attributes_mut());
+        visit_mut::walk_global_declaration(self, declaration);
+    }
+
+    fn visit_module_member_declaration(&mut self, member: &mut ModuleMemberDeclaration) {
+        Self::strip(member.attributes_mut());
+        visit_mut::walk_module_member_declaration(self, member);
+    }
+}
+
+/// Checks that the private members of the resolved `translation_unit` are only referred to
+/// from within their module, then drops their `@private` attribute.
+pub(crate) fn check_visibility(
+    translation_unit: &mut TranslationUnit,
+    errors: &mut ErrorCollector,
+) {
+    let mut private = PrivateMembers::default();
+    private.visit_translation_unit(translation_unit);
+    if !private.members.is_empty() {
+        let mut accesses = Accesses {
+            private: &private.members,
+            module_path: vec![],
+            errors,
+        };
+        accesses.visit_translation_unit(translation_unit);
+    }
+    StripPrivate.visit_translation_unit(translation_unit);
+}
@@ -5,11 +5,19 @@ use mew_parse::{
     span::{Span, Spanned},
     syntax::*,
 };
-use mew_types::{mangling::maybe_mangle_template_args_if_needed, CompilerPass, CompilerPassError};
+use mew_types::{
+    consteval::ConstEvaluator, mangling::maybe_mangle_template_args_if_needed, CompilerPass,
+    CompilerPassError, ErrorCollector, KEEP_ATTRIBUTE,
+};
 
 #[derive(Debug, Clone)]
 pub struct Specializer {
-    pub entrypoint: Option<Vec<PathPart>>,
+    /// The paths of the members to specialize, with everything they use. Members that
+    /// several entry points use are specialized once.
+    ///
+    /// Breaking: replaces the `entrypoint: Option<Vec<PathPart>>` field, `None` is an
+    /// empty list and `Some(path)` is `vec![path]`.
+    pub entrypoints: Vec<Vec<PathPart>>,
 }
 
 type ConcreteSymbolPath = im::Vector<String>;
@@ -411,6 +419,10 @@ impl OwnedMember {
             if let Some(args) = part.template_args.as_mut() {
                 for template_arg in args.iter_mut() {
                     Self::substitute_expression(&mut template_arg.expression, name, value)?;
+                    // e.g. `N + 1u` is a literal once `N` is
+                    if template_arg.arg_name.is_some() {
+                        ConstEvaluator::default().canonicalize(&mut template_arg.expression);
+                    }
                 }
             }
         }
@@ -1244,6 +1256,7 @@ impl<'a> Parent<'a> {
             })
             .collect();
         Alias {
+            attributes: vec![],
             name: Spanned::new(
                 maybe_mangle_template_args_if_needed(path_part),
                 path_part.name.span(),
@@ -1317,8 +1330,7 @@ impl<'a> Parent<'a> {
                 Ok(())
             }
             _ => Ok(()),
-        }?;
-        Ok(())
+        }
     }
 }
 type SymbolPath = im::Vector<String>;
@@ -1330,26 +1342,31 @@ impl Specializer {
     ) -> Result<(), CompilerPassError> {
         let mut symbol_map: SymbolMap = HashMap::new();
         let mut usages: Usages = Usages::new();
-        if let Some(entrypoint) = self.entrypoint.as_ref() {
+        for entrypoint in &self.entrypoints {
             usages.insert(entrypoint.iter().cloned().collect());
         }
+        for kept in kept_paths(translation_unit) {
+            usages.insert(kept);
+        }
         let mut parent: Parent<'a> = Parent::TranslationUnit(translation_unit);
         parent.initialize(im::Vector::new(), &mut symbol_map, &mut usages)?;
 
+        // keep specializing the remaining usages after an error, so that all of them are reported
+        let mut errors = ErrorCollector::default();
         while let Some(remaining_path) = usages.pop() {
             assert!(!remaining_path.is_empty());
             let current_path = im::Vector::new();
-            if let Some(concrete_path) = Self::specialize(
+            if let Some(Some(concrete_path)) = errors.check(Self::specialize(
                 &mut parent,
                 &mut usages,
                 &mut symbol_map,
                 remaining_path.clone(),
                 current_path,
-            )? {
-                Self::alias(&mut parent, remaining_path, concrete_path)?;
+            )) {
+                errors.check(Self::alias(&mut parent, remaining_path, concrete_path));
             }
         }
-        Ok(())
+        Ok(errors.finish()?)
     }
 
     fn alias<'a, 'b: 'a>(
@@ -1457,6 +1474,63 @@ impl Specializer {
     }
 }
 
+/// The paths of the functions and declarations marked `@keep`, which are specialized like
+/// entry points. Members of templates cannot be kept, they need template arguments.
+fn kept_paths(translation_unit: &TranslationUnit) -> Vec<im::Vector<PathPart>> {
+    #[derive(Default)]
+    struct Kept {
+        module_path: im::Vector<PathPart>,
+        paths: Vec<im::Vector<PathPart>>,
+    }
+
+    impl Kept {
+        fn add(&mut self, name: &Spanned<String>, attributes: &[Spanned<Attribute>]) {
+            if attributes
+                .iter()
+                .any(|attribute| attribute.name.value == KEEP_ATTRIBUTE)
+            {
+                let mut path = self.module_path.clone();
+                path.push_back(PathPart {
+                    name: name.clone(),
+                    template_args: None,
+                    inline_template_args: None,
+                });
+                self.paths.push(path);
+            }
+        }
+    }
+
+    impl visit::Visit for Kept {
+        fn visit_module(&mut self, module: &Module) {
+            if module.template_parameters.is_empty() {
+                self.module_path.push_back(PathPart {
+                    name: module.name.clone(),
+                    template_args: None,
+                    inline_template_args: None,
+                });
+                visit::walk_module(self, module);
+                self.module_path.pop_back();
+            }
+        }
+
+        fn visit_declaration(&mut self, declaration: &Declaration) {
+            if declaration.template_parameters.is_empty() {
+                self.add(&declaration.name, &declaration.attributes);
+            }
+        }
+
+        fn visit_function(&mut self, function: &Function) {
+            if function.template_parameters.is_empty() {
+                self.add(&function.name, &function.attributes);
+            }
+        }
+    }
+
+    let mut kept = Kept::default();
+    visit::Visit::visit_translation_unit(&mut kept, translation_unit);
+    kept.paths
+}
+
 impl CompilerPass for Specializer {
     fn apply_mut(
         &mut self,
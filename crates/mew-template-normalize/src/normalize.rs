@@ -1,7 +1,7 @@
 use std::collections::VecDeque;
 
 use mew_parse::{span::Spanned, syntax::*};
-use mew_types::{CompilerPass, CompilerPassError};
+use mew_types::{consteval::ConstEvaluator, CompilerPass, CompilerPassError};
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct TemplateNormalizer;
@@ -683,12 +683,102 @@ impl TemplateNormalizer {
     }
 }
 
+/// Rewrites the value arguments of templates to the literal of their value, so that
+/// arguments written differently instantiate a template once, see
+/// [`ConstEvaluator::canonicalize`].
+struct Canonicalizer<'a> {
+    evaluator: ConstEvaluator<'a>,
+    /// The template parameters in scope. Their names are mangled like those of the
+    /// declarations generated for inline template arguments, which they shadow.
+    parameters: Vec<String>,
+}
+
+impl Canonicalizer<'_> {
+    fn scoped<T>(
+        &mut self,
+        parameters: &[Spanned<FormalTemplateParameter>],
+        node: &mut T,
+        walk: fn(&mut Self, &mut T),
+    ) {
+        let len = self.parameters.len();
+        self.parameters
+            .extend(parameters.iter().map(|param| param.name.value.clone()));
+        walk(self, node);
+        self.parameters.truncate(len);
+    }
+
+    fn refers_to_parameter(&self, expression: &Expression) -> bool {
+        struct References<'a> {
+            parameters: &'a [String],
+            found: bool,
+        }
+
+        impl Visit for References<'_> {
+            fn visit_path(&mut self, node: &Spanned<Vec<PathPart>>) {
+                self.found |= node
+                    .first()
+                    .is_some_and(|first| self.parameters.contains(&first.name.value));
+                visit::walk_path(self, node);
+            }
+        }
+
+        let mut references = References {
+            parameters: &self.parameters,
+            found: false,
+        };
+        references.visit_expression(expression);
+        references.found
+    }
+}
+
+impl VisitMut for Canonicalizer<'_> {
+    fn visit_module(&mut self, node: &mut Module) {
+        let parameters = node.template_parameters.clone();
+        self.scoped(&parameters, node, visit_mut::walk_module);
+    }
+
+    fn visit_function(&mut self, node: &mut Function) {
+        let parameters = node.template_parameters.clone();
+        self.scoped(&parameters, node, visit_mut::walk_function);
+    }
+
+    fn visit_struct(&mut self, node: &mut Struct) {
+        let parameters = node.template_parameters.clone();
+        self.scoped(&parameters, node, visit_mut::walk_struct);
+    }
+
+    fn visit_alias(&mut self, node: &mut Alias) {
+        let parameters = node.template_parameters.clone();
+        self.scoped(&parameters, node, visit_mut::walk_alias);
+    }
+
+    fn visit_declaration(&mut self, node: &mut Declaration) {
+        let parameters = node.template_parameters.clone();
+        self.scoped(&parameters, node, visit_mut::walk_declaration);
+    }
+
+    fn visit_template_arg(&mut self, node: &mut TemplateArg) {
+        visit_mut::walk_template_arg(self, node);
+        // only the arguments of templates are named once normalized, builtin types such
+        // as `array<f32, 4>` are not instantiated
+        if node.arg_name.is_some() && !self.refers_to_parameter(&node.expression) {
+            self.evaluator.canonicalize(&mut node.expression);
+        }
+    }
+}
+
 impl CompilerPass for TemplateNormalizer {
     fn apply_mut(
         &mut self,
         translation_unit: &mut mew_parse::syntax::TranslationUnit,
     ) -> Result<(), mew_types::CompilerPassError> {
         Self::normalize_template_arguments_from_translation_unit(translation_unit)?;
+        let clone = translation_unit.clone();
+        let mut canonicalizer = Canonicalizer {
+            evaluator: ConstEvaluator::new(&clone),
+            parameters: vec![],
+        };
+        canonicalizer.visit_translation_unit(translation_unit);
         Ok(())
     }
 }
@@ -0,0 +1,33 @@
+
+
+@compute @workgroup_size(64)
+fn pipeline_cs__main(@builtin(global_invocation_id) id: vec3<u32>) {
+    pipeline_colors[id.x] = pipeline_Lighting_shade__pipeline____58____58____Rgb(pipeline_colors[id.x]);
+}
+
+fn pipeline_Lighting_shade__pipeline____58____58____Rgb(color: vec3f) -> vec3f {
+    return color * 0.5;
+}
+
+@group(0) @binding(0)
+var<storage, read_write> pipeline_colors: array<vec3f>;
+
+@fragment
+fn pipeline_fs__main(input: pipeline_VertexOutput) -> @location(0) vec4f {
+    return vec4f(pipeline_Lighting_shade__pipeline____58____58____Rgb(input.color), 1f);
+}
+
+struct pipeline_VertexOutput {
+    @builtin(position)
+    position: vec4f,
+    @location(0)
+    color: vec3f
+}
+
+@vertex
+fn pipeline_vs__main(@builtin(vertex_index) index: u32) -> pipeline_VertexOutput {
+    var out: pipeline_VertexOutput;
+    out.position = vec4f(0f);
+    out.color = pipeline_Lighting_shade__pipeline____58____58____Rgb(vec3f(1f));
+    return out;
+}
@@ -0,0 +1,30 @@
+
+
+const_assert dce_workgroup__size % 32u == 0u;
+
+fn dce_Debug_checksum() -> f32 {
+    return dce_data[0] + dce_data[1];
+}
+
+@group(0) @binding(0)
+var<storage, read_write> dce_data: array<f32>;
+
+const dce_workgroup__size = 64u;
+
+override dce_scale: f32 = 1.0;
+
+@compute @workgroup_size(64)
+fn dce_main(@builtin(global_invocation_id) id: vec3u) {
+    if id.x >= dce_workgroup__size {
+        return;
+    }
+    dce_data[id.x] = dce_double(dce_Math_square__f32(dce_data[id.x]));
+}
+
+fn dce_Math_square__f32(x: f32) -> f32 {
+    return x * x;
+}
+
+fn dce_double(x: f32) -> f32 {
+    return x * 2.0;
+}
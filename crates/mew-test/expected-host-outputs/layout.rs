@@ -0,0 +1,102 @@
+/// `layout::items`
+pub const LAYOUT_ITEMS_GROUP: u32 = 2;
+pub const LAYOUT_ITEMS_BINDING: u32 = 1;
+
+/// `layout::padded`
+pub const LAYOUT_PADDED_GROUP: u32 = 2;
+pub const LAYOUT_PADDED_BINDING: u32 = 0;
+
+/// `layout::sums`
+pub const LAYOUT_SUMS_GROUP: u32 = 1;
+pub const LAYOUT_SUMS_BINDING: u32 = 0;
+
+/// `layout::particles`
+pub const LAYOUT_PARTICLES_GROUP: u32 = 0;
+pub const LAYOUT_PARTICLES_BINDING: u32 = 1;
+
+/// `layout::camera`
+pub const LAYOUT_CAMERA_GROUP: u32 = 0;
+pub const LAYOUT_CAMERA_BINDING: u32 = 0;
+
+/// `layout_Items`, 64 bytes aligned to 16.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+#[allow(non_camel_case_types)]
+pub struct layout_Items {
+    pub items: [[f32; 4]; 4],
+}
+
+/// `layout_Padded`, 64 bytes aligned to 32.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+#[allow(non_camel_case_types)]
+pub struct layout_Padded {
+    pub a: f32,
+    pub _pad0: [u8; 28],
+    pub b: f32,
+    pub _pad1: [u8; 28],
+}
+
+/// `layout_Sum__vec3f`, 32 bytes aligned to 16.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+#[allow(non_camel_case_types)]
+pub struct layout_Sum__vec3f {
+    pub count: u32,
+    pub _pad0: [u8; 12],
+    pub sum: [f32; 3],
+    pub _pad1: [u8; 4],
+}
+
+/// `layout_Particles`, 16 bytes aligned to 16.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+#[allow(non_camel_case_types)]
+pub struct layout_Particles {
+    pub count: u32,
+    pub _pad0: [u8; 12],
+}
+
+impl layout_Particles {
+    /// The offset of the elements of `particles`, of type `layout_Particle`.
+    pub const PARTICLES_OFFSET: u64 = 16;
+    /// The distance between the elements of `particles`.
+    pub const PARTICLES_STRIDE: u64 = 64;
+}
+
+/// `layout_Particle`, 64 bytes aligned to 16.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+#[allow(non_camel_case_types)]
+pub struct layout_Particle {
+    pub position: [f32; 3],
+    pub mass: f32,
+    pub basis: [[f32; 4]; 3],
+}
+
+/// `layout_Camera`, 256 bytes aligned to 16.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+#[allow(non_camel_case_types)]
+pub struct layout_Camera {
+    pub view_projection: [[f32; 4]; 4],
+    pub position: [f32; 3],
+    pub _pad0: [u8; 4],
+    pub lights: [layout_Lights_Light; 4],
+    pub exposure: f32,
+    pub _pad1: [u8; 4],
+    pub tint: [f32; 2],
+    pub _pad2: [u8; 32],
+}
+
+/// `layout_Lights_Light`, 32 bytes aligned to 16.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, ::bytemuck::Pod, ::bytemuck::Zeroable)]
+#[allow(non_camel_case_types)]
+pub struct layout_Lights_Light {
+    pub position: [f32; 3],
+    pub intensity: f32,
+    pub color: [f32; 3],
+    pub _pad0: [u8; 4],
+}
+
@@ -0,0 +1,16 @@
+
+
+@fragment
+fn root_main() -> @location(0) vec4f {
+    return vec4f(shapes_circle_area(2f) + util_math_double(1f));
+}
+
+fn util_math_double(x: f32) -> f32 {
+    return x * 2f;
+}
+
+fn shapes_circle_area(r: f32) -> f32 {
+    return shapes_pi * r * r;
+}
+
+const shapes_pi: f32 = 3.14159f;
@@ -0,0 +1,21 @@
+
+
+fn test__7_main() -> f32 {
+    return test__7_Buffer_first__4u() + test__7_Buffer_first__4u() + test__7_Buffer_first__4u() + test__7_Buffer_first__4u() + test__7_Doubled_first__2u() + test__7_Buffer_first__8u();
+}
+
+fn test__7_Buffer_first__8u() -> f32 {
+    return test__7_Buffer_values__8u[0];
+}
+
+var<private> test__7_Buffer_values__8u: array<f32, 8u>;
+
+fn test__7_Buffer_first__4u() -> f32 {
+    return test__7_Buffer_values__4u[0];
+}
+
+var<private> test__7_Buffer_values__4u: array<f32, 4u>;
+
+fn test__7_Doubled_first__2u() -> f32 {
+    return test__7_Buffer_first__4u();
+}
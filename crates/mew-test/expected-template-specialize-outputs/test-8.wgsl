@@ -0,0 +1,34 @@
+
+
+@vertex
+fn test__8_main(@builtin(instance_index) instanceIdx: u32, @location(0) position: vec4<f32>, @location(1) color: vec4f) -> test__8_My__Lib_VertexShader_Types_VertexOutput__test____95____8____58____58____Hey {
+    use test_8::My_Lib<test_8::Hey, test_8::WhatsUp>::VertexShader<Hi> as V;
+    let uni = test__8_Camera__mat4x4f();
+    var output: test__8_My__Lib_VertexShader_Types_VertexOutput__test____95____8____58____58____Hey;
+    output.Position = test__8_My__Lib_VertexShader_camera__test____95____8____58____58____Hey__test____95____8____58____58____WhatsUp__test____95____8____58____58____Hi__test____95____8____58____58____Hi.viewProjectionMatrix * test__8_My__Lib_VertexShader_uniforms__test____95____8____58____58____Hey__test____95____8____58____58____WhatsUp__test____95____8____58____58____Hi__test____95____8____58____58____Hi.modelMatrix.viewProjectionMatrix[instanceIdx] * position;
+    output.fragColor = color / test__8_My__Lib_x__test____95____8____58____58____Hey__test____95____8____58____58____WhatsUp__test____95____8____58____58____Hi.viewProjectionMatrix[0];
+    return output;
+}
+
+const test__8_My__Lib_x__test____95____8____58____58____Hey__test____95____8____58____58____WhatsUp__test____95____8____58____58____Hi: test__8_Camera__mat4x4f = test__8_Camera__mat4x4f();
+
+@binding(0) @group(0)
+var<uniform> test__8_My__Lib_VertexShader_uniforms__test____95____8____58____58____Hey__test____95____8____58____58____WhatsUp__test____95____8____58____58____Hi__test____95____8____58____58____Hi: test__8_My__Lib_VertexShader_Types_Uniforms__test____95____8____58____58____Hi;
+
+struct test__8_My__Lib_VertexShader_Types_Uniforms__test____95____8____58____58____Hi {
+    modelMatrix: test__8_Camera__mat4x4f
+}
+
+struct test__8_My__Lib_VertexShader_Types_VertexOutput__test____95____8____58____58____Hey {
+    @builtin(position)
+    Position: vec4f,
+    @location(0)
+    fragColor: vec4f
+}
+
+@binding(1) @group(0)
+var<uniform> test__8_My__Lib_VertexShader_camera__test____95____8____58____58____Hey__test____95____8____58____58____WhatsUp__test____95____8____58____58____Hi__test____95____8____58____58____Hi: test__8_Camera__mat4x4f;
+
+struct test__8_Camera__mat4x4f {
+    viewProjectionMatrix: mat4x4f
+}
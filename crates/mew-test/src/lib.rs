@@ -68,6 +68,7 @@ pub fn bundle_mew_samples() -> Result<(), CompilerPassError> {
     let mut bundler = Bundler {
         sources: entrypoints.iter().map(|x| x.as_str()).collect(),
         enclosing_module_name: Some("MyLib".to_owned()),
+        ..Default::default()
     };
 
     let translation_unit = TranslationUnit::default();
@@ -77,6 +78,7 @@ pub fn bundle_mew_samples() -> Result<(), CompilerPassError> {
     let mut bundler = Bundler {
         sources: entrypoints.iter().map(|x| x.as_str()).collect(),
         enclosing_module_name: None,
+        ..Default::default()
     };
 
     let result_without_root_module = bundler.apply(&translation_unit)?;
@@ -312,7 +314,7 @@ fn dealias_mew_samples() -> Result<(), CompilerPassError> {
 
             let mut result = resolver.apply(&source_module)?;
 
-            let mut dealiaser = mew_dealias::Dealiaser;
+            let mut dealiaser = mew_dealias::Dealiaser::default();
 
             dealiaser.apply_mut(&mut result)?;
 
@@ -344,11 +346,12 @@ fn template_specialize_mew_samples() -> Result<(), MewError> {
         std::fs::read_dir("template-specialize-inputs").expect("missing expected-test-inputs");
 
     let entrypoints = HashMap::from([
-        ("test_1", "test_1::main"),
-        ("test_2", "test_2::main"),
         ("test_3", "test_3::main"),
         ("test_4", "test_4::main"),
         ("test_5", "test_5::My_Lib::Demo::main"),
+        ("test_7", "test_7::main"),
+        ("test_8", "test_8::main"),
+        ("test_9", "test_9::main"),
         (
             "test_6",
             r#"test_6::ReduceBuffer with {
@@ -408,3 +411,1654 @@ fn template_specialize_mew_samples() -> Result<(), MewError> {
     }
     Ok(())
 }
+
+#[test]
+fn load_mew_samples() -> Result<(), MewError> {
+    let source = std::fs::read_to_string("loader-inputs/root.mew").expect("failed to read file");
+
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("loader-inputs"));
+
+    api.add_module(ModuleDescriptor {
+        module_name: "root",
+        source: mew_api::Source::Text(&source),
+    })?;
+
+    let mut loaded_files = api.loaded_files.clone();
+    loaded_files.sort();
+    assert_eq!(
+        loaded_files,
+        vec![
+            PathBuf::from("loader-inputs/shapes/circle.mew"),
+            PathBuf::from("loader-inputs/shapes/mod.mew"),
+            PathBuf::from("loader-inputs/util/math.mew"),
+        ]
+    );
+
+    let result = api.compile(&Path::Text("root::main".to_string()))?;
+
+    let expected_output_location: PathBuf = std::env::current_dir()
+        .unwrap()
+        .join("expected-loader-outputs")
+        .join("root.wgsl");
+
+    #[cfg(feature = "update_expected_output")]
+    {
+        let _ = std::fs::write(expected_output_location.clone(), result.clone()).expect("Written");
+    }
+
+    let expected_output_module = mew_parse::Parser::parse_str(
+        &std::fs::read_to_string(expected_output_location.clone()).expect("READ"),
+    )
+    .inspect_err(|err| eprintln!("{err}"))
+    .expect("parse error");
+    assert_eq!(result, format!("{}", expected_output_module));
+    Ok(())
+}
+
+#[test]
+fn remove_module_mew_samples() -> Result<(), MewError> {
+    let source = std::fs::read_to_string("loader-inputs/root.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("loader-inputs"));
+    api.add_module(ModuleDescriptor {
+        module_name: "root",
+        source: mew_api::Source::Text(&source),
+    })?;
+
+    api.remove_module(&"root".to_string())?;
+    api.compile(&Path::Text("root::main".to_string()))
+        .expect_err("the module is removed");
+    let err = api
+        .remove_module(&"root".to_string())
+        .expect_err("the module is already removed");
+    assert!(matches!(err.error, mew_api::MewErrorInner::ModuleNotFound));
+    Ok(())
+}
+
+#[test]
+fn error_mew_samples() {
+    let source =
+        std::fs::read_to_string("error-inputs/symbol-not-found.mew").expect("failed to read file");
+
+    let mut api = mew_api::MewApi::default();
+    api.add_module(ModuleDescriptor {
+        module_name: "symbol_not_found",
+        source: mew_api::Source::Text(&source),
+    })
+    .expect("failed to add module");
+
+    let err = api
+        .compile(&Path::Text("symbol_not_found::main".to_string()))
+        .expect_err("expected an error");
+
+    assert_eq!(err.module_name.as_deref(), Some("symbol_not_found"));
+    assert_eq!(
+        format!("{err}"),
+        "cannot find `symbol_not_found::Lib::missing` (in module `symbol_not_found`)"
+    );
+    let span = err.span.clone().expect("missing span");
+    let file = api
+        .source_map
+        .lookup(&span)
+        .expect("span outside of source map");
+    let file = api.source_map.file(file);
+    assert_eq!(&file.text[file.local_span(&span)], "Lib::missing");
+    let location = api
+        .source_map
+        .location(span.start)
+        .expect("missing location");
+    assert_eq!((location.line, location.column), (9, 18));
+
+    let rendered = api.format_error(&err);
+    assert!(rendered.contains("not found in this scope"));
+    assert!(rendered.contains("return vec4f(Lib::missing());"));
+
+    // syntax trees have no source text, their spans belong to no file
+    let mut ast = mew_parse::Parser::parse_str("mod tree { fn main() -> f32 { return missing; } }")
+        .expect("parse error");
+    api.add_module(ModuleDescriptor {
+        module_name: "tree",
+        source: mew_api::Source::Ast(&mut ast),
+    })
+    .expect("failed to add module");
+    let err = api
+        .compile(&Path::Text("tree::main".to_string()))
+        .expect_err("expected an error");
+    let span = err.span.clone().expect("missing span");
+    assert_eq!(api.source_map.lookup(&span), None);
+    assert_eq!(err.module_name, None);
+    assert!(!api.format_error(&err).contains("return vec4f"));
+}
+
+#[test]
+fn many_errors_mew_samples() {
+    let source = std::fs::read_to_string("error-inputs/many-symbols-not-found.mew")
+        .expect("failed to read file");
+
+    let mut api = mew_api::MewApi::default();
+    api.add_module(ModuleDescriptor {
+        module_name: "many_symbols_not_found",
+        source: mew_api::Source::Text(&source),
+    })
+    .expect("failed to add module");
+
+    let errors = api
+        .compile(&Path::Text("many_symbols_not_found::main".to_string()))
+        .expect_err("expected an error")
+        .into_errors();
+
+    let messages: Vec<String> = errors.iter().map(|err| format!("{err}")).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "cannot find `MissingType` (in module `many_symbols_not_found`)",
+            "cannot find `missing_constant` (in module `many_symbols_not_found`)",
+            "cannot find `missing_function` (in module `many_symbols_not_found`)",
+        ]
+    );
+}
+
+#[test]
+fn recover_syntax_errors_mew_samples() {
+    let source =
+        std::fs::read_to_string("error-inputs/syntax-errors.mew").expect("failed to read file");
+
+    let (translation_unit, errors) = mew_parse::Parser::parse_str_recovering(&source);
+    let error_sources: Vec<&str> = errors.iter().map(|err| &source[err.span()]).collect();
+    assert_eq!(error_sources, vec![";", ";", ")"]);
+
+    let names: Vec<String> = translation_unit
+        .global_declarations
+        .iter()
+        .filter_map(|decl| decl.name().map(|name| name.value))
+        .collect();
+    assert_eq!(names, vec!["a", "f", "M", "e"]);
+
+    let mut api = mew_api::MewApi::default();
+    let err = api
+        .add_module(ModuleDescriptor {
+            module_name: "syntax_errors",
+            source: mew_api::Source::Text(&source),
+        })
+        .expect_err("expected syntax errors");
+    assert_eq!(err.into_errors().len(), 3);
+}
+
+#[test]
+fn visit_mew_samples() {
+    use mew_parse::{
+        span::Spanned,
+        syntax::{Module, PathPart, Visit, VisitMut},
+    };
+
+    let source = std::fs::read_to_string("mew-samples/mod.mew").expect("failed to read file");
+    let mut translation_unit = mew_parse::Parser::parse_str(&source).expect("failed to parse");
+
+    struct Paths(Vec<String>);
+    impl Visit for Paths {
+        fn visit_path(&mut self, path: &Spanned<Vec<PathPart>>) {
+            let names: Vec<&str> = path.iter().map(|part| part.name.as_str()).collect();
+            self.0.push(names.join("::"));
+            mew_parse::syntax::visit::walk_path(self, path);
+        }
+    }
+    let mut paths = Paths(vec![]);
+    paths.visit_translation_unit(&translation_unit);
+    for expected in [
+        "Types2::Uniforms",
+        "Types2",
+        "mat4x4f",
+        "instanceIdx",
+        "f32",
+    ] {
+        assert!(paths.0.iter().any(|path| path == expected), "{expected}");
+    }
+
+    struct Rename;
+    impl VisitMut for Rename {
+        fn visit_module(&mut self, module: &mut Module) {
+            if module.name.value == "Types2" {
+                module.name.value = "Shapes".to_string();
+            }
+            mew_parse::syntax::visit_mut::walk_module(self, module);
+        }
+        fn visit_path_part(&mut self, part: &mut PathPart) {
+            if part.name.value == "Types2" {
+                part.name.value = "Shapes".to_string();
+            }
+            mew_parse::syntax::visit_mut::walk_path_part(self, part);
+        }
+    }
+    Rename.visit_translation_unit(&mut translation_unit);
+    let renamed = translation_unit.to_string();
+    assert!(!renamed.contains("Types2"));
+    assert_eq!(
+        renamed.matches("Shapes").count(),
+        source.matches("Types2").count()
+    );
+}
+
+#[test]
+fn trivia_mew_samples() {
+    let source =
+        std::fs::read_to_string("trivia-inputs/comments.mew").expect("failed to read file");
+
+    let (translation_unit, trivia) =
+        mew_parse::Parser::parse_str_lossless(&source).expect("failed to parse");
+    let result = translation_unit.display_with_trivia(&trivia).to_string();
+
+    let comment_count = trivia
+        .nodes
+        .values()
+        .map(|t| t.leading.len() + t.trailing.len())
+        .sum::<usize>()
+        + trivia.dangling.len();
+    assert_eq!(comment_count, 16);
+
+    let expected_output_location: PathBuf = std::env::current_dir()
+        .unwrap()
+        .join("expected-trivia-outputs")
+        .join("comments.mew");
+
+    #[cfg(feature = "update_expected_output")]
+    {
+        let _ = std::fs::write(expected_output_location.clone(), result.clone()).expect("Written");
+    }
+
+    let expected_output = std::fs::read_to_string(expected_output_location.clone()).expect("READ");
+    assert_eq!(result, expected_output);
+
+    // printing is stable
+    let (translation_unit, trivia) =
+        mew_parse::Parser::parse_str_lossless(&result).expect("failed to parse");
+    assert_eq!(
+        translation_unit.display_with_trivia(&trivia).to_string(),
+        result
+    );
+}
+
+#[test]
+fn format_mew_samples() {
+    use mew_format::{format_str, AttributePlacement, FormatOptions};
+
+    let source =
+        std::fs::read_to_string("format-inputs/unformatted.mew").expect("failed to read file");
+    let compact = FormatOptions {
+        indent_width: 2,
+        max_width: 60,
+        attributes: AttributePlacement::SameLine,
+        sort_uses: true,
+        group_uses: true,
+    };
+
+    for (name, options) in [
+        ("default.mew", FormatOptions::default()),
+        ("compact.mew", compact),
+    ] {
+        let result = format_str(&source, &options).expect("failed to format");
+
+        let expected_output_location: PathBuf = std::env::current_dir()
+            .unwrap()
+            .join("expected-format-outputs")
+            .join(name);
+
+        #[cfg(feature = "update_expected_output")]
+        {
+            let _ =
+                std::fs::write(expected_output_location.clone(), result.clone()).expect("Written");
+        }
+
+        let expected_output =
+            std::fs::read_to_string(expected_output_location.clone()).expect("READ");
+        assert_eq!(result, expected_output, "{name}");
+
+        // formatting is idempotent
+        let again = format_str(&result, &options).expect("failed to format");
+        assert_eq!(again, result, "{name}");
+    }
+
+    // without sorting and grouping, the syntax tree is unchanged
+    let result = format_str(&source, &FormatOptions::default()).expect("failed to format");
+    assert_eq!(
+        mew_parse::Parser::parse_str(&result).expect("failed to parse"),
+        mew_parse::Parser::parse_str(&source).expect("failed to parse")
+    );
+}
+
+#[test]
+fn layout_mew_samples() -> Result<(), MewError> {
+    use mew_api::naga::TypeInner;
+    use mew_types::layout::TypeLayout;
+
+    let source = std::fs::read_to_string("layout-inputs/layout.mew").expect("failed to read file");
+
+    let mut api = mew_api::MewApi::default();
+    api.add_module(ModuleDescriptor {
+        module_name: "layout",
+        source: mew_api::Source::Text(&source),
+    })?;
+    let (wgsl, reflection) =
+        api.compile_with_reflection(&[Path::Text("layout::main".to_string())])?;
+
+    let layout = |name: &str| {
+        reflection
+            .structs
+            .iter()
+            .find(|layout| layout.name.ends_with(name))
+            .unwrap_or_else(|| panic!("missing layout of {name}"))
+    };
+    let offsets = |name: &str| {
+        layout(name)
+            .members
+            .iter()
+            .map(|member| (member.name.as_str(), member.offset, member.size))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        offsets("Camera"),
+        [
+            ("view_projection", 0, 64),
+            ("position", 64, 12),
+            ("lights", 80, 128),
+            ("exposure", 208, 4),
+            ("tint", 216, 32),
+        ]
+    );
+    assert_eq!(
+        layout("Camera").layout,
+        TypeLayout {
+            size: 256,
+            align: 16,
+            stride: None,
+            runtime_sized: false
+        }
+    );
+    assert_eq!(layout("Camera").members[2].layout.stride, Some(32));
+    assert_eq!(
+        offsets("Light"),
+        [("position", 0, 12), ("intensity", 12, 4), ("color", 16, 12)]
+    );
+    assert_eq!(
+        offsets("Particles"),
+        [("count", 0, 4), ("particles", 16, 0)]
+    );
+    assert_eq!(
+        layout("Particles").layout,
+        TypeLayout {
+            size: 16,
+            align: 16,
+            stride: None,
+            runtime_sized: true
+        }
+    );
+    assert_eq!(layout("Particles").members[1].layout.stride, Some(64));
+    // `@size`, `@align` and element counts are const-expressions
+    assert_eq!(offsets("Padded"), [("a", 0, 32), ("b", 32, 4)]);
+    assert_eq!(layout("Padded").layout.size, 64);
+    assert_eq!(offsets("Items"), [("items", 0, 64)]);
+
+    // arguments that are not constants have no layout, rather than the type's
+    let translation_unit =
+        mew_parse::Parser::parse_str("struct S { @size(2 * 16) a: f32, @align(missing) b: f32 }")
+            .expect("parse error");
+    let err = mew_types::layout::Layouts::new(&translation_unit)
+        .struct_layout("S", Default::default())
+        .expect_err("expected a layout error");
+    assert_eq!(
+        err.value,
+        mew_types::layout::LayoutError::UnsupportedAttributeArgument {
+            attribute: "align".to_string(),
+            member: "b".to_string()
+        }
+    );
+
+    // the layouts match naga's
+    let module = mew_api::naga::front::wgsl::parse_str(&wgsl).expect("invalid WGSL");
+    for struct_layout in &reflection.structs {
+        let (_, ty) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(struct_layout.name.as_str()))
+            .expect("missing struct");
+        let TypeInner::Struct { members, span } = &ty.inner else {
+            panic!("not a struct");
+        };
+        // naga counts one element of runtime-sized arrays
+        if !struct_layout.layout.runtime_sized {
+            assert_eq!(*span, struct_layout.layout.size, "{}", struct_layout.name);
+        }
+        let offsets: Vec<_> = members.iter().map(|member| member.offset).collect();
+        let expected_offsets: Vec<_> = struct_layout
+            .members
+            .iter()
+            .map(|member| member.offset)
+            .collect();
+        assert_eq!(offsets, expected_offsets, "{}", struct_layout.name);
+    }
+
+    let layout_errors = |module_name: &str| {
+        let file = format!("error-inputs/{}.mew", module_name.replace('_', "-"));
+        let source = std::fs::read_to_string(file).expect("failed to read file");
+        let mut api = mew_api::MewApi::default();
+        api.add_module(ModuleDescriptor {
+            module_name,
+            source: mew_api::Source::Text(&source),
+        })
+        .expect("failed to add module");
+        let err = api
+            .compile(&Path::Text(format!("{module_name}::main")))
+            .expect_err("expected layout errors");
+        let mut errors: Vec<_> = err
+            .into_errors()
+            .iter()
+            .map(|err| {
+                let span = err.span.clone().expect("missing span");
+                let location = api
+                    .source_map
+                    .location(span.start)
+                    .expect("missing location");
+                (location.line, location.column, err.message())
+            })
+            .collect();
+        errors.sort();
+        errors
+    };
+
+    let errors = layout_errors("uniform_layout");
+    assert_eq!(
+        errors,
+        [
+            (
+                2,
+                13,
+                "`array<f32, 4>` has a stride of 4 bytes, but arrays in the uniform address \
+                 space need a stride that is a multiple of 16 (in module `uniform_layout`)"
+                    .to_string()
+            ),
+            (
+                11,
+                5,
+                "`inner` is a struct, so the next member must be at least 16 bytes after it in \
+                 the uniform address space (in module `uniform_layout`)"
+                    .to_string()
+            ),
+            (
+                11,
+                5,
+                "`inner` is at offset 8, but its type must be aligned to 16 bytes in the \
+                 uniform address space (in module `uniform_layout`)"
+                    .to_string()
+            ),
+        ]
+    );
+
+    // invalid `@align` and `@size`, and sizes beyond `u32`, are errors rather than panics
+    assert_eq!(
+        layout_errors("layout_attributes"),
+        [
+            (
+                2,
+                5,
+                "`@align(0)` on `value` is not a positive power of two \
+                 (in module `layout_attributes`)"
+                    .to_string()
+            ),
+            (
+                6,
+                5,
+                "`@align(12)` on `value` is not a positive power of two \
+                 (in module `layout_attributes`)"
+                    .to_string()
+            ),
+            (
+                10,
+                5,
+                "`@size(8)` on `value` is less than the 16 bytes of its type \
+                 (in module `layout_attributes`)"
+                    .to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        layout_errors("layout_overflow"),
+        [(
+            7,
+            22,
+            "`array<vec4<f32>, 1000000000>` is larger than 4294967295 bytes \
+             (in module `layout_overflow`)"
+                .to_string()
+        )]
+    );
+    let source =
+        std::fs::read_to_string("error-inputs/layout-overflow.mew").expect("failed to read file");
+    let translation_unit = mew_parse::Parser::parse_str(&source).expect("parse error");
+    let err = mew_types::layout::Layouts::new(&translation_unit)
+        .struct_layout("Huge", Default::default())
+        .expect_err("expected a layout error");
+    assert_eq!(
+        err.value,
+        mew_types::layout::LayoutError::TooLarge("array<vec4<f32>, 1000000000>".to_string())
+    );
+    assert_eq!(mew_types::layout::struct_layouts(&translation_unit), []);
+    Ok(())
+}
+
+#[test]
+fn mewc_mew_samples() {
+    use clap::Parser;
+
+    let run = |args: &[&str]| {
+        let cli = mewc::Cli::try_parse_from(["mewc", "--color", "never"].iter().chain(args))
+            .expect("invalid arguments");
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        let code = mewc::run(&cli, &mut stdout, &mut stderr);
+        (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    };
+
+    let (code, stdout, stderr) = run(&["compile", "loader-inputs/root.mew", "-e", "root::main"]);
+    assert_eq!((code, stderr.as_str()), (mewc::EXIT_SUCCESS, ""));
+    let expected_output_module = mew_parse::Parser::parse_str(
+        &std::fs::read_to_string("expected-loader-outputs/root.wgsl").expect("READ"),
+    )
+    .expect("parse error");
+    assert_eq!(stdout, format!("{}", expected_output_module));
+
+    let (code, stdout, _) = run(&["check", "loader-inputs"]);
+    assert_eq!((code, stdout.as_str()), (mewc::EXIT_SUCCESS, ""));
+
+    let (code, _, stderr) = run(&[
+        "check",
+        "--message-format",
+        "short",
+        "error-inputs/many-symbols-not-found.mew",
+        "error-inputs/syntax-errors.mew",
+        "-e",
+        "many_symbols_not_found::main",
+    ]);
+    assert_eq!(code, mewc::EXIT_COMPILE_ERROR);
+    assert_eq!(
+        stderr.lines().collect::<Vec<_>>(),
+        vec![
+            "error-inputs/syntax-errors.mew:2:16: error: unexpected token `;`",
+            "error-inputs/syntax-errors.mew:5:17: error: unexpected token `;`",
+            "error-inputs/syntax-errors.mew:10:20: error: unexpected token `)`",
+        ]
+    );
+
+    let (code, _, stderr) = run(&[
+        "check",
+        "--message-format",
+        "short",
+        "error-inputs/many-symbols-not-found.mew",
+        "-e",
+        "many_symbols_not_found::main",
+    ]);
+    assert_eq!(code, mewc::EXIT_COMPILE_ERROR);
+    assert_eq!(stderr.lines().count(), 3);
+    assert!(stderr.starts_with(
+        "error-inputs/many-symbols-not-found.mew:2:22: error: cannot find `MissingType`"
+    ));
+
+    let (code, _, stderr) = run(&["bundle", "error-inputs/missing.mew"]);
+    assert_eq!(code, mewc::EXIT_USAGE_ERROR);
+    assert!(stderr.starts_with("error: error-inputs/missing.mew: "));
+}
+
+#[test]
+fn build_mew_samples() {
+    let out_dir = std::env::temp_dir().join("mew-build-samples");
+    let build = mew_build::Build {
+        modules: vec![PathBuf::from("loader-inputs/root.mew")],
+        entries: vec![mew_build::Entry::new("root::main", "shaders/root.wgsl")],
+        out_dir: Some(out_dir.clone()),
+        ..Default::default()
+    };
+    let compiled = build.try_compile().expect("failed to compile");
+    assert_eq!(compiled.outputs, vec![out_dir.join("shaders/root.wgsl")]);
+
+    let mut dependencies = compiled.dependencies.clone();
+    dependencies.sort();
+    assert_eq!(
+        dependencies,
+        vec![
+            PathBuf::from("loader-inputs/root.mew"),
+            PathBuf::from("loader-inputs/shapes/circle.mew"),
+            PathBuf::from("loader-inputs/shapes/mod.mew"),
+            PathBuf::from("loader-inputs/util/math.mew"),
+        ]
+    );
+
+    let result = std::fs::read_to_string(&compiled.outputs[0]).expect("READ");
+    let expected_output_module = mew_parse::Parser::parse_str(
+        &std::fs::read_to_string("expected-loader-outputs/root.wgsl").expect("READ"),
+    )
+    .expect("parse error");
+    assert_eq!(result, format!("{}", expected_output_module));
+
+    let build = mew_build::Build {
+        modules: vec![PathBuf::from("error-inputs/many-symbols-not-found.mew")],
+        entries: vec![mew_build::Entry::new(
+            "many_symbols_not_found::main",
+            "main.wgsl",
+        )],
+        out_dir: Some(out_dir),
+        ..Default::default()
+    };
+    match build.try_compile() {
+        Err(mew_build::BuildError::Mew(errors)) => {
+            assert_eq!(errors.len(), 3);
+            // build script output is not a terminal
+            assert!(errors.iter().all(|err| !err.contains('\x1b')));
+        }
+        other => panic!("expected shader errors, got {other:?}"),
+    }
+}
+
+/// The Rust types generated for `layout-inputs/layout.mew`.
+#[allow(dead_code)]
+mod host_layout {
+    include!("../expected-host-outputs/layout.rs");
+}
+
+#[test]
+fn host_mew_samples() {
+    use std::mem::{offset_of, size_of};
+
+    let out_dir = std::env::temp_dir().join("mew-host-samples");
+    let build = mew_build::Build {
+        modules: vec![PathBuf::from("layout-inputs/layout.mew")],
+        entries: vec![
+            mew_build::Entry::new("layout::main", "layout.wgsl").with_host_types("layout.rs")
+        ],
+        out_dir: Some(out_dir.clone()),
+        ..Default::default()
+    };
+    let compiled = build.try_compile().expect("failed to compile");
+    assert_eq!(
+        compiled.outputs,
+        vec![out_dir.join("layout.wgsl"), out_dir.join("layout.rs")]
+    );
+    let result = std::fs::read_to_string(&compiled.outputs[1]).expect("READ");
+
+    let expected_output_location: PathBuf = std::env::current_dir()
+        .unwrap()
+        .join("expected-host-outputs")
+        .join("layout.rs");
+
+    #[cfg(feature = "update_expected_output")]
+    {
+        let _ = std::fs::write(expected_output_location.clone(), result.clone()).expect("Written");
+    }
+
+    let expected_output = std::fs::read_to_string(expected_output_location).expect("READ");
+    assert_eq!(result, expected_output);
+
+    // the generated types have the layout of the WGSL structs
+    assert_eq!(size_of::<host_layout::layout_Camera>(), 256);
+    assert_eq!(offset_of!(host_layout::layout_Camera, lights), 80);
+    assert_eq!(offset_of!(host_layout::layout_Camera, exposure), 208);
+    assert_eq!(offset_of!(host_layout::layout_Camera, tint), 216);
+    assert_eq!(size_of::<host_layout::layout_Lights_Light>(), 32);
+    assert_eq!(size_of::<host_layout::layout_Particle>(), 64);
+    assert_eq!(size_of::<host_layout::layout_Particles>(), 16);
+    assert_eq!(size_of::<host_layout::layout_Padded>(), 64);
+    assert_eq!(offset_of!(host_layout::layout_Padded, b), 32);
+    assert_eq!(host_layout::layout_Particles::PARTICLES_OFFSET, 16);
+    assert_eq!(host_layout::layout_Particles::PARTICLES_STRIDE, 64);
+    assert_eq!(
+        (
+            host_layout::LAYOUT_SUMS_GROUP,
+            host_layout::LAYOUT_SUMS_BINDING
+        ),
+        (1, 0)
+    );
+
+    let camera = host_layout::layout_Camera {
+        exposure: 2.0,
+        ..bytemuck::Zeroable::zeroed()
+    };
+    assert_eq!(bytemuck::bytes_of(&camera)[208..212], 2f32.to_le_bytes());
+
+    // structs without a valid layout are reported, rather than turned into host types
+    for (module, module_name) in [
+        ("error-inputs/layout-attributes.mew", "layout_attributes"),
+        ("error-inputs/layout-overflow.mew", "layout_overflow"),
+    ] {
+        let build = mew_build::Build {
+            modules: vec![PathBuf::from(module)],
+            entries: vec![
+                mew_build::Entry::new(format!("{module_name}::main"), "invalid.wgsl")
+                    .with_host_types("invalid.rs"),
+            ],
+            out_dir: Some(out_dir.clone()),
+            ..Default::default()
+        };
+        match build.try_compile() {
+            Err(mew_build::BuildError::Mew(errors)) => assert!(!errors.is_empty()),
+            other => panic!("expected layout errors, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn include_mew_samples() {
+    const ROOT: &str = mew_macros::include_mew!("loader-inputs/root.mew", "root::main");
+
+    let expected_output_module = mew_parse::Parser::parse_str(
+        &std::fs::read_to_string("expected-loader-outputs/root.wgsl").expect("READ"),
+    )
+    .expect("parse error");
+    assert_eq!(ROOT, format!("{}", expected_output_module));
+}
+
+#[test]
+fn validate_mew_samples() {
+    let source =
+        std::fs::read_to_string("error-inputs/invalid-index.mew").expect("failed to read file");
+
+    let mut api = mew_api::MewApi::default();
+    api.add_module(ModuleDescriptor {
+        module_name: "invalid_index",
+        source: mew_api::Source::Text(&source),
+    })
+    .expect("failed to add module");
+
+    let path = Path::Text("invalid_index::main".to_string());
+    api.compile(&path)
+        .expect("invalid WGSL is not validated by default");
+
+    api.validation = Some(mew_api::naga::valid::Capabilities::default());
+    let err = api.compile(&path).expect_err("expected a validation error");
+    assert!(matches!(
+        err.error,
+        mew_api::MewErrorInner::ValidationError(_)
+    ));
+    assert_eq!(err.module_name.as_deref(), Some("invalid_index"));
+    let span = err.span.clone().expect("missing span");
+    let location = api
+        .source_map
+        .location(span.start)
+        .expect("missing location");
+    assert_eq!((location.line, location.column), (10, 8));
+
+    // validation errors point to the declaration whose generated WGSL naga rejects
+    let source =
+        std::fs::read_to_string("error-inputs/out-of-bounds.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi {
+        validation: Some(mew_api::naga::valid::Capabilities::default()),
+        ..Default::default()
+    };
+    api.add_module(ModuleDescriptor {
+        module_name: "bounds",
+        source: mew_api::Source::Text(&source),
+    })
+    .expect("failed to add module");
+    let err = api
+        .compile(&Path::Text("bounds::main".to_string()))
+        .expect_err("expected a validation error");
+    let mew_api::MewErrorInner::ValidationError(message) = &err.error else {
+        panic!("expected a validation error, got {err:?}");
+    };
+    assert!(message.contains("'bounds_pick' is invalid"), "{message}");
+    let span = err.span.clone().expect("missing span");
+    let location = api
+        .source_map
+        .location(span.start)
+        .expect("missing location");
+    assert_eq!((location.line, location.column), (3, 4));
+
+    let source = std::fs::read_to_string("loader-inputs/root.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("loader-inputs"));
+    api.validation = Some(mew_api::naga::valid::Capabilities::default());
+    api.add_module(ModuleDescriptor {
+        module_name: "root",
+        source: mew_api::Source::Text(&source),
+    })
+    .expect("failed to add module");
+    api.compile(&Path::Text("root::main".to_string()))
+        .expect("failed to validate");
+}
+
+#[test]
+fn typecheck_mew_samples() -> Result<(), MewError> {
+    let source =
+        std::fs::read_to_string("error-inputs/type-errors.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi::default();
+    api.add_module(ModuleDescriptor {
+        module_name: "type_errors",
+        source: mew_api::Source::Text(&source),
+    })?;
+    let err = api
+        .compile(&Path::Text("type_errors::main".to_string()))
+        .expect_err("expected type errors");
+    let mut errors: Vec<_> = err
+        .into_errors()
+        .iter()
+        .map(|err| {
+            assert!(matches!(err.error, mew_api::MewErrorInner::TypeError(_)));
+            let span = err.span.clone().expect("missing span");
+            let location = api
+                .source_map
+                .location(span.start)
+                .expect("missing location");
+            (location.line, location.column, err.message())
+        })
+        .collect();
+    errors.sort();
+    let expected = [
+        (
+            11,
+            16,
+            "mismatched types: expected `f32`, found `vec2<f32>`",
+        ),
+        (
+            25,
+            28,
+            "mismatched types: expected `vec4<f32>`, found `vec3<f32>`",
+        ),
+        (
+            26,
+            18,
+            "no overload of `dot` takes arguments (`vec3<f32>`, `vec4<f32>`)",
+        ),
+        (27, 8, "mismatched types: expected `bool`, found `f32`"),
+        (28, 28, "no member `colour` on `type_errors::Light`"),
+        (30, 30, "no member `xyw` on `vec3<f32>`"),
+        (
+            30,
+            35,
+            "`type_errors::brightness` takes 1 argument, 2 given",
+        ),
+    ]
+    .map(|(line, column, message)| (line, column, format!("{message} (in module `type_errors`)")));
+    assert_eq!(errors, expected);
+
+    // inputs that specialize, but that the type checker rejects
+    for (module_name, expected) in [
+        (
+            "template_type_error_1",
+            (
+                56,
+                32,
+                "cannot construct `vec4<f32>` from (`template_type_error_1::Camera_mat4x4f`)",
+            ),
+        ),
+        (
+            "template_type_error_2",
+            (
+                58,
+                40,
+                "cannot construct `vec4<f32>` from (`template_type_error_2::Camera_mat4x4f`)",
+            ),
+        ),
+        // naga rejected it before the type checker existed
+        (
+            "invalid_wgsl",
+            (
+                11,
+                16,
+                "mismatched types: expected `f32`, found `vec2<f32>`",
+            ),
+        ),
+    ] {
+        let file = format!("error-inputs/{}.mew", module_name.replace('_', "-"));
+        let source = std::fs::read_to_string(file).expect("failed to read file");
+        let mut api = mew_api::MewApi::default();
+        api.add_module(ModuleDescriptor {
+            module_name,
+            source: mew_api::Source::Text(&source),
+        })?;
+        let err = api
+            .compile(&Path::Text(format!("{module_name}::main")))
+            .expect_err("expected a type error");
+        let errors: Vec<_> = err
+            .into_errors()
+            .iter()
+            .map(|err| {
+                assert!(matches!(err.error, mew_api::MewErrorInner::TypeError(_)));
+                let span = err.span.clone().expect("missing span");
+                let location = api
+                    .source_map
+                    .location(span.start)
+                    .expect("missing location");
+                (location.line, location.column, err.message())
+            })
+            .collect();
+        let (line, column, message) = expected;
+        let message = format!("{message} (in module `{module_name}`)");
+        assert_eq!(errors, [(line, column, message)], "{module_name}");
+    }
+    Ok(())
+}
+
+#[test]
+fn backend_mew_samples() {
+    use mew_api::naga::back::{glsl, hlsl, msl, spv};
+
+    let source = std::fs::read_to_string("loader-inputs/root.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("loader-inputs"));
+    api.add_module(ModuleDescriptor {
+        module_name: "root",
+        source: mew_api::Source::Text(&source),
+    })
+    .expect("failed to add module");
+    let path = Path::Text("root::main".to_string());
+
+    let (module, _) = api.compile_module(&path).expect("failed to compile");
+    let entry_points: Vec<&str> = module
+        .entry_points
+        .iter()
+        .map(|entry_point| entry_point.name.as_str())
+        .collect();
+    assert_eq!(entry_points, vec!["root_main"]);
+
+    let spirv = api
+        .compile_spirv(&path, &spv::Options::default())
+        .expect("failed to compile to SPIR-V");
+    assert_eq!(spirv[0], 0x07230203);
+
+    let options = glsl::Options {
+        version: glsl::Version::Desktop(450),
+        ..Default::default()
+    };
+    let glsl = api
+        .compile_glsl(&path, &options)
+        .expect("failed to compile to GLSL");
+    assert!(glsl.starts_with("#version 450 core"));
+    assert!(glsl.contains("void main()"));
+
+    let options = hlsl::Options {
+        shader_model: hlsl::ShaderModel::V6_0,
+        ..Default::default()
+    };
+    let hlsl = api
+        .compile_hlsl(&path, &options)
+        .expect("failed to compile to HLSL");
+    assert!(hlsl.contains("root_main("));
+
+    let options = msl::Options {
+        lang_version: (2, 0),
+        ..Default::default()
+    };
+    let msl = api
+        .compile_msl(&path, &options)
+        .expect("failed to compile to MSL");
+    assert!(msl.contains("#include <metal_stdlib>"));
+    assert!(msl.contains("fragment root_mainOutput root_main("));
+}
+
+#[test]
+fn compile_many_mew_samples() -> Result<(), MewError> {
+    let source =
+        std::fs::read_to_string("compile-many-inputs/pipeline.mew").expect("failed to read file");
+
+    let mut api = mew_api::MewApi {
+        validation: Some(mew_api::naga::valid::Capabilities::default()),
+        ..Default::default()
+    };
+    api.add_module(ModuleDescriptor {
+        module_name: "pipeline",
+        source: mew_api::Source::Text(&source),
+    })?;
+
+    let paths = [
+        "pipeline::vs_main",
+        "pipeline::fs_main",
+        "pipeline::cs_main",
+    ]
+    .map(|path| Path::Text(path.to_string()));
+    let result = api.compile_many(&paths)?;
+
+    let expected_output_location: PathBuf = std::env::current_dir()
+        .unwrap()
+        .join("expected-compile-many-outputs")
+        .join("pipeline.wgsl");
+
+    #[cfg(feature = "update_expected_output")]
+    {
+        let _ = std::fs::write(expected_output_location.clone(), result.clone()).expect("Written");
+    }
+
+    let expected_output_module = mew_parse::Parser::parse_str(
+        &std::fs::read_to_string(expected_output_location.clone()).expect("READ"),
+    )
+    .inspect_err(|err| eprintln!("{err}"))
+    .expect("parse error");
+    assert_eq!(result, format!("{}", expected_output_module));
+
+    // the instantiation used by every entry point is emitted once
+    let module = mew_api::naga::front::wgsl::parse_str(&result).expect("invalid WGSL");
+    assert_eq!(module.entry_points.len(), 3);
+    let shade = module
+        .functions
+        .iter()
+        .filter(|(_, function)| function.name.as_deref().unwrap_or("").contains("shade"))
+        .count();
+    assert_eq!(shade, 1);
+
+    // entry points compiled one by one contain the same declarations
+    for path in &paths {
+        let single = api.compile(path)?;
+        for declaration in mew_parse::Parser::parse_str(&single)
+            .expect("parse error")
+            .global_declarations
+        {
+            assert!(expected_output_module
+                .global_declarations
+                .contains(&declaration));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn reflect_mew_samples() -> Result<(), MewError> {
+    use mew_api::reflect::{InterfaceVariable, ShaderStage};
+
+    let source =
+        std::fs::read_to_string("compile-many-inputs/pipeline.mew").expect("failed to read file");
+
+    let mut api = mew_api::MewApi {
+        validation: Some(mew_api::naga::valid::Capabilities::default()),
+        ..Default::default()
+    };
+    api.add_module(ModuleDescriptor {
+        module_name: "pipeline",
+        source: mew_api::Source::Text(&source),
+    })?;
+
+    let paths = [
+        "pipeline::vs_main",
+        "pipeline::fs_main",
+        "pipeline::cs_main",
+    ]
+    .map(|path| Path::Text(path.to_string()));
+    let (wgsl, reflection) = api.compile_with_reflection(&paths)?;
+    assert_eq!(wgsl, api.compile_many(&paths)?);
+
+    let [binding] = reflection.bindings.as_slice() else {
+        panic!("expected one binding, found {:?}", reflection.bindings);
+    };
+    assert_eq!(binding.name.path, ["pipeline", "colors"]);
+    assert!(wgsl.contains(&format!(
+        "var<storage, read_write> {}",
+        binding.name.mangled
+    )));
+    assert_eq!((binding.group, binding.binding), (Some(0), Some(0)));
+    assert_eq!(binding.address_space.as_deref(), Some("storage"));
+    assert_eq!(binding.access_mode.as_deref(), Some("read_write"));
+    assert_eq!(binding.ty, "array<vec3f>");
+
+    let entry_point = |name: &str| {
+        reflection
+            .entry_points
+            .iter()
+            .find(|entry_point| entry_point.name.path == ["pipeline", name])
+            .unwrap_or_else(|| panic!("missing entry point {name}"))
+    };
+    assert_eq!(reflection.entry_points.len(), 3);
+
+    let vs_main = entry_point("vs_main");
+    assert_eq!(vs_main.stage, ShaderStage::Vertex);
+    assert_eq!(vs_main.workgroup_size, None);
+    assert_eq!(
+        vs_main.inputs,
+        [InterfaceVariable {
+            name: Some("index".to_string()),
+            location: None,
+            builtin: Some("vertex_index".to_string()),
+            ty: "u32".to_string(),
+        }]
+    );
+    let locations = |variables: &[InterfaceVariable]| {
+        variables
+            .iter()
+            .map(|variable| (variable.name.clone(), variable.location))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        locations(&vs_main.outputs),
+        [
+            (Some("position".to_string()), None),
+            (Some("color".to_string()), Some(0))
+        ]
+    );
+
+    let fs_main = entry_point("fs_main");
+    assert_eq!(fs_main.stage, ShaderStage::Fragment);
+    assert_eq!(locations(&fs_main.inputs), locations(&vs_main.outputs));
+    assert_eq!(locations(&fs_main.outputs), [(None, Some(0))]);
+
+    let cs_main = entry_point("cs_main");
+    assert_eq!(cs_main.stage, ShaderStage::Compute);
+    assert_eq!(cs_main.workgroup_size, Some([64, 1, 1]));
+
+    // the mangled names are the names in the WGSL
+    for entry_point in &reflection.entry_points {
+        assert!(wgsl.contains(&format!("fn {}(", entry_point.name.mangled)));
+    }
+    Ok(())
+}
+
+#[test]
+fn dce_mew_samples() -> Result<(), MewError> {
+    let source = std::fs::read_to_string("dce-inputs/dce.mew").expect("failed to read file");
+
+    // not validated, naga does not support `const_assert`
+    let mut api = mew_api::MewApi::default();
+    api.add_module(ModuleDescriptor {
+        module_name: "dce",
+        source: mew_api::Source::Text(&source),
+    })?;
+    let result = api.compile(&Path::Text("dce::main".to_string()))?;
+
+    let expected_output_location: PathBuf = std::env::current_dir()
+        .unwrap()
+        .join("expected-dce-outputs")
+        .join("dce.wgsl");
+
+    #[cfg(feature = "update_expected_output")]
+    {
+        let _ = std::fs::write(expected_output_location.clone(), result.clone()).expect("Written");
+    }
+
+    let expected_output_module = mew_parse::Parser::parse_str(
+        &std::fs::read_to_string(expected_output_location.clone()).expect("READ"),
+    )
+    .inspect_err(|err| eprintln!("{err}"))
+    .expect("parse error");
+    assert_eq!(result, format!("{}", expected_output_module));
+
+    for removed in ["unused", "Unused", "cube", "dump"] {
+        assert!(!result.contains(removed), "`{removed}` is not removed");
+    }
+    for kept in ["dce_scale", "dce_Debug_checksum"] {
+        assert!(result.contains(kept), "`{kept}` is removed");
+    }
+    assert!(!result.contains("@keep"));
+    Ok(())
+}
+
+#[test]
+fn lsp_mew_samples() {
+    use mew_lsp::{analysis::File, Analysis};
+
+    let source = std::fs::read_to_string("lsp-inputs/main.mew").expect("failed to read file");
+    let lighting = std::fs::read_to_string("lsp-inputs/lighting.mew").expect("failed to read file");
+    let root = std::path::Path::new("lsp-inputs");
+    let at = |text: &str, needle: &str| text.find(needle).expect("needle") + 1;
+
+    let analysis = Analysis::new("main", &source, Some(root));
+    assert!(analysis.is_parsed());
+    assert_eq!(analysis.diagnostics, vec![]);
+
+    // the item of a `use`, and a use of the imported item
+    for offset in [at(&source, "Light,"), at(&source, "Light(")] {
+        let location = analysis.definition(offset).expect("definition");
+        assert_eq!(
+            location.file,
+            File::Path(PathBuf::from("lsp-inputs/lighting.mew"))
+        );
+        assert_eq!(&lighting[location.range], "Light");
+    }
+    let location = analysis
+        .definition(at(&source, "shade(light"))
+        .expect("definition");
+    assert_eq!(&lighting[location.range], "shade");
+    let location = analysis
+        .definition(at(&source, "presets::white"))
+        .expect("definition");
+    assert_eq!(&lighting[location.range], "presets");
+    let location = analysis
+        .definition(at(&source, "lighting::presets"))
+        .expect("definition");
+    assert_eq!(location.range, 0..0);
+    assert_eq!(analysis.definition(at(&source, "vec4f(shade")), None);
+
+    let hover = analysis.hover(at(&source, "white")).expect("hover");
+    assert_eq!(&source[hover.range], "white");
+    assert_eq!(hover.path, "lighting::presets::white");
+    assert_eq!(hover.declaration, "const white: vec3f = vec3f(1f);");
+    let hover = analysis.hover(at(&source, "shade(light")).expect("hover");
+    assert_eq!(hover.path, "lighting::shade");
+    assert_eq!(hover.declaration, "fn shade(light: Light) -> vec3f");
+
+    // completed from the analysis of the last text that parsed
+    let edited = source.replace("lighting::presets::white", "lighting::");
+    let offset = edited.find("lighting::,").expect("edit") + "lighting::".len();
+    let mut names = analysis
+        .completions(&edited, offset)
+        .into_iter()
+        .map(|completion| completion.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["Light", "presets", "shade"]);
+    let edited = source.replace("lighting::presets::white", "lighting::presets::wh");
+    let offset = edited.find("wh,").expect("edit") + "wh".len();
+    let completions = analysis.completions(&edited, offset);
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].name, "white");
+    assert_eq!(completions[0].kind, mew_lsp::analysis::SymbolKind::Const);
+
+    let unresolved = Analysis::new(
+        "main",
+        &source.replace("Light, shade", "Light, shadow"),
+        Some(root),
+    );
+    assert!(unresolved.is_parsed());
+    assert!(!unresolved.diagnostics.is_empty());
+    let unparsed = Analysis::new("main", &source.replace("{Light", "{Light::"), Some(root));
+    assert!(!unparsed.is_parsed());
+    assert!(!unparsed.diagnostics.is_empty());
+}
+
+#[test]
+fn lsp_server_mew_samples() {
+    use lsp_server::{Connection, Message, Notification, Request, RequestId};
+    use lsp_types::{
+        notification::{
+            DidChangeTextDocument, DidOpenTextDocument, Exit, Initialized, Notification as _,
+            PublishDiagnostics,
+        },
+        request::{HoverRequest, Initialize, Request as _, Shutdown},
+        DidChangeTextDocumentParams, DidOpenTextDocumentParams, Hover, HoverContents, HoverParams,
+        InitializeParams, InitializedParams, Position, PublishDiagnosticsParams,
+        TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+        TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier,
+    };
+
+    let (server, client) = Connection::memory();
+    let server = std::thread::spawn(move || mew_lsp::run(&server).expect("server error"));
+    let request = |id: i32, method: &str, params: serde_json::Value| {
+        let request = Request::new(RequestId::from(id), method.to_string(), params);
+        client.sender.send(Message::Request(request)).expect("send");
+    };
+    let notify = |method: &str, params: serde_json::Value| {
+        let notification = Notification::new(method.to_string(), params);
+        client
+            .sender
+            .send(Message::Notification(notification))
+            .expect("send");
+    };
+
+    request(
+        0,
+        Initialize::METHOD,
+        serde_json::to_value(InitializeParams::default()).unwrap(),
+    );
+    let Ok(Message::Response(response)) = client.receiver.recv() else {
+        panic!("expected the initialize response");
+    };
+    assert!(response.error.is_none());
+    notify(
+        Initialized::METHOD,
+        serde_json::to_value(InitializedParams {}).unwrap(),
+    );
+
+    let source = std::fs::read_to_string("lsp-inputs/main.mew").expect("failed to read file");
+    let path = std::env::current_dir().unwrap().join("lsp-inputs/main.mew");
+    let uri = Url::from_file_path(path).unwrap();
+    let open = DidOpenTextDocumentParams {
+        text_document: TextDocumentItem::new(uri.clone(), "mew".to_string(), 0, source.clone()),
+    };
+    notify(
+        DidOpenTextDocument::METHOD,
+        serde_json::to_value(open).unwrap(),
+    );
+    let Ok(Message::Notification(notification)) = client.receiver.recv() else {
+        panic!("expected the diagnostics");
+    };
+    assert_eq!(notification.method, PublishDiagnostics::METHOD);
+    let diagnostics: PublishDiagnosticsParams =
+        serde_json::from_value(notification.params).unwrap();
+    assert_eq!(diagnostics.uri, uri);
+    assert_eq!(diagnostics.diagnostics, vec![]);
+
+    let request_hover = |id: i32, position: Position| {
+        let hover = HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri.clone()),
+                position,
+            ),
+            work_done_progress_params: Default::default(),
+        };
+        request(
+            id,
+            HoverRequest::METHOD,
+            serde_json::to_value(hover).unwrap(),
+        );
+    };
+    request_hover(1, Position::new(4, 42));
+    let Ok(Message::Response(response)) = client.receiver.recv() else {
+        panic!("expected the hover response");
+    };
+    let hover: Hover = serde_json::from_value(response.result.expect("hover")).unwrap();
+    let HoverContents::Markup(contents) = hover.contents else {
+        panic!("expected markup");
+    };
+    assert!(contents.value.contains("`lighting::presets::white`"));
+
+    // the analysis of the last version that parsed does not match the new text
+    let change = DidChangeTextDocumentParams {
+        text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 1),
+        content_changes: vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: format!("fn broken( {{\n{source}"),
+        }],
+    };
+    notify(
+        DidChangeTextDocument::METHOD,
+        serde_json::to_value(change).unwrap(),
+    );
+    let Ok(Message::Notification(notification)) = client.receiver.recv() else {
+        panic!("expected the diagnostics");
+    };
+    let diagnostics: PublishDiagnosticsParams =
+        serde_json::from_value(notification.params).unwrap();
+    assert!(!diagnostics.diagnostics.is_empty());
+    request_hover(2, Position::new(6, 4));
+    let Ok(Message::Response(response)) = client.receiver.recv() else {
+        panic!("expected the hover response");
+    };
+    assert_eq!(response.result, Some(serde_json::Value::Null));
+
+    assert_eq!(mew_lsp::position("aé", 2), Position::new(0, 1));
+
+    request(3, Shutdown::METHOD, serde_json::Value::Null);
+    let Ok(Message::Response(response)) = client.receiver.recv() else {
+        panic!("expected the shutdown response");
+    };
+    assert!(response.error.is_none());
+    notify(Exit::METHOD, serde_json::Value::Null);
+    server.join().expect("server panicked");
+}
+
+#[test]
+fn index_mew_samples() -> Result<(), MewError> {
+    let source = std::fs::read_to_string("index-inputs/main.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("index-inputs"));
+    api.add_module(ModuleDescriptor {
+        module_name: "main",
+        source: mew_api::Source::Text(&source),
+    })?;
+    let (index, result) = mew_resolve::Resolver.index(&api.translation_unit);
+    result?;
+
+    let text = |span: &std::ops::Range<usize>| {
+        let file = api
+            .source_map
+            .file(api.source_map.lookup(span).expect("file"));
+        format!("{}:{}", file.name, &file.text[file.local_span(span)])
+    };
+    let symbol = |path: &str| {
+        let path = path.split("::").map(str::to_string).collect::<Vec<_>>();
+        index.get(&path).expect("symbol")
+    };
+    let references = |path: &str| {
+        symbol(path)
+            .references
+            .iter()
+            .map(|reference| (text(&reference.span), reference.renamed))
+            .collect::<Vec<_>>()
+    };
+
+    let light = symbol("lighting::Light");
+    assert_eq!(text(&light.definition), "index-inputs/lighting.mew:Light");
+    assert_eq!(
+        references("lighting::Light"),
+        vec![
+            ("main:Light".to_string(), false),
+            ("main:Light".to_string(), false),
+            ("index-inputs/lighting.mew:Light".to_string(), false),
+        ]
+    );
+    // the `use` item, and the name it is imported as
+    assert_eq!(
+        references("lighting::shade"),
+        vec![
+            ("main:shade".to_string(), false),
+            ("main:lit".to_string(), true)
+        ]
+    );
+    // the modules of whole files are named after their file
+    assert_eq!(symbol("lighting").definition, 0..0);
+    assert_eq!(
+        references("lighting"),
+        vec![
+            ("main:lighting".to_string(), false),
+            ("main:lighting".to_string(), false)
+        ]
+    );
+    assert_eq!(
+        references("lighting::Tinted::tint"),
+        vec![("main:tint".to_string(), false)]
+    );
+    // inside inline template args
+    assert_eq!(
+        references("main::half"),
+        vec![("main:half".to_string(), false)]
+    );
+    // template parameters, bound by the members of `with { }` blocks
+    assert_eq!(
+        text(&symbol("lighting::Tinted::factor").definition),
+        "index-inputs/lighting.mew:factor"
+    );
+    assert_eq!(
+        references("lighting::Tinted::factor"),
+        vec![
+            ("main:factor".to_string(), false),
+            ("index-inputs/lighting.mew:factor".to_string(), false)
+        ]
+    );
+    // through the alias generated by `extend util`
+    assert_eq!(references("util"), vec![("main:util".to_string(), false)]);
+    assert_eq!(
+        references("util::double"),
+        vec![("main:double".to_string(), false)]
+    );
+    assert!(index
+        .get(&["main".to_string(), "double".to_string()])
+        .is_none());
+
+    let start = api.source_map.file(api.modules["main"]).start;
+    let (path, _) = index
+        .at(start + source.find("lit(").unwrap())
+        .expect("symbol");
+    assert_eq!(path, ["lighting", "shade"]);
+    Ok(())
+}
+
+#[test]
+fn rename_mew_samples() -> Result<(), MewError> {
+    let source = std::fs::read_to_string("index-inputs/main.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("index-inputs"));
+    api.add_module(ModuleDescriptor {
+        module_name: "main",
+        source: mew_api::Source::Text(&source),
+    })?;
+    let rename = |path: &str, new_name: &str| {
+        api.rename(&Path::Text(path.to_string()), new_name)
+            .map(|renamed| {
+                renamed
+                    .into_iter()
+                    .map(|renamed| (api.source_map.file(renamed.file).name.clone(), renamed.text))
+                    .collect::<HashMap<_, _>>()
+            })
+    };
+
+    let renamed = rename("lighting::Light", "Lamp")?;
+    assert_eq!(renamed.len(), 2);
+    assert!(renamed["main"].starts_with("use lighting::{Lamp, shade as lit}"));
+    assert!(renamed["main"].contains("let light = Lamp(vec3f(1f), 1f);"));
+    let lighting = &renamed["index-inputs/lighting.mew"];
+    assert!(lighting.starts_with("struct Lamp {"));
+    assert!(lighting.contains("fn shade(light: Lamp) -> vec3f {"));
+
+    // the name of the `use ... as` item is kept
+    let renamed = rename("lighting::shade", "shading")?;
+    assert!(renamed["main"].starts_with("use lighting::{Light, shading as lit}"));
+    assert!(renamed["main"].contains("::tint(lit(light))"));
+
+    let renamed = rename("lighting::Tinted::factor", "scale")?;
+    assert!(renamed["main"].contains("with { const scale: f32 = half; }"));
+    let lighting = &renamed["index-inputs/lighting.mew"];
+    assert!(lighting.contains("mod Tinted<scale> {"));
+    assert!(lighting.contains("return color * scale;"));
+
+    // through the alias generated by `extend util`
+    let renamed = rename("util::double", "twice")?;
+    assert!(renamed["index-inputs/util.mew"].starts_with("fn twice(x: f32)"));
+    assert!(renamed["main"].contains("return vec4f(color, twice(1f));"));
+
+    let err = rename("lighting::shade", "Light").expect_err("declared next to it");
+    assert!(matches!(err.error, mew_api::MewErrorInner::NameConflict(_)));
+    assert_eq!(err.module_name.as_deref(), Some("lighting"));
+    // `half` is used where the local variable `light` is in scope
+    let err = rename("main::half", "light").expect_err("captured by a local variable");
+    assert!(matches!(err.error, mew_api::MewErrorInner::NameConflict(_)));
+    assert_eq!(err.module_name.as_deref(), Some("main"));
+    let err = rename("main::half", "fn").expect_err("a keyword");
+    assert!(matches!(
+        err.error,
+        mew_api::MewErrorInner::InvalidRename(_)
+    ));
+    let err = rename("lighting", "shading").expect_err("the module of a file");
+    assert!(matches!(
+        err.error,
+        mew_api::MewErrorInner::InvalidRename(_)
+    ));
+
+    // modules added as syntax trees have no source text to edit
+    let mut ast = mew_parse::Parser::parse_str("mod extra { fn lamp() -> lighting::Light {} }")
+        .expect("parse error");
+    api.add_module(ModuleDescriptor {
+        module_name: "extra",
+        source: mew_api::Source::Ast(&mut ast),
+    })?;
+    let err = api
+        .rename(&Path::Text("lighting::Light".to_string()), "Lamp")
+        .expect_err("a reference without source text");
+    assert!(matches!(
+        err.error,
+        mew_api::MewErrorInner::InvalidRename(_)
+    ));
+    Ok(())
+}
+
+#[test]
+fn visibility_mew_samples() -> Result<(), MewError> {
+    let compile = |module_name: &str| {
+        let source = std::fs::read_to_string(format!("visibility-inputs/{module_name}.mew"))
+            .expect("failed to read file");
+        let mut api =
+            mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("visibility-inputs"));
+        api.add_module(ModuleDescriptor {
+            module_name,
+            source: mew_api::Source::Text(&source),
+        })?;
+        let result = api.compile(&Path::Text(format!("{module_name}::main")));
+        Ok::<_, MewError>((api, result))
+    };
+
+    // private members are used inside their module, and the modules inside it
+    let (_, result) = compile("main")?;
+    let result = result?;
+    assert!(result.contains("shading_linear"));
+    assert!(!result.contains("@private"));
+
+    let (api, result) = compile("leak")?;
+    let errors = result.expect_err("private members").into_errors();
+    let messages = errors
+        .iter()
+        .map(|err| format!("{err}"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        vec![
+            "`shading::linear` is private (in module `leak`)",
+            "`shading::gamma` is private (in module `leak`)",
+        ]
+    );
+    let rendered = errors[0].render_plain(&api.source_map);
+    assert!(rendered.contains("use shading::linear;"));
+    assert!(rendered.contains("not accessible from here"));
+    assert!(rendered.contains("visibility-inputs/shading.mew"));
+    assert!(rendered.contains("declared private here"));
+
+    // `extend` only brings the public members into scope
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("visibility-inputs"));
+    api.add_module(ModuleDescriptor {
+        module_name: "extended",
+        source: mew_api::Source::Text(
+            "extend shading;\n\n@fragment\nfn main() -> @location(0) vec4f {\n    return vec4f(vec3f(linear(1f)), 1f);\n}\n",
+        ),
+    })?;
+    let err = api
+        .compile(&Path::Text("extended::main".to_string()))
+        .expect_err("not in scope");
+    assert_eq!(
+        format!("{err}"),
+        "cannot find `linear` (in module `extended`)"
+    );
+    Ok(())
+}
+
+#[test]
+fn roots_mew_samples() -> Result<(), MewError> {
+    let source = std::fs::read_to_string("roots-inputs/main.mew").expect("failed to read file");
+    let mut api = mew_api::MewApi::with_loader(mew_api::FileSystemLoader::new("roots-inputs"));
+    api.add_module(ModuleDescriptor {
+        module_name: "main",
+        source: mew_api::Source::Text(&source),
+    })?;
+    let result = api.compile(&Path::Text("main::main".to_string()))?;
+    // `lighting` is the constant of `main`, `package::lighting` the module
+    assert!(result.contains(
+        "let light = lighting_Light(main_palette_presets_sunset() * main_lighting, 1f);"
+    ));
+    assert!(result.contains("return vec4f(lighting_shade(light) + main_palette_warm, 1f);"));
+    assert!(result.contains("return main_palette_warm * main_tint;"));
+
+    let (index, result) = mew_resolve::Resolver.index(&api.translation_unit);
+    result?;
+    let text = |span: &std::ops::Range<usize>| {
+        let file = api
+            .source_map
+            .file(api.source_map.lookup(span).expect("file"));
+        file.text[file.local_span(span)].to_string()
+    };
+    let references = |path: &[&str]| {
+        let path = path.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        index
+            .get(&path)
+            .expect("symbol")
+            .references
+            .iter()
+            .map(|reference| text(&reference.span))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(references(&["lighting"]), vec!["lighting"]);
+    assert_eq!(references(&["main", "tint"]), vec!["tint"]);
+    assert_eq!(references(&["main", "palette"]), vec!["palette", "palette"]);
+
+    let mut api = mew_api::MewApi::default();
+    api.add_module(ModuleDescriptor {
+        module_name: "escape",
+        source: mew_api::Source::Text(
+            "use super::super::outside;\n\n@fragment\nfn main() -> @location(0) vec4f {\n    return vec4f(outside);\n}\n",
+        ),
+    })?;
+    let err = api
+        .compile(&Path::Text("escape::main".to_string()))
+        .expect_err("above the translation unit");
+    assert_eq!(
+        format!("{err}"),
+        "cannot find `super::super` (in module `escape`)"
+    );
+    Ok(())
+}
@@ -0,0 +1,3 @@
+mod overload;
+pub mod typecheck;
+pub use typecheck::*;
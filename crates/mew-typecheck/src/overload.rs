@@ -0,0 +1,370 @@
+//! Overload resolution of builtin functions, from the signatures of the WGSL specification
+//! loaded in [`mew_types::builtins`].
+//!
+//! A signature such as `fn dot ( e1: vecN<T>, e2: vecN<T> ) -> T` is read as patterns over
+//! [`Type`]s. Type variables are bound by the arguments, then checked against the types
+//! they are restricted to. Signatures with template parameters or variadic parameters, e.g.
+//! value constructors and `bitcast`, are left to the type checker.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+};
+
+use mew_types::typing::Type;
+use wgsl_spec::ParameterizationKind;
+
+static BUILTIN_OVERLOADS: OnceLock<HashMap<String, Vec<Overload>>> = OnceLock::new();
+
+/// The outcome of calling a builtin function.
+pub(crate) enum Resolution {
+    /// There is no builtin function with this name, or none of its signatures is modelled.
+    NotBuiltin,
+    /// The type of the call, [`Type::Unknown`] if the function returns nothing or if
+    /// several overloads with different results match.
+    Resolved(Type),
+    /// No overload accepts the arguments.
+    NoMatch,
+}
+
+/// The result type of calling the builtin function `name` with `arguments`.
+pub(crate) fn resolve(name: &str, arguments: &[Type]) -> Resolution {
+    let overloads = BUILTIN_OVERLOADS.get_or_init(parse_builtins);
+    let Some(overloads) = overloads.get(name) else {
+        return Resolution::NotBuiltin;
+    };
+    let mut result = None;
+    for overload in overloads {
+        if overload.parameters.len() != arguments.len() {
+            continue;
+        }
+        let mut bindings = Bindings::default();
+        let arguments_match = overload
+            .parameters
+            .iter()
+            .zip(arguments)
+            .all(|(parameter, argument)| bindings.bind(parameter, argument));
+        if !arguments_match || !bindings.check_constraints(&overload.constraints, &HashSet::new()) {
+            continue;
+        }
+        let ty = overload
+            .result
+            .as_ref()
+            .map_or(Type::Unknown, |result| bindings.substitute(result));
+        result = Some(match result {
+            Some(previous) if previous != ty => Type::Unknown,
+            _ => ty,
+        });
+    }
+    match result {
+        Some(ty) => Resolution::Resolved(ty),
+        None => Resolution::NoMatch,
+    }
+}
+
+struct Overload {
+    parameters: Vec<Pattern>,
+    /// `None` if the function returns nothing.
+    result: Option<Pattern>,
+    /// The types each type variable can be, e.g. `S` or `vecN<S>`.
+    constraints: Vec<(String, Vec<Pattern>)>,
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    Type(Type),
+    Var(String),
+    Vector(Size, Box<Pattern>),
+    Matrix(Size, Size, Box<Pattern>),
+    Array(Box<Pattern>),
+    Atomic(Box<Pattern>),
+    /// The address space and access mode are not checked.
+    Ptr(Box<Pattern>),
+    AnyScalar,
+    AnyVector,
+    /// Textures, samplers and other types that are not modelled.
+    Any,
+}
+
+#[derive(Clone, Debug)]
+enum Size {
+    Fixed(u8),
+    Var(String),
+}
+
+fn parse_builtins() -> HashMap<String, Vec<Overload>> {
+    let functions = mew_types::builtins::get_builtin_functions();
+    let mut builtins = HashMap::new();
+    for (name, function) in &functions.functions {
+        let overloads: Vec<_> = function
+            .overloads
+            .iter()
+            .filter_map(|overload| {
+                parse_overload(&overload.signature, &overload.parameterization.typevars)
+            })
+            .collect();
+        if !overloads.is_empty() {
+            builtins.insert(name.clone(), overloads);
+        }
+    }
+    builtins
+}
+
+fn parse_overload(
+    signature: &str,
+    typevars: &std::collections::BTreeMap<String, ParameterizationKind>,
+) -> Option<Overload> {
+    // some signatures of value constructors are merged, e.g. `fn vec2<T> ( e: T ) -> vec2<T>
+    // fn vec2 ( e: S ) -> vec2<S>`
+    let [_, signature] = signature.split("fn ").collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let (name, rest) = signature.split_once('(')?;
+    if name.contains('<') {
+        return None;
+    }
+    let (parameters, result) = rest.rsplit_once(')')?;
+    let variables: HashSet<&str> = typevars.keys().map(String::as_str).collect();
+    let mut parsed = vec![];
+    for parameter in split_top_level(parameters) {
+        if parameter == "..." {
+            return None;
+        }
+        let (_, ty) = parameter.split_once(':')?;
+        parsed.push(parse_pattern(ty, &variables)?);
+    }
+    let result = match result.trim().strip_prefix("->") {
+        Some(result) => Some(parse_pattern(result, &variables)?),
+        None => None,
+    };
+    let constraints = typevars
+        .iter()
+        .filter_map(|(name, kind)| match kind {
+            ParameterizationKind::Types(types) => Some((
+                name.clone(),
+                types
+                    .iter()
+                    .filter_map(|ty| parse_pattern(ty, &variables))
+                    .collect(),
+            )),
+            ParameterizationKind::Description(_) => None,
+        })
+        .collect();
+    Some(Overload {
+        parameters: parsed,
+        result,
+        constraints,
+    })
+}
+
+/// Splits `text` at the commas that are not within template arguments.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(text[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn parse_pattern(text: &str, variables: &HashSet<&str>) -> Option<Pattern> {
+    let text = text.trim();
+    let (name, args) = match text.split_once('<') {
+        Some((name, args)) => (name.trim(), split_top_level(args.strip_suffix('>')?)),
+        None => (text, vec![]),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    let arg = |index: usize| -> Option<Box<Pattern>> {
+        Some(Box::new(parse_pattern(args.get(index)?, variables)?))
+    };
+    if let Some(scalar) = Type::scalar(name) {
+        return Some(Pattern::Type(scalar));
+    }
+    if let Some(size) = name.strip_prefix("vec").and_then(parse_size) {
+        return Some(Pattern::Vector(size, arg(0)?));
+    }
+    if let Some((columns, rows)) = name.strip_prefix("mat").and_then(|dims| {
+        let (columns, rows) = dims.split_once('x')?;
+        Some((parse_size(columns)?, parse_size(rows)?))
+    }) {
+        return Some(Pattern::Matrix(columns, rows, arg(0)?));
+    }
+    let pattern = match name {
+        "scalar" => Pattern::AnyScalar,
+        "vector" => Pattern::AnyVector,
+        "array" => Pattern::Array(arg(0)?),
+        "atomic" => Pattern::Atomic(arg(0)?),
+        "ptr" => Pattern::Ptr(arg(1)?),
+        // some signatures use type variables without listing them, e.g. `atomicAdd`
+        _ if args.is_empty() && (variables.contains(name) || is_variable(name)) => {
+            Pattern::Var(name.to_string())
+        }
+        _ => Pattern::Any,
+    };
+    Some(pattern)
+}
+
+/// `2` to `4`, or a variable such as the `N` of `vecN`.
+fn parse_size(text: &str) -> Option<Size> {
+    match text {
+        "2" | "3" | "4" => text.parse().ok().map(Size::Fixed),
+        _ if is_variable(text) => Some(Size::Var(text.to_string())),
+        _ => None,
+    }
+}
+
+/// Type variables are short upper case names, e.g. `T`, `S` or `T2`.
+fn is_variable(name: &str) -> bool {
+    name.len() <= 2
+        && name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+#[derive(Clone, Default)]
+struct Bindings {
+    types: HashMap<String, Type>,
+    sizes: HashMap<String, u8>,
+    /// The constraint alternative of a type variable, when it is not a plain type, e.g.
+    /// `vecN<S>`: the variable is resolved through it once `N` and `S` are known.
+    substitutions: HashMap<String, Pattern>,
+}
+
+impl Bindings {
+    /// Matches `ty` against `pattern`, binding the variables it contains.
+    fn bind(&mut self, pattern: &Pattern, ty: &Type) -> bool {
+        if *ty == Type::Unknown {
+            return true;
+        }
+        match (pattern, ty) {
+            (Pattern::Type(expected), ty) => ty.converts_to(expected),
+            (Pattern::Var(name), ty) => {
+                let joined = match self.types.get(name) {
+                    Some(bound) => bound.join(ty),
+                    None => Some(ty.clone()),
+                };
+                match joined {
+                    Some(joined) => {
+                        self.types.insert(name.clone(), joined);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            (Pattern::Vector(size, scalar), Type::Vector(n, ty)) => {
+                self.bind_size(size, *n) && self.bind(scalar, ty)
+            }
+            (
+                Pattern::Matrix(columns, rows, scalar),
+                Type::Matrix {
+                    columns: c,
+                    rows: r,
+                    scalar: ty,
+                },
+            ) => self.bind_size(columns, *c) && self.bind_size(rows, *r) && self.bind(scalar, ty),
+            (Pattern::Array(element), Type::Array(ty, _))
+            | (Pattern::Atomic(element), Type::Atomic(ty))
+            | (Pattern::Ptr(element), Type::Ptr(ty)) => self.bind(element, ty),
+            (Pattern::AnyScalar, ty) => ty.is_scalar(),
+            (Pattern::AnyVector, ty) => matches!(ty, Type::Vector(..)),
+            (Pattern::Any, _) => true,
+            _ => false,
+        }
+    }
+
+    fn bind_size(&mut self, size: &Size, n: u8) -> bool {
+        match size {
+            Size::Fixed(size) => *size == n,
+            Size::Var(name) => *self.sizes.entry(name.clone()).or_insert(n) == n,
+        }
+    }
+
+    /// Checks that every bound type variable is one of the types it is restricted to,
+    /// converting abstract types when they are not allowed, e.g. `AbstractInt` to
+    /// `AbstractFloat` for `sqrt`. Variables in `checked` are already checked.
+    fn check_constraints(
+        &mut self,
+        constraints: &[(String, Vec<Pattern>)],
+        checked: &HashSet<String>,
+    ) -> bool {
+        // constraints bind further variables, e.g. `T` being `vecN<S>` binds `S`
+        let Some((name, alternatives)) = constraints
+            .iter()
+            .find(|(name, _)| !checked.contains(name) && self.types.contains_key(name))
+        else {
+            return true;
+        };
+        let mut checked = checked.clone();
+        checked.insert(name.clone());
+        let ty = self.types[name].clone();
+        if !ty.is_known() {
+            return self.check_constraints(constraints, &checked);
+        }
+        for alternative in alternatives {
+            let mut bindings = self.clone();
+            if !bindings.bind(alternative, &ty) {
+                continue;
+            }
+            match alternative {
+                Pattern::Type(ty) => {
+                    bindings.types.insert(name.clone(), ty.clone());
+                }
+                alternative => {
+                    bindings
+                        .substitutions
+                        .insert(name.clone(), alternative.clone());
+                }
+            }
+            if bindings.check_constraints(constraints, &checked) {
+                *self = bindings;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The type matched by `pattern`, with the bound variables.
+    fn substitute(&self, pattern: &Pattern) -> Type {
+        let size = |size: &Size| match size {
+            Size::Fixed(size) => Some(*size),
+            Size::Var(name) => self.sizes.get(name).copied(),
+        };
+        match pattern {
+            Pattern::Type(ty) => ty.clone(),
+            Pattern::Var(name) => match self.substitutions.get(name) {
+                Some(alternative) => self.substitute(alternative),
+                None => self.types.get(name).cloned().unwrap_or(Type::Unknown),
+            },
+            Pattern::Vector(n, scalar) => match size(n) {
+                Some(n) => Type::Vector(n, Box::new(self.substitute(scalar))),
+                None => Type::Unknown,
+            },
+            Pattern::Matrix(columns, rows, scalar) => match (size(columns), size(rows)) {
+                (Some(columns), Some(rows)) => Type::Matrix {
+                    columns,
+                    rows,
+                    scalar: Box::new(self.substitute(scalar)),
+                },
+                _ => Type::Unknown,
+            },
+            Pattern::Array(element) => Type::Array(Box::new(self.substitute(element)), None),
+            Pattern::Atomic(scalar) => Type::Atomic(Box::new(self.substitute(scalar))),
+            Pattern::Ptr(pointee) => Type::Ptr(Box::new(self.substitute(pointee))),
+            Pattern::AnyScalar | Pattern::AnyVector | Pattern::Any => Type::Unknown,
+        }
+    }
+}
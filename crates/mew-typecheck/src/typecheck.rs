@@ -0,0 +1,992 @@
+//! Infers the type of every expression and declaration of a specialized translation unit,
+//! and reports the expressions whose type does not match where they are used.
+//!
+//! The checker runs once templates are specialized and aliases are resolved, so that every
+//! type is concrete, but before names are mangled: errors point at the original source.
+//! It is conservative: a type that cannot be inferred, e.g. the type of a texture or of a
+//! builtin whose signature is not modelled, is [`Type::Unknown`] and never reported.
+
+use std::collections::HashMap;
+
+use mew_parse::{
+    span::{Span, Spanned},
+    syntax::{
+        AssignmentOperator, BinaryOperator, CaseSelector, CompoundStatement, ConstAssert,
+        Declaration, DeclarationKind, Expression, Function, FunctionCallExpression,
+        GlobalDeclaration, LiteralExpression, Module, ModuleMemberDeclaration, PathPart, Statement,
+        Struct, TemplateArg, TranslationUnit, TypeExpression, UnaryOperator,
+    },
+};
+use mew_types::{
+    consteval::ConstEvaluator,
+    typing::{Type, TypeError},
+    CompilerPass, CompilerPassError, ErrorCollector,
+};
+
+use crate::overload::{self, Resolution};
+
+/// Type checks a translation unit after the `Specializer` and `Dealiaser` passes. Reports
+/// every [`TypeError`] it finds, and leaves the translation unit untouched.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TypeChecker;
+
+impl CompilerPass for TypeChecker {
+    fn apply_mut(
+        &mut self,
+        translation_unit: &mut TranslationUnit,
+    ) -> Result<(), CompilerPassError> {
+        let translation_unit: &TranslationUnit = translation_unit;
+        let mut checker = Checker {
+            evaluator: ConstEvaluator::new(translation_unit),
+            ..Default::default()
+        };
+        for declaration in &translation_unit.global_declarations {
+            match &declaration.value {
+                GlobalDeclaration::Declaration(declaration) => {
+                    checker.index(vec![], Item::Declaration(declaration))
+                }
+                GlobalDeclaration::Struct(s) => checker.index(vec![], Item::Struct(s)),
+                GlobalDeclaration::Function(function) => {
+                    checker.index(vec![], Item::Function(function))
+                }
+                GlobalDeclaration::ConstAssert(assert) => {
+                    checker.index(vec![], Item::ConstAssert(assert))
+                }
+                GlobalDeclaration::Module(module) => checker.index_module(vec![], module),
+                GlobalDeclaration::Void | GlobalDeclaration::Alias(_) => {}
+            }
+        }
+        for item in checker.items.clone() {
+            checker.check_item(item);
+        }
+        Ok(checker.errors.finish()?)
+    }
+}
+
+/// A declaration that is type checked.
+#[derive(Clone, Copy)]
+enum Item<'a> {
+    Declaration(&'a Declaration),
+    Struct(&'a Struct),
+    Function(&'a Function),
+    ConstAssert(&'a ConstAssert),
+}
+
+#[derive(Default)]
+struct Checker<'a> {
+    /// Every declaration, by path, e.g. `["my_module", "Lib", "main"]`.
+    globals: HashMap<Vec<String>, Item<'a>>,
+    items: Vec<Item<'a>>,
+    /// The types of the global declarations inferred so far.
+    global_types: HashMap<Vec<String>, Type>,
+    /// The local declarations of the function being checked, innermost scope last.
+    scopes: Vec<HashMap<String, Type>>,
+    /// The return type of the function being checked.
+    return_type: Option<Type>,
+    evaluator: ConstEvaluator<'a>,
+    errors: ErrorCollector,
+}
+
+impl<'a> Checker<'a> {
+    fn index(&mut self, mut path: Vec<String>, item: Item<'a>) {
+        let (name, template_parameters) = match item {
+            Item::Declaration(declaration) => {
+                (Some(&declaration.name), &declaration.template_parameters)
+            }
+            Item::Struct(s) => (Some(&s.name), &s.template_parameters),
+            Item::Function(function) => (Some(&function.name), &function.template_parameters),
+            Item::ConstAssert(assert) => (None, &assert.template_parameters),
+        };
+        // declarations that are not specialized have no concrete types
+        if !template_parameters.is_empty() {
+            return;
+        }
+        if let Some(name) = name {
+            path.push(name.value.clone());
+            self.globals.insert(path, item);
+        }
+        self.items.push(item);
+    }
+
+    fn index_module(&mut self, mut path: Vec<String>, module: &'a Module) {
+        if !module.template_parameters.is_empty() {
+            return;
+        }
+        path.push(module.name.value.clone());
+        for member in &module.members {
+            match &member.value {
+                ModuleMemberDeclaration::Declaration(declaration) => {
+                    self.index(path.clone(), Item::Declaration(declaration))
+                }
+                ModuleMemberDeclaration::Struct(s) => self.index(path.clone(), Item::Struct(s)),
+                ModuleMemberDeclaration::Function(function) => {
+                    self.index(path.clone(), Item::Function(function))
+                }
+                ModuleMemberDeclaration::ConstAssert(assert) => {
+                    self.index(path.clone(), Item::ConstAssert(assert))
+                }
+                ModuleMemberDeclaration::Module(module) => self.index_module(path.clone(), module),
+                ModuleMemberDeclaration::Void | ModuleMemberDeclaration::Alias(_) => {}
+            }
+        }
+    }
+
+    fn check_item(&mut self, item: Item<'a>) {
+        match item {
+            Item::Declaration(declaration) => {
+                self.declaration_type(declaration);
+            }
+            Item::Struct(_) => {}
+            Item::Function(function) => self.check_function(function),
+            Item::ConstAssert(assert) => self.check_condition(&assert.expression),
+        }
+    }
+
+    fn check_function(&mut self, function: &Function) {
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| (parameter.name.value.clone(), self.ty(&parameter.typ)))
+            .collect();
+        self.scopes = vec![parameters];
+        self.return_type = function.return_type.as_ref().map(|ty| self.ty(ty));
+        self.check_compound(&function.body);
+        self.scopes.clear();
+        self.return_type = None;
+    }
+
+    fn check_compound(&mut self, compound: &CompoundStatement) {
+        self.scopes.push(HashMap::new());
+        for statement in &compound.statements {
+            self.check_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_statement(&mut self, statement: &Spanned<Statement>) {
+        match &statement.value {
+            Statement::Void
+            | Statement::Break
+            | Statement::Continue
+            | Statement::Discard
+            | Statement::Return(None) => {}
+            Statement::Compound(compound) => self.check_compound(compound),
+            Statement::Assignment(assignment) => {
+                let rhs = self.infer(&assignment.rhs);
+                // the phony assignment `_ = e` accepts any type
+                if let Expression::Identifier(identifier) = &assignment.lhs.value {
+                    if matches!(identifier.path.as_slice(), [part] if part.name.value == "_") {
+                        return;
+                    }
+                }
+                let lhs = self.infer(&assignment.lhs);
+                let value = match binary_operator(&assignment.operator) {
+                    Some(operator) => {
+                        self.binary(&operator, lhs.clone(), rhs, assignment.rhs.span())
+                    }
+                    None => rhs,
+                };
+                self.expect(&lhs, &value, assignment.rhs.span());
+            }
+            Statement::Increment(expression) | Statement::Decrement(expression) => {
+                self.infer_expression(expression, statement.span());
+            }
+            Statement::If(statement) => {
+                self.check_condition(&statement.if_clause.0);
+                self.check_compound(&statement.if_clause.1);
+                for (condition, body) in &statement.else_if_clauses {
+                    self.check_condition(condition);
+                    self.check_compound(body);
+                }
+                if let Some(body) = &statement.else_clause {
+                    self.check_compound(body);
+                }
+            }
+            Statement::Switch(statement) => {
+                let selector = self.infer(&statement.expression);
+                for clause in &statement.clauses {
+                    for case in &clause.case_selectors {
+                        if let CaseSelector::Expression(expression) = &case.value {
+                            let case_type = self.infer_expression(expression, case.span());
+                            if selector.join(&case_type).is_none() {
+                                self.error(
+                                    TypeError::Mismatch {
+                                        expected: selector.clone(),
+                                        found: case_type,
+                                    },
+                                    case.span(),
+                                );
+                            }
+                        }
+                    }
+                    self.check_compound(&clause.body);
+                }
+            }
+            Statement::Loop(statement) => {
+                // the continuing statement sees the declarations of the body
+                self.scopes.push(HashMap::new());
+                for body_statement in &statement.body.statements {
+                    self.check_statement(body_statement);
+                }
+                if let Some(continuing) = &statement.continuing {
+                    self.check_compound(&continuing.body);
+                    if let Some(break_if) = &continuing.break_if {
+                        self.check_condition(break_if);
+                    }
+                }
+                self.scopes.pop();
+            }
+            Statement::For(statement) => {
+                self.scopes.push(HashMap::new());
+                if let Some(initializer) = &statement.initializer {
+                    self.check_statement(initializer);
+                }
+                if let Some(condition) = &statement.condition {
+                    self.check_condition(condition);
+                }
+                if let Some(update) = &statement.update {
+                    self.check_statement(update);
+                }
+                self.check_compound(&statement.body);
+                self.scopes.pop();
+            }
+            Statement::While(statement) => {
+                self.check_condition(&statement.condition);
+                self.check_compound(&statement.body);
+            }
+            Statement::Return(Some(expression)) => {
+                let ty = self.infer(expression);
+                if let Some(return_type) = self.return_type.clone() {
+                    self.expect(&return_type, &ty, expression.span());
+                }
+            }
+            Statement::FunctionCall(call) => {
+                self.call(call, statement.span());
+            }
+            Statement::ConstAssert(assert) => self.check_condition(&assert.expression),
+            Statement::Declaration(statement) => {
+                let ty = self.declaration_type(&statement.declaration);
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(statement.declaration.name.value.clone(), ty);
+                }
+                for statement in &statement.statements {
+                    self.check_statement(statement);
+                }
+            }
+        }
+    }
+
+    fn check_condition(&mut self, condition: &Spanned<Expression>) {
+        let ty = self.infer(condition);
+        self.expect(&Type::Bool, &ty, condition.span());
+    }
+
+    /// Reports a mismatch if a value of type `found` cannot be used as a value of type
+    /// `expected`.
+    fn expect(&mut self, expected: &Type, found: &Type, span: Span) -> bool {
+        if found.converts_to(expected) {
+            return true;
+        }
+        self.error(
+            TypeError::Mismatch {
+                expected: expected.clone(),
+                found: found.clone(),
+            },
+            span,
+        );
+        false
+    }
+
+    fn error(&mut self, error: TypeError, span: Span) {
+        self.errors.push(CompilerPassError::TypeError(error, span));
+    }
+
+    /// The type of the value declared by `declaration`, after checking its initializer.
+    fn declaration_type(&mut self, declaration: &Declaration) -> Type {
+        let declared = declaration.typ.as_ref().map(|ty| self.ty(ty));
+        let initializer = declaration
+            .initializer
+            .as_ref()
+            .map(|initializer| (self.infer(initializer), initializer.span()));
+        match (declared, initializer) {
+            (Some(declared), Some((ty, span))) => {
+                self.expect(&declared, &ty, span);
+                declared
+            }
+            (Some(declared), None) => declared,
+            (None, Some((ty, _))) if declaration.kind.value == DeclarationKind::Const => ty,
+            (None, Some((ty, _))) => ty.concretize(),
+            (None, None) => Type::Unknown,
+        }
+    }
+
+    /// The type of the global declaration at `path`, inferred on first use.
+    fn global_type(&mut self, path: Vec<String>, declaration: &Declaration) -> Type {
+        if let Some(ty) = self.global_types.get(&path) {
+            return ty.clone();
+        }
+        // declarations whose initializers refer to each other
+        self.global_types.insert(path.clone(), Type::Unknown);
+        // the initializer does not see the locals of the function being checked
+        let scopes = std::mem::take(&mut self.scopes);
+        let ty = self.declaration_type(declaration);
+        self.scopes = scopes;
+        self.global_types.insert(path, ty.clone());
+        ty
+    }
+
+    fn ty(&mut self, ty: &TypeExpression) -> Type {
+        self.path_type(&ty.path)
+    }
+
+    /// The type named by `path`, e.g. `vec3<f32>` or `my_module::Light`.
+    fn path_type(&mut self, path: &[PathPart]) -> Type {
+        let names = names(path);
+        if let Some(Item::Struct(_)) = self.globals.get(&names) {
+            return Type::Struct(names);
+        }
+        let [part] = path else {
+            return Type::Unknown;
+        };
+        let args = part.template_args.as_deref().unwrap_or_default();
+        let name = part.name.as_str();
+        if let Some(scalar) = concrete_scalar(name) {
+            return scalar;
+        }
+        if let Some((shape, scalar)) = shape(name) {
+            let scalar = match scalar {
+                Some(scalar) => scalar,
+                None => match args.first() {
+                    Some(arg) => self.arg_type(arg),
+                    None => return Type::Unknown,
+                },
+            };
+            return shape.ty(scalar);
+        }
+        let mut arg = |index: usize| match args.get(index) {
+            Some(arg) => self.arg_type(arg),
+            None => Type::Unknown,
+        };
+        match name {
+            "array" => {
+                let element = arg(0);
+                let count = args
+                    .get(1)
+                    .and_then(|count| self.constant_u32(&count.expression));
+                Type::Array(Box::new(element), count)
+            }
+            "atomic" => Type::Atomic(Box::new(arg(0))),
+            "ptr" => Type::Ptr(Box::new(arg(1))),
+            _ => Type::Unknown,
+        }
+    }
+
+    fn arg_type(&mut self, arg: &TemplateArg) -> Type {
+        match &arg.expression.value {
+            Expression::Identifier(identifier) => self.path_type(&identifier.path),
+            Expression::Type(ty) => self.path_type(&ty.path),
+            _ => Type::Unknown,
+        }
+    }
+
+    /// The value of `expression` if it is a non-negative integer const-expression.
+    fn constant_u32(&self, expression: &Expression) -> Option<u32> {
+        self.evaluator.evaluate(expression)?.as_u32()
+    }
+
+    fn infer(&mut self, expression: &Spanned<Expression>) -> Type {
+        self.infer_expression(expression, expression.span())
+    }
+
+    fn infer_expression(&mut self, expression: &Expression, span: Span) -> Type {
+        match expression {
+            Expression::Literal(literal) => match &literal.value {
+                LiteralExpression::True | LiteralExpression::False => Type::Bool,
+                LiteralExpression::AbstractInt(_) => Type::AbstractInt,
+                LiteralExpression::AbstractFloat(_) => Type::AbstractFloat,
+                LiteralExpression::I32(_) => Type::I32,
+                LiteralExpression::U32(_) => Type::U32,
+                LiteralExpression::F32(_) => Type::F32,
+                LiteralExpression::F16(_) => Type::F16,
+            },
+            Expression::Parenthesized(expression) => self.infer(expression),
+            Expression::NamedComponent(access) => {
+                let base = self.infer(&access.base);
+                self.component(base, &access.component)
+            }
+            Expression::Indexing(indexing) => {
+                let base = self.infer(&indexing.base);
+                self.infer(&indexing.index);
+                match dereference(base) {
+                    Type::Array(element, _) => *element,
+                    Type::Vector(_, scalar) => *scalar,
+                    Type::Matrix { rows, scalar, .. } => Type::Vector(rows, scalar),
+                    Type::Unknown => Type::Unknown,
+                    base => {
+                        self.error(TypeError::NotIndexable(base), indexing.base.span());
+                        Type::Unknown
+                    }
+                }
+            }
+            Expression::Unary(unary) => {
+                let operand = self.infer(&unary.operand);
+                match unary.operator.value {
+                    UnaryOperator::AddressOf => Type::Ptr(Box::new(operand)),
+                    UnaryOperator::Indirection => match operand {
+                        Type::Ptr(pointee) => *pointee,
+                        _ => Type::Unknown,
+                    },
+                    UnaryOperator::LogicalNegation
+                    | UnaryOperator::Negation
+                    | UnaryOperator::BitwiseComplement => operand,
+                }
+            }
+            Expression::Binary(binary) => {
+                let left = self.infer(&binary.left);
+                let right = self.infer(&binary.right);
+                self.binary(&binary.operator, left, right, span)
+            }
+            Expression::FunctionCall(call) => self.call(call, span),
+            Expression::Identifier(identifier) => self.identifier(&identifier.path),
+            Expression::Type(_) => Type::Unknown,
+        }
+    }
+
+    fn identifier(&mut self, path: &[PathPart]) -> Type {
+        if let [part] = path {
+            let local = self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(part.name.as_str()));
+            if let Some(ty) = local {
+                return ty.clone();
+            }
+        }
+        let names = names(path);
+        match self.globals.get(&names).copied() {
+            Some(Item::Declaration(declaration)) => self.global_type(names, declaration),
+            _ => Type::Unknown,
+        }
+    }
+
+    /// The type of the member or swizzle `component` of a value of type `base`.
+    fn component(&mut self, base: Type, component: &Spanned<String>) -> Type {
+        let no_member = |ty: Type| TypeError::NoMember {
+            ty,
+            member: component.value.clone(),
+        };
+        match dereference(base) {
+            Type::Struct(path) => {
+                let Some(Item::Struct(s)) = self.globals.get(&path).copied() else {
+                    return Type::Unknown;
+                };
+                match s.members.iter().find(|member| member.name == *component) {
+                    Some(member) => self.ty(&member.typ),
+                    None => {
+                        self.error(no_member(Type::Struct(path)), component.span());
+                        Type::Unknown
+                    }
+                }
+            }
+            Type::Vector(size, scalar) => {
+                let set = if component.chars().all(|c| "xyzw".contains(c)) {
+                    "xyzw"
+                } else {
+                    "rgba"
+                };
+                let set = &set[..usize::from(size)];
+                let is_swizzle = (1..=4).contains(&component.len())
+                    && component.chars().all(|c| set.contains(c));
+                match component.len() {
+                    _ if !is_swizzle => {
+                        self.error(no_member(Type::Vector(size, scalar)), component.span());
+                        Type::Unknown
+                    }
+                    1 => *scalar,
+                    len => Type::Vector(len as u8, scalar),
+                }
+            }
+            Type::Unknown => Type::Unknown,
+            base => {
+                self.error(no_member(base), component.span());
+                Type::Unknown
+            }
+        }
+    }
+
+    fn binary(&mut self, operator: &BinaryOperator, left: Type, right: Type, span: Span) -> Type {
+        use BinaryOperator::*;
+        let result = match operator {
+            ShortCircuitOr | ShortCircuitAnd => (left.converts_to(&Type::Bool)
+                && right.converts_to(&Type::Bool))
+            .then_some(Type::Bool),
+            _ if !left.is_known() || !right.is_known() => return Type::Unknown,
+            Addition | Subtraction | Multiplication | Division | Remainder => {
+                arithmetic(operator, &left, &right)
+            }
+            Equality | Inequality => comparison(&left, &right, true),
+            LessThan | LessThanEqual | GreaterThan | GreaterThanEqual => {
+                comparison(&left, &right, false)
+            }
+            BitwiseOr | BitwiseAnd | BitwiseXor => left.join(&right).filter(|ty| {
+                (ty.is_scalar() || matches!(ty, Type::Vector(..)))
+                    && (ty.element().is_integer() || *ty.element() == Type::Bool)
+            }),
+            ShiftLeft | ShiftRight => {
+                // shifted by a `u32`, or a vector of them
+                let amount = match &left {
+                    Type::Vector(size, _) => Type::Vector(*size, Box::new(Type::U32)),
+                    _ => Type::U32,
+                };
+                let is_integer = (left.is_scalar() || matches!(left, Type::Vector(..)))
+                    && left.element().is_integer();
+                (is_integer && right.converts_to(&amount)).then(|| left.clone())
+            }
+        };
+        match result {
+            Some(ty) => ty,
+            None => {
+                self.invalid_operands(operator, left, right, span);
+                Type::Unknown
+            }
+        }
+    }
+
+    fn invalid_operands(&mut self, operator: &BinaryOperator, left: Type, right: Type, span: Span) {
+        self.error(
+            TypeError::InvalidOperands {
+                operator: format!("{operator}"),
+                left,
+                right,
+            },
+            span,
+        );
+    }
+
+    fn call(&mut self, call: &FunctionCallExpression, span: Span) -> Type {
+        let arguments: Vec<Type> = call
+            .arguments
+            .iter()
+            .map(|argument| self.infer(argument))
+            .collect();
+        let names = names(&call.path);
+        match self.globals.get(&names).copied() {
+            Some(Item::Function(function)) => {
+                let name = names.join("::");
+                if self.argument_count(&name, function.parameters.len(), arguments.len(), span) {
+                    for ((parameter, argument), ty) in function
+                        .parameters
+                        .iter()
+                        .zip(&call.arguments)
+                        .zip(&arguments)
+                    {
+                        let parameter = self.ty(&parameter.typ);
+                        self.expect(&parameter, ty, argument.span());
+                    }
+                }
+                return match &function.return_type {
+                    Some(ty) => self.ty(ty),
+                    None => Type::Unknown,
+                };
+            }
+            Some(Item::Struct(s)) => {
+                let name = names.join("::");
+                // `S()` is the zero value
+                if !arguments.is_empty()
+                    && self.argument_count(&name, s.members.len(), arguments.len(), span)
+                {
+                    for ((member, argument), ty) in
+                        s.members.iter().zip(&call.arguments).zip(&arguments)
+                    {
+                        let member = self.ty(&member.typ);
+                        self.expect(&member, ty, argument.span());
+                    }
+                }
+                return Type::Struct(names);
+            }
+            Some(_) => return Type::Unknown,
+            None => {}
+        }
+        let [part] = call.path.as_slice() else {
+            return Type::Unknown;
+        };
+        let template_args = part.template_args.as_deref().unwrap_or_default();
+        let mut template_arg =
+            |index: usize| template_args.get(index).map(|arg| self.arg_type(arg));
+        let name = part.name.as_str();
+        if let Some(scalar) = concrete_scalar(name) {
+            let valid = match arguments.as_slice() {
+                [] => true,
+                [argument] => argument.is_scalar() || !argument.is_known(),
+                _ => false,
+            };
+            if !valid {
+                self.invalid_constructor(scalar.clone(), arguments, span);
+            }
+            return scalar;
+        }
+        if let Some((shape, scalar)) = shape(name) {
+            let scalar = scalar.or_else(|| template_arg(0));
+            return self.construct(shape, scalar, arguments, span);
+        }
+        match name {
+            "array" => {
+                let element = template_arg(0);
+                let count = template_args
+                    .get(1)
+                    .and_then(|count| self.constant_u32(&count.expression));
+                self.construct_array(element, count, &call.arguments, arguments, span)
+            }
+            "bitcast" => template_arg(0).unwrap_or(Type::Unknown),
+            _ => match overload::resolve(name, &arguments) {
+                Resolution::Resolved(ty) => ty,
+                Resolution::NotBuiltin => Type::Unknown,
+                Resolution::NoMatch => {
+                    if arguments.iter().all(Type::is_known) {
+                        self.error(
+                            TypeError::NoMatchingOverload {
+                                function: name.to_string(),
+                                arguments,
+                            },
+                            span,
+                        );
+                    }
+                    Type::Unknown
+                }
+            },
+        }
+    }
+
+    fn argument_count(
+        &mut self,
+        function: &str,
+        expected: usize,
+        found: usize,
+        span: Span,
+    ) -> bool {
+        if expected == found {
+            return true;
+        }
+        self.error(
+            TypeError::ArgumentCount {
+                function: function.to_string(),
+                expected,
+                found,
+            },
+            span,
+        );
+        false
+    }
+
+    /// The type of a vector or matrix value constructor, `scalar` is the explicit or
+    /// predeclared component type, if any.
+    fn construct(
+        &mut self,
+        shape: Shape,
+        scalar: Option<Type>,
+        arguments: Vec<Type>,
+        span: Span,
+    ) -> Type {
+        if !arguments.iter().all(Type::is_known) {
+            return shape.ty(scalar.unwrap_or(Type::Unknown));
+        }
+        let components = |ty: &Type| match ty {
+            _ if ty.is_scalar() => Some(1),
+            Type::Vector(size, _) => Some(*size),
+            _ => None,
+        };
+        // a conversion from a vector or matrix of the same shape accepts any component type
+        let is_conversion = match (shape, arguments.as_slice()) {
+            (Shape::Vector(size), [Type::Vector(from, _)]) => *from == size,
+            (
+                Shape::Matrix(columns, rows),
+                [Type::Matrix {
+                    columns: from_columns,
+                    rows: from_rows,
+                    ..
+                }],
+            ) => *from_columns == columns && *from_rows == rows,
+            _ => false,
+        };
+        let mut is_valid = is_conversion
+            || match shape {
+                Shape::Vector(size) => match arguments.as_slice() {
+                    [] => true,
+                    [argument] => argument.is_scalar(),
+                    arguments => arguments.iter().map(components).sum::<Option<u8>>() == Some(size),
+                },
+                Shape::Matrix(columns, rows) => {
+                    let is_column = |argument: &Type| matches!(argument, Type::Vector(size, _) if *size == rows);
+                    arguments.is_empty()
+                        || arguments.len() == usize::from(columns)
+                            && arguments.iter().all(is_column)
+                        || arguments.len() == usize::from(columns * rows)
+                            && arguments.iter().all(Type::is_scalar)
+                }
+            };
+        // otherwise, the components of every argument have the same type, or convert to the
+        // explicit component type
+        let scalar = match scalar {
+            Some(scalar) => {
+                is_valid &= is_conversion
+                    || arguments
+                        .iter()
+                        .all(|argument| argument.element().converts_to(&scalar));
+                scalar
+            }
+            None if is_conversion => arguments[0].element().clone(),
+            None => {
+                let joined = arguments
+                    .iter()
+                    .try_fold(None, |joined: Option<Type>, argument| {
+                        match joined {
+                            None => Some(argument.element().clone()),
+                            Some(joined) => joined.join(argument.element()),
+                        }
+                        .map(Some)
+                    });
+                match joined {
+                    Some(Some(joined)) => joined,
+                    // the zero value
+                    Some(None) => match shape {
+                        Shape::Vector(_) => Type::AbstractInt,
+                        Shape::Matrix(..) => Type::AbstractFloat,
+                    },
+                    None => {
+                        is_valid = false;
+                        Type::Unknown
+                    }
+                }
+            }
+        };
+        let ty = shape.ty(scalar);
+        if !is_valid {
+            self.invalid_constructor(ty.clone(), arguments, span);
+        }
+        ty
+    }
+
+    fn construct_array(
+        &mut self,
+        element: Option<Type>,
+        count: Option<u32>,
+        argument_expressions: &[Spanned<Expression>],
+        arguments: Vec<Type>,
+        span: Span,
+    ) -> Type {
+        let element = match element {
+            Some(element) => {
+                for (argument, ty) in argument_expressions.iter().zip(&arguments) {
+                    self.expect(&element, ty, argument.span());
+                }
+                element
+            }
+            None => {
+                let joined = arguments
+                    .iter()
+                    .try_fold(Type::Unknown, |joined, argument| match joined {
+                        Type::Unknown => Some(argument.clone()),
+                        joined => joined.join(argument),
+                    });
+                match joined {
+                    Some(element) => element,
+                    None => {
+                        let ty = Type::Array(Box::new(Type::Unknown), Some(arguments.len() as u32));
+                        self.invalid_constructor(ty, arguments, span);
+                        return Type::Unknown;
+                    }
+                }
+            }
+        };
+        let ty = Type::Array(Box::new(element), count.or(Some(arguments.len() as u32)));
+        if count.is_some_and(|count| !arguments.is_empty() && count as usize != arguments.len()) {
+            self.invalid_constructor(ty.clone(), arguments, span);
+        }
+        ty
+    }
+
+    fn invalid_constructor(&mut self, ty: Type, arguments: Vec<Type>, span: Span) {
+        self.error(TypeError::InvalidConstructor { ty, arguments }, span);
+    }
+}
+
+/// The number of rows and columns of a vector or matrix type.
+#[derive(Clone, Copy)]
+enum Shape {
+    Vector(u8),
+    Matrix(u8, u8),
+}
+
+impl Shape {
+    fn ty(self, scalar: Type) -> Type {
+        match self {
+            Shape::Vector(size) => Type::Vector(size, Box::new(scalar)),
+            Shape::Matrix(columns, rows) => Type::Matrix {
+                columns,
+                rows,
+                scalar: Box::new(scalar),
+            },
+        }
+    }
+}
+
+/// `vec2` to `vec4`, `mat2x2` to `mat4x4`, and their predeclared aliases, e.g. `vec3f`
+/// or `mat4x4h`, with their component type.
+fn shape(name: &str) -> Option<(Shape, Option<Type>)> {
+    let dimension = |d: &str| match d {
+        "2" => Some(2),
+        "3" => Some(3),
+        "4" => Some(4),
+        _ => None,
+    };
+    let shape = |name: &str| {
+        if let Some(size) = name.strip_prefix("vec") {
+            return Some(Shape::Vector(dimension(size)?));
+        }
+        let (columns, rows) = name.strip_prefix("mat")?.split_once('x')?;
+        Some(Shape::Matrix(dimension(columns)?, dimension(rows)?))
+    };
+    if let Some(shape) = shape(name) {
+        return Some((shape, None));
+    }
+    let (index, suffix) = name.char_indices().last()?;
+    let scalar = match suffix {
+        'i' => Type::I32,
+        'u' => Type::U32,
+        'f' => Type::F32,
+        'h' => Type::F16,
+        _ => return None,
+    };
+    Some((shape(&name[..index])?, Some(scalar)))
+}
+
+/// The scalar types that can be named in the source.
+fn concrete_scalar(name: &str) -> Option<Type> {
+    Type::scalar(name).filter(|ty| !matches!(ty, Type::AbstractInt | Type::AbstractFloat))
+}
+
+fn names(path: &[PathPart]) -> Vec<String> {
+    path.iter().map(|part| part.name.value.clone()).collect()
+}
+
+/// Members and elements are accessed through pointers too.
+fn dereference(ty: Type) -> Type {
+    match ty {
+        Type::Ptr(pointee) => *pointee,
+        ty => ty,
+    }
+}
+
+fn binary_operator(operator: &AssignmentOperator) -> Option<BinaryOperator> {
+    match operator {
+        AssignmentOperator::Equal => None,
+        AssignmentOperator::PlusEqual => Some(BinaryOperator::Addition),
+        AssignmentOperator::MinusEqual => Some(BinaryOperator::Subtraction),
+        AssignmentOperator::TimesEqual => Some(BinaryOperator::Multiplication),
+        AssignmentOperator::DivisionEqual => Some(BinaryOperator::Division),
+        AssignmentOperator::ModuloEqual => Some(BinaryOperator::Remainder),
+        AssignmentOperator::AndEqual => Some(BinaryOperator::BitwiseAnd),
+        AssignmentOperator::OrEqual => Some(BinaryOperator::BitwiseOr),
+        AssignmentOperator::XorEqual => Some(BinaryOperator::BitwiseXor),
+        AssignmentOperator::ShiftRightAssign => Some(BinaryOperator::ShiftRight),
+        AssignmentOperator::ShiftLeftAssign => Some(BinaryOperator::ShiftLeft),
+    }
+}
+
+/// The type of `left operator right` for the arithmetic operators.
+fn arithmetic(operator: &BinaryOperator, left: &Type, right: &Type) -> Option<Type> {
+    let numeric = |ty: Type| ty.element().is_numeric().then_some(ty);
+    match (left, right) {
+        (Type::Vector(size, scalar), other) | (other, Type::Vector(size, scalar))
+            if other.is_scalar() =>
+        {
+            numeric(Type::Vector(*size, Box::new(scalar.join(other)?)))
+        }
+        (
+            Type::Matrix {
+                columns,
+                rows,
+                scalar,
+            },
+            other,
+        )
+        | (
+            other,
+            Type::Matrix {
+                columns,
+                rows,
+                scalar,
+            },
+        ) if other.is_scalar() && *operator == BinaryOperator::Multiplication => {
+            numeric(Type::Matrix {
+                columns: *columns,
+                rows: *rows,
+                scalar: Box::new(scalar.join(other)?),
+            })
+        }
+        (
+            Type::Matrix {
+                columns,
+                rows,
+                scalar,
+            },
+            Type::Vector(size, other),
+        ) if *operator == BinaryOperator::Multiplication && columns == size => {
+            numeric(Type::Vector(*rows, Box::new(scalar.join(other)?)))
+        }
+        (
+            Type::Vector(size, other),
+            Type::Matrix {
+                columns,
+                rows,
+                scalar,
+            },
+        ) if *operator == BinaryOperator::Multiplication && rows == size => {
+            numeric(Type::Vector(*columns, Box::new(scalar.join(other)?)))
+        }
+        (
+            Type::Matrix {
+                columns: inner,
+                rows,
+                scalar,
+            },
+            Type::Matrix {
+                columns,
+                rows: other_inner,
+                scalar: other,
+            },
+        ) if *operator == BinaryOperator::Multiplication && inner == other_inner => {
+            numeric(Type::Matrix {
+                columns: *columns,
+                rows: *rows,
+                scalar: Box::new(scalar.join(other)?),
+            })
+        }
+        (Type::Matrix { .. }, Type::Matrix { .. })
+            if matches!(
+                operator,
+                BinaryOperator::Addition | BinaryOperator::Subtraction
+            ) =>
+        {
+            numeric(left.join(right)?)
+        }
+        (Type::Matrix { .. }, _) | (_, Type::Matrix { .. }) => None,
+        _ if left.is_scalar() && right.is_scalar() || matches!(left, Type::Vector(..)) => {
+            numeric(left.join(right)?)
+        }
+        _ => None,
+    }
+}
+
+/// The type of a comparison of `left` and `right`.
+fn comparison(left: &Type, right: &Type, allow_bool: bool) -> Option<Type> {
+    let comparable = |scalar: &Type| scalar.is_numeric() || allow_bool && *scalar == Type::Bool;
+    match left.join(right)? {
+        Type::Vector(size, scalar) if comparable(&scalar) => {
+            Some(Type::Vector(size, Box::new(Type::Bool)))
+        }
+        scalar if comparable(&scalar) => Some(Type::Bool),
+        _ => None,
+    }
+}
@@ -0,0 +1,1082 @@
+//! Evaluates WGSL const-expressions: literals, arithmetic, the `const` builtin functions
+//! that are common in template arguments, `const` declarations and the constructors of
+//! scalars, vectors and arrays.
+//!
+//! Template arguments are compared and mangled by how they are written, so
+//! [`ConstEvaluator::canonicalize`] rewrites value arguments to the literal of their value:
+//! `Foo<2u + 2u>` and `Foo<4u>` are then the same instantiation.
+//!
+//! Expressions that are not const-expressions, or that the evaluator does not model (e.g.
+//! `f16` values and matrices), evaluate to `None`, as do those that are errors in WGSL,
+//! e.g. an overflowing `i32` or a division by zero. They are left for naga to report.
+
+use mew_parse::{
+    span::{Span, Spanned},
+    syntax::*,
+};
+
+/// How deep `const` declarations may refer to each other, which also stops cycles.
+const MAX_DEPTH: usize = 64;
+
+/// The value of a const-expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    AbstractInt(i64),
+    AbstractFloat(f64),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    Vector(Vec<Value>),
+    Array(Vec<Value>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    Bool,
+    AbstractInt,
+    AbstractFloat,
+    I32,
+    U32,
+    F32,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Scalar> {
+        match name {
+            "bool" => Some(Scalar::Bool),
+            "i32" => Some(Scalar::I32),
+            "u32" => Some(Scalar::U32),
+            "f32" => Some(Scalar::F32),
+            _ => None,
+        }
+    }
+
+    /// The name of a concrete scalar.
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Scalar::Bool => Some("bool"),
+            Scalar::I32 => Some("i32"),
+            Scalar::U32 => Some("u32"),
+            Scalar::F32 => Some("f32"),
+            Scalar::AbstractInt | Scalar::AbstractFloat => None,
+        }
+    }
+
+    /// The scalar that values of both scalars automatically convert to.
+    fn join(self, other: Scalar) -> Option<Scalar> {
+        match (self, other) {
+            _ if self == other => Some(self),
+            (Scalar::AbstractInt, Scalar::AbstractFloat)
+            | (Scalar::AbstractFloat, Scalar::AbstractInt) => Some(Scalar::AbstractFloat),
+            (Scalar::AbstractInt, other) | (other, Scalar::AbstractInt)
+                if other != Scalar::Bool =>
+            {
+                Some(other)
+            }
+            (Scalar::AbstractFloat, Scalar::F32) | (Scalar::F32, Scalar::AbstractFloat) => {
+                Some(Scalar::F32)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A type named in a constructor or declaration.
+#[derive(Clone, Debug, PartialEq)]
+enum Target {
+    Scalar(Scalar),
+    Vector(usize, Scalar),
+    Array(Box<Target>, Option<usize>),
+}
+
+impl Value {
+    /// The scalar of a scalar or vector.
+    fn scalar(&self) -> Option<Scalar> {
+        match self {
+            Value::Bool(_) => Some(Scalar::Bool),
+            Value::AbstractInt(_) => Some(Scalar::AbstractInt),
+            Value::AbstractFloat(_) => Some(Scalar::AbstractFloat),
+            Value::I32(_) => Some(Scalar::I32),
+            Value::U32(_) => Some(Scalar::U32),
+            Value::F32(_) => Some(Scalar::F32),
+            Value::Vector(components) => components.first()?.scalar(),
+            Value::Array(_) => None,
+        }
+    }
+
+    /// The scalars of the value and every value it contains joined, see [`Scalar::join`].
+    fn leaf_scalar(&self) -> Option<Scalar> {
+        match self {
+            Value::Vector(values) | Value::Array(values) => join_scalars(values),
+            scalar => scalar.scalar(),
+        }
+    }
+
+    /// The value with every abstract scalar converted to `to`.
+    fn convert_leaves(&self, to: Scalar) -> Option<Value> {
+        match self {
+            Value::Vector(components) => Some(Value::Vector(
+                components
+                    .iter()
+                    .map(|component| component.convert_leaves(to))
+                    .collect::<Option<_>>()?,
+            )),
+            Value::Array(elements) => Some(Value::Array(
+                elements
+                    .iter()
+                    .map(|element| element.convert_leaves(to))
+                    .collect::<Option<_>>()?,
+            )),
+            scalar => scalar.convert(&Target::Scalar(to)),
+        }
+    }
+
+    /// Converts a scalar like the value constructor of `to`, e.g. `i32(2.5f)` is `2i`.
+    fn cast(&self, to: Scalar) -> Option<Value> {
+        let value = match (self, to) {
+            (Value::Bool(value), Scalar::Bool) => Value::Bool(*value),
+            (Value::Bool(value), Scalar::I32) => Value::I32(i32::from(*value)),
+            (Value::Bool(value), Scalar::U32) => Value::U32(u32::from(*value)),
+            (Value::Bool(value), Scalar::F32) => Value::F32(f32::from(u8::from(*value))),
+            (Value::Bool(_), _) => return None,
+            (value, Scalar::Bool) => Value::Bool(value.as_f64()? != 0.0),
+            (value, Scalar::AbstractInt) => Value::AbstractInt(value.as_i64()?),
+            (value, Scalar::AbstractFloat) => Value::AbstractFloat(value.as_f64()?),
+            (value, Scalar::I32) => Value::I32(i32::try_from(value.as_i64()?).ok()?),
+            (value, Scalar::U32) => Value::U32(u32::try_from(value.as_i64()?).ok()?),
+            (value, Scalar::F32) => Value::F32(finite_f32(value.as_f64()? as f32)?),
+        };
+        Some(value)
+    }
+
+    /// The value after the automatic conversion of its abstract scalars to `to`, `None`
+    /// if it does not convert.
+    fn convert(&self, to: &Target) -> Option<Value> {
+        match (self, to) {
+            (Value::Vector(components), Target::Vector(size, scalar))
+                if components.len() == *size =>
+            {
+                self.convert_leaves(*scalar)
+            }
+            (Value::Array(elements), Target::Array(element, count))
+                if count.is_none_or(|count| count == elements.len()) =>
+            {
+                Some(Value::Array(
+                    elements
+                        .iter()
+                        .map(|value| value.convert(element))
+                        .collect::<Option<_>>()?,
+                ))
+            }
+            (Value::Vector(_) | Value::Array(_), _) => None,
+            (value, Target::Scalar(to)) => match (value.scalar()?, to) {
+                (from, to) if from == *to => Some(value.clone()),
+                (Scalar::AbstractInt, to) if *to != Scalar::Bool => value.cast(*to),
+                (Scalar::AbstractFloat, Scalar::F32) => value.cast(*to),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The value of a non-negative integer, e.g. the element count of an array.
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Value::AbstractInt(value) => u32::try_from(*value).ok(),
+            Value::I32(value) => u32::try_from(*value).ok(),
+            Value::U32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::AbstractInt(value) => Some(*value),
+            Value::I32(value) => Some(i64::from(*value)),
+            Value::U32(value) => Some(i64::from(*value)),
+            // value constructors round towards zero
+            Value::AbstractFloat(_) | Value::F32(_) => {
+                let value = self.as_f64()?.trunc();
+                (value >= i64::MIN as f64 && value <= i64::MAX as f64).then_some(value as i64)
+            }
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::AbstractInt(value) => Some(*value as f64),
+            Value::AbstractFloat(value) => Some(*value),
+            Value::I32(value) => Some(f64::from(*value)),
+            Value::U32(value) => Some(f64::from(*value)),
+            Value::F32(value) => Some(f64::from(*value)),
+            _ => None,
+        }
+    }
+
+    /// The expression of the value, e.g. `vec2<f32>(1f, 2f)`, with every node at `span`.
+    /// `None` for values that no literal expresses, e.g. the smallest `i32`.
+    pub fn to_expression(&self, span: Span) -> Option<Expression> {
+        let literal = |literal| Some(Expression::Literal(Spanned::new(literal, span.clone())));
+        let negated = |value: Value| {
+            Some(Expression::Unary(UnaryExpression {
+                operator: Spanned::new(UnaryOperator::Negation, span.clone()),
+                operand: Box::new(Spanned::new(
+                    value.to_expression(span.clone())?,
+                    span.clone(),
+                )),
+            }))
+        };
+        match self {
+            Value::Bool(true) => literal(LiteralExpression::True),
+            Value::Bool(false) => literal(LiteralExpression::False),
+            Value::AbstractInt(value) if *value < 0 => {
+                negated(Value::AbstractInt(value.checked_neg()?))
+            }
+            Value::AbstractInt(value) => literal(LiteralExpression::AbstractInt(value.to_string())),
+            Value::I32(value) if *value < 0 => negated(Value::I32(value.checked_neg()?)),
+            Value::I32(value) => literal(LiteralExpression::I32(*value)),
+            Value::U32(value) => literal(LiteralExpression::U32(*value)),
+            Value::AbstractFloat(value) if value.is_sign_negative() => {
+                negated(Value::AbstractFloat(-value))
+            }
+            // the `Debug` format keeps the `.0` of integral floats
+            Value::AbstractFloat(value) => {
+                literal(LiteralExpression::AbstractFloat(format!("{value:?}")))
+            }
+            Value::F32(value) if value.is_sign_negative() => negated(Value::F32(-value)),
+            Value::F32(value) => literal(LiteralExpression::F32(format!("{value:?}"))),
+            Value::Vector(components) => {
+                let ty = self
+                    .scalar()?
+                    .name()
+                    .map(|name| scalar_expression(name, &span));
+                constructor(
+                    format!("vec{}", components.len()),
+                    ty.into_iter().collect(),
+                    components,
+                    span,
+                )
+            }
+            Value::Array(elements) => {
+                let template_args = match self.ty(&span) {
+                    Some(Expression::Type(TypeExpression { mut path })) => path
+                        .pop()?
+                        .template_args?
+                        .into_iter()
+                        .map(|arg| arg.value.expression.value)
+                        .collect(),
+                    _ => vec![],
+                };
+                constructor("array".to_string(), template_args, elements, span)
+            }
+        }
+    }
+
+    /// The expression of the concrete type of the value, `None` for abstract values.
+    fn ty(&self, span: &Span) -> Option<Expression> {
+        match self {
+            Value::Vector(components) => Some(type_expression(
+                format!("vec{}", components.len()),
+                vec![scalar_expression(self.scalar()?.name()?, span)],
+                span,
+            )),
+            Value::Array(elements) => {
+                let count = Value::U32(u32::try_from(elements.len()).ok()?);
+                Some(type_expression(
+                    "array".to_string(),
+                    vec![
+                        elements.first()?.ty(span)?,
+                        count.to_expression(span.clone())?,
+                    ],
+                    span,
+                ))
+            }
+            scalar => Some(scalar_expression(scalar.scalar()?.name()?, span)),
+        }
+    }
+}
+
+fn join_scalars(values: &[Value]) -> Option<Scalar> {
+    let (first, rest) = values.split_first()?;
+    rest.iter().try_fold(first.leaf_scalar()?, |joined, value| {
+        joined.join(value.leaf_scalar()?)
+    })
+}
+
+/// The values with their abstract scalars converted to the scalar they all convert to.
+fn unify(values: &[Value]) -> Option<Vec<Value>> {
+    let scalar = join_scalars(values)?;
+    values
+        .iter()
+        .map(|value| value.convert_leaves(scalar))
+        .collect()
+}
+
+fn finite_f32(value: f32) -> Option<f32> {
+    value.is_finite().then_some(value)
+}
+
+fn finite_f64(value: f64) -> Option<f64> {
+    value.is_finite().then_some(value)
+}
+
+fn path(name: String, template_args: Vec<Expression>, span: &Span) -> Spanned<Vec<PathPart>> {
+    let template_args = (!template_args.is_empty()).then(|| {
+        template_args
+            .into_iter()
+            .map(|expression| {
+                Spanned::new(
+                    TemplateArg {
+                        expression: Spanned::new(expression, span.clone()),
+                        arg_name: None,
+                    },
+                    span.clone(),
+                )
+            })
+            .collect()
+    });
+    Spanned::new(
+        vec![PathPart {
+            name: Spanned::new(name, span.clone()),
+            template_args,
+            inline_template_args: None,
+        }],
+        span.clone(),
+    )
+}
+
+fn scalar_expression(name: &str, span: &Span) -> Expression {
+    Expression::Identifier(IdentifierExpression {
+        path: path(name.to_string(), vec![], span),
+    })
+}
+
+fn type_expression(name: String, template_args: Vec<Expression>, span: &Span) -> Expression {
+    Expression::Type(TypeExpression {
+        path: path(name, template_args, span),
+    })
+}
+
+fn constructor(
+    name: String,
+    template_args: Vec<Expression>,
+    arguments: &[Value],
+    span: Span,
+) -> Option<Expression> {
+    let arguments = arguments
+        .iter()
+        .map(|value| {
+            Some(Spanned::new(
+                value.to_expression(span.clone())?,
+                span.clone(),
+            ))
+        })
+        .collect::<Option<_>>()?;
+    Some(Expression::FunctionCall(FunctionCallExpression {
+        path: path(name, template_args, &span),
+        arguments,
+    }))
+}
+
+/// Evaluates const-expressions, looking up the `const` declarations of a translation unit
+/// that identifiers refer to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstEvaluator<'a> {
+    translation_unit: Option<&'a TranslationUnit>,
+}
+
+impl<'a> ConstEvaluator<'a> {
+    /// An evaluator that looks up the `const` declarations of `translation_unit` that are
+    /// outside of templates by their absolute paths. [`ConstEvaluator::default`] looks up
+    /// none.
+    pub fn new(translation_unit: &'a TranslationUnit) -> Self {
+        ConstEvaluator {
+            translation_unit: Some(translation_unit),
+        }
+    }
+
+    /// The value of `expression`, if it is a const-expression.
+    pub fn evaluate(&self, expression: &Expression) -> Option<Value> {
+        self.evaluate_at(expression, 0)
+    }
+
+    /// Replaces `expression` with the literal of its value, if it is a const-expression.
+    pub fn canonicalize(&self, expression: &mut Spanned<Expression>) {
+        if let Some(canonical) = self
+            .evaluate(expression)
+            .and_then(|value| value.to_expression(expression.span()))
+        {
+            expression.value = canonical;
+        }
+    }
+
+    fn evaluate_at(&self, expression: &Expression, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        match expression {
+            Expression::Literal(literal) => literal_value(literal),
+            Expression::Parenthesized(inner) => self.evaluate_at(inner, depth),
+            Expression::NamedComponent(component) => {
+                let Value::Vector(components) = self.evaluate_at(&component.base, depth)? else {
+                    return None;
+                };
+                let swizzle = component
+                    .component
+                    .chars()
+                    .map(|letter| {
+                        let index = "xyzw".find(letter).or_else(|| "rgba".find(letter))?;
+                        components.get(index).cloned()
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                match swizzle.len() {
+                    1 => swizzle.into_iter().next(),
+                    2..=4 => Some(Value::Vector(swizzle)),
+                    _ => None,
+                }
+            }
+            Expression::Indexing(indexing) => {
+                let index = match self.evaluate_at(&indexing.index, depth)? {
+                    index @ (Value::AbstractInt(_) | Value::I32(_) | Value::U32(_)) => {
+                        usize::try_from(index.as_i64()?).ok()?
+                    }
+                    _ => return None,
+                };
+                match self.evaluate_at(&indexing.base, depth)? {
+                    Value::Vector(values) | Value::Array(values) => values.get(index).cloned(),
+                    _ => None,
+                }
+            }
+            Expression::Unary(unary) => {
+                unary_operation(&unary.operator, self.evaluate_at(&unary.operand, depth)?)
+            }
+            Expression::Binary(binary) => {
+                let left = self.evaluate_at(&binary.left, depth)?;
+                // `false && x` and `true || x` do not evaluate `x`
+                match (&binary.operator.value, &left) {
+                    (BinaryOperator::ShortCircuitAnd, Value::Bool(false))
+                    | (BinaryOperator::ShortCircuitOr, Value::Bool(true)) => return Some(left),
+                    _ => {}
+                }
+                let right = self.evaluate_at(&binary.right, depth)?;
+                binary_operation(&binary.operator, left, right)
+            }
+            Expression::FunctionCall(call) => {
+                let arguments = call
+                    .arguments
+                    .iter()
+                    .map(|argument| self.evaluate_at(argument, depth))
+                    .collect::<Option<Vec<_>>>()?;
+                let [part] = &call.path[..] else {
+                    return None;
+                };
+                let evaluate = |expression: &Expression| self.evaluate_at(expression, depth);
+                construct(part, &arguments, &evaluate)
+                    .or_else(|| call_builtin(&part.name, arguments))
+            }
+            Expression::Identifier(identifier) => self.constant(&identifier.path, depth),
+            Expression::Type(_) => None,
+        }
+    }
+
+    /// The value of the `const` declaration at `path`.
+    fn constant(&self, path: &[PathPart], depth: usize) -> Option<Value> {
+        if path.iter().any(|part| part.template_args.is_some()) {
+            return None;
+        }
+        let declaration = self.declaration(path)?;
+        if declaration.kind.value != DeclarationKind::Const
+            || !declaration.template_parameters.is_empty()
+        {
+            return None;
+        }
+        let value = self.evaluate_at(declaration.initializer.as_ref()?, depth + 1)?;
+        match declaration.typ.as_ref() {
+            Some(ty) => {
+                let evaluate = |expression: &Expression| self.evaluate_at(expression, depth + 1);
+                value.convert(&target(&ty.path, &evaluate)?)
+            }
+            None => Some(value),
+        }
+    }
+
+    /// The declaration at `path`, outside of templated modules.
+    fn declaration(&self, path: &[PathPart]) -> Option<&'a Declaration> {
+        let translation_unit = self.translation_unit?;
+        let (name, modules) = path.split_last()?;
+        let Some((first, modules)) = modules.split_first() else {
+            return translation_unit
+                .global_declarations
+                .iter()
+                .find_map(|declaration| match declaration.as_ref() {
+                    GlobalDeclaration::Declaration(declaration)
+                        if declaration.name == name.name =>
+                    {
+                        Some(declaration)
+                    }
+                    _ => None,
+                });
+        };
+        let mut module = translation_unit
+            .global_declarations
+            .iter()
+            .find_map(|declaration| match declaration.as_ref() {
+                GlobalDeclaration::Module(module) if module.name == first.name => Some(module),
+                _ => None,
+            })?;
+        for part in modules {
+            if !module.template_parameters.is_empty() {
+                return None;
+            }
+            module = module
+                .members
+                .iter()
+                .find_map(|member| match member.as_ref() {
+                    ModuleMemberDeclaration::Module(inner) if inner.name == part.name => {
+                        Some(inner)
+                    }
+                    _ => None,
+                })?;
+        }
+        if !module.template_parameters.is_empty() {
+            return None;
+        }
+        module
+            .members
+            .iter()
+            .find_map(|member| match member.as_ref() {
+                ModuleMemberDeclaration::Declaration(declaration)
+                    if declaration.name == name.name =>
+                {
+                    Some(declaration)
+                }
+                _ => None,
+            })
+    }
+}
+
+fn literal_value(literal: &LiteralExpression) -> Option<Value> {
+    let value = match literal {
+        LiteralExpression::True => Value::Bool(true),
+        LiteralExpression::False => Value::Bool(false),
+        LiteralExpression::AbstractInt(value) => Value::AbstractInt(value.parse().ok()?),
+        LiteralExpression::AbstractFloat(value) => Value::AbstractFloat(value.parse().ok()?),
+        LiteralExpression::I32(value) => Value::I32(*value),
+        LiteralExpression::U32(value) => Value::U32(*value),
+        LiteralExpression::F32(value) => Value::F32(value.parse().ok()?),
+        LiteralExpression::F16(_) => return None,
+    };
+    Some(value)
+}
+
+/// Applies `operation` to the components of vectors, or to the scalars themselves.
+fn componentwise(
+    arguments: &[Value],
+    operation: &dyn Fn(&[Value]) -> Option<Value>,
+) -> Option<Value> {
+    match arguments.first()? {
+        Value::Vector(first) => {
+            let components = (0..first.len())
+                .map(|index| {
+                    let arguments = arguments
+                        .iter()
+                        .map(|argument| match argument {
+                            Value::Vector(components) if components.len() == first.len() => {
+                                Some(components[index].clone())
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    operation(&arguments)
+                })
+                .collect::<Option<_>>()?;
+            Some(Value::Vector(components))
+        }
+        Value::Array(_) => None,
+        _ if arguments.iter().all(|argument| argument.scalar().is_some()) => operation(arguments),
+        _ => None,
+    }
+}
+
+fn unary_operation(operator: &UnaryOperator, operand: Value) -> Option<Value> {
+    componentwise(&[operand], &|operand| match (operator, &operand[0]) {
+        (UnaryOperator::LogicalNegation, Value::Bool(value)) => Some(Value::Bool(!value)),
+        (UnaryOperator::Negation, Value::AbstractInt(value)) => {
+            value.checked_neg().map(Value::AbstractInt)
+        }
+        (UnaryOperator::Negation, Value::I32(value)) => value.checked_neg().map(Value::I32),
+        (UnaryOperator::Negation, Value::AbstractFloat(value)) => {
+            Some(Value::AbstractFloat(-value))
+        }
+        (UnaryOperator::Negation, Value::F32(value)) => Some(Value::F32(-value)),
+        (UnaryOperator::BitwiseComplement, Value::AbstractInt(value)) => {
+            Some(Value::AbstractInt(!value))
+        }
+        (UnaryOperator::BitwiseComplement, Value::I32(value)) => Some(Value::I32(!value)),
+        (UnaryOperator::BitwiseComplement, Value::U32(value)) => Some(Value::U32(!value)),
+        _ => None,
+    })
+}
+
+/// The operations of `operator` on integers, where overflows and divisions by zero are
+/// errors.
+macro_rules! integer_operation {
+    ($operator:expr, $variant:ident, $left:expr, $right:expr) => {
+        match $operator {
+            BinaryOperator::Addition => $left.checked_add($right).map(Value::$variant),
+            BinaryOperator::Subtraction => $left.checked_sub($right).map(Value::$variant),
+            BinaryOperator::Multiplication => $left.checked_mul($right).map(Value::$variant),
+            BinaryOperator::Division => $left.checked_div($right).map(Value::$variant),
+            BinaryOperator::Remainder => $left.checked_rem($right).map(Value::$variant),
+            BinaryOperator::BitwiseAnd => Some(Value::$variant($left & $right)),
+            BinaryOperator::BitwiseOr => Some(Value::$variant($left | $right)),
+            BinaryOperator::BitwiseXor => Some(Value::$variant($left ^ $right)),
+            operator => compare(operator, $left.partial_cmp(&$right)?),
+        }
+    };
+}
+
+/// The operations of `operator` on floats, whose results must be finite.
+macro_rules! float_operation {
+    ($operator:expr, $variant:ident, $finite:ident, $left:expr, $right:expr) => {
+        match $operator {
+            BinaryOperator::Addition => $finite($left + $right).map(Value::$variant),
+            BinaryOperator::Subtraction => $finite($left - $right).map(Value::$variant),
+            BinaryOperator::Multiplication => $finite($left * $right).map(Value::$variant),
+            BinaryOperator::Division => $finite($left / $right).map(Value::$variant),
+            BinaryOperator::Remainder => $finite($left % $right).map(Value::$variant),
+            operator => compare(operator, $left.partial_cmp(&$right)?),
+        }
+    };
+}
+
+fn compare(operator: &BinaryOperator, ordering: std::cmp::Ordering) -> Option<Value> {
+    let result = match operator {
+        BinaryOperator::Equality => ordering.is_eq(),
+        BinaryOperator::Inequality => ordering.is_ne(),
+        BinaryOperator::LessThan => ordering.is_lt(),
+        BinaryOperator::LessThanEqual => ordering.is_le(),
+        BinaryOperator::GreaterThan => ordering.is_gt(),
+        BinaryOperator::GreaterThanEqual => ordering.is_ge(),
+        _ => return None,
+    };
+    Some(Value::Bool(result))
+}
+
+fn binary_operation(operator: &BinaryOperator, left: Value, right: Value) -> Option<Value> {
+    if matches!(
+        operator,
+        BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight
+    ) {
+        return shift(operator, left, right);
+    }
+    let [left, right] = <[Value; 2]>::try_from(unify(&[left, right])?).ok()?;
+    // arithmetic broadcasts scalars to vectors
+    let is_arithmetic = matches!(
+        operator,
+        BinaryOperator::Addition
+            | BinaryOperator::Subtraction
+            | BinaryOperator::Multiplication
+            | BinaryOperator::Division
+            | BinaryOperator::Remainder
+    );
+    let (left, right) = match (left, right) {
+        (Value::Vector(components), scalar) if is_arithmetic && scalar.scalar().is_some() => {
+            let size = components.len();
+            (Value::Vector(components), Value::Vector(vec![scalar; size]))
+        }
+        (scalar, Value::Vector(components)) if is_arithmetic && scalar.scalar().is_some() => {
+            let size = components.len();
+            (Value::Vector(vec![scalar; size]), Value::Vector(components))
+        }
+        operands => operands,
+    };
+    componentwise(
+        &[left, right],
+        &|operands| match (&operands[0], &operands[1]) {
+            (Value::Bool(left), Value::Bool(right)) => match operator {
+                BinaryOperator::ShortCircuitAnd | BinaryOperator::BitwiseAnd => {
+                    Some(Value::Bool(*left && *right))
+                }
+                BinaryOperator::ShortCircuitOr | BinaryOperator::BitwiseOr => {
+                    Some(Value::Bool(*left || *right))
+                }
+                BinaryOperator::BitwiseXor => Some(Value::Bool(left != right)),
+                BinaryOperator::Equality => Some(Value::Bool(left == right)),
+                BinaryOperator::Inequality => Some(Value::Bool(left != right)),
+                _ => None,
+            },
+            (Value::AbstractInt(left), Value::AbstractInt(right)) => {
+                integer_operation!(operator, AbstractInt, left, *right)
+            }
+            (Value::I32(left), Value::I32(right)) => {
+                integer_operation!(operator, I32, left, *right)
+            }
+            (Value::U32(left), Value::U32(right)) => {
+                integer_operation!(operator, U32, left, *right)
+            }
+            (Value::AbstractFloat(left), Value::AbstractFloat(right)) => {
+                float_operation!(operator, AbstractFloat, finite_f64, left, right)
+            }
+            (Value::F32(left), Value::F32(right)) => {
+                float_operation!(operator, F32, finite_f32, left, right)
+            }
+            _ => None,
+        },
+    )
+}
+
+/// `<<` and `>>`, whose right operand is a `u32` shift amount. Shifting out bits that
+/// differ from the sign is an error, as is shifting by the bit width or more.
+fn shift(operator: &BinaryOperator, left: Value, right: Value) -> Option<Value> {
+    let right = right.convert_leaves(Scalar::U32)?;
+    let right = match (&left, right) {
+        (Value::Vector(components), Value::U32(amount)) => {
+            Value::Vector(vec![Value::U32(amount); components.len()])
+        }
+        (_, right) => right,
+    };
+    componentwise(&[left, right], &|operands| {
+        let Value::U32(amount) = operands[1] else {
+            return None;
+        };
+        let is_left = *operator == BinaryOperator::ShiftLeft;
+        match operands[0] {
+            Value::AbstractInt(value) if amount < 64 => {
+                let shifted = if is_left {
+                    value << amount
+                } else {
+                    value >> amount
+                };
+                (!is_left || shifted >> amount == value).then_some(Value::AbstractInt(shifted))
+            }
+            Value::I32(value) if amount < 32 => {
+                let shifted = if is_left {
+                    value << amount
+                } else {
+                    value >> amount
+                };
+                (!is_left || shifted >> amount == value).then_some(Value::I32(shifted))
+            }
+            Value::U32(value) if amount < 32 => {
+                let shifted = if is_left {
+                    value << amount
+                } else {
+                    value >> amount
+                };
+                (!is_left || shifted >> amount == value).then_some(Value::U32(shifted))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// The value of a value constructor call, e.g. `vec3f(1, 2, 3)` or `u32(4)`.
+fn construct(
+    part: &PathPart,
+    arguments: &[Value],
+    evaluate: &dyn Fn(&Expression) -> Option<Value>,
+) -> Option<Value> {
+    match &part.name[..] {
+        "array" => {
+            let count = part
+                .template_args
+                .as_ref()
+                .and_then(|args| args.get(1))
+                .map(|count| usize::try_from(evaluate(&count.expression)?.as_i64()?).ok());
+            let element = match part.template_args.as_ref().and_then(|args| args.first()) {
+                Some(element) => Some(type_argument(&element.expression, evaluate)?),
+                None => None,
+            };
+            if arguments.is_empty() || count.is_some_and(|count| count != Some(arguments.len())) {
+                return None;
+            }
+            let elements = match element {
+                Some(element) => arguments
+                    .iter()
+                    .map(|argument| argument.convert(&element))
+                    .collect::<Option<Vec<_>>>()?,
+                None => unify(arguments)?,
+            };
+            // the elements must all have the same type
+            let first = elements.first()?;
+            let same_shape = |value: &Value| match (first, value) {
+                (Value::Vector(first), Value::Vector(value))
+                | (Value::Array(first), Value::Array(value)) => first.len() == value.len(),
+                (first, value) => std::mem::discriminant(first) == std::mem::discriminant(value),
+            };
+            elements
+                .iter()
+                .all(same_shape)
+                .then_some(Value::Array(elements))
+        }
+        name => match target(std::slice::from_ref(part), evaluate) {
+            Some(Target::Scalar(scalar)) => match arguments {
+                [] => Value::AbstractInt(0).cast(scalar),
+                [argument] => argument.cast(scalar),
+                _ => None,
+            },
+            Some(Target::Vector(size, scalar)) => construct_vector(size, Some(scalar), arguments),
+            Some(Target::Array(..)) => None,
+            // `vec3(...)` infers its scalar from the arguments
+            None => {
+                let size = match name {
+                    "vec2" => 2,
+                    "vec3" => 3,
+                    "vec4" => 4,
+                    _ => return None,
+                };
+                part.template_args
+                    .is_none()
+                    .then(|| construct_vector(size, None, arguments))?
+            }
+        },
+    }
+}
+
+fn construct_vector(size: usize, scalar: Option<Scalar>, arguments: &[Value]) -> Option<Value> {
+    if let (Some(scalar), []) = (scalar, arguments) {
+        return Some(Value::Vector(vec![
+            Value::AbstractInt(0).cast(scalar)?;
+            size
+        ]));
+    }
+    // a vector of another scalar converts component-wise
+    if let (Some(scalar), [Value::Vector(components)]) = (scalar, arguments) {
+        if components.len() == size {
+            let components = components
+                .iter()
+                .map(|component| component.cast(scalar))
+                .collect::<Option<_>>()?;
+            return Some(Value::Vector(components));
+        }
+    }
+    let mut components = vec![];
+    for argument in arguments {
+        match argument {
+            Value::Vector(inner) => components.extend(inner.iter().cloned()),
+            Value::Array(_) => return None,
+            scalar => components.push(scalar.clone()),
+        }
+    }
+    if let [component] = &components[..] {
+        components = vec![component.clone(); size];
+    }
+    if components.len() != size {
+        return None;
+    }
+    let scalar = match scalar {
+        Some(scalar) => scalar,
+        None => join_scalars(&components)?,
+    };
+    Some(Value::Vector(
+        components
+            .iter()
+            .map(|component| component.convert(&Target::Scalar(scalar)))
+            .collect::<Option<_>>()?,
+    ))
+}
+
+/// The builtin function `name` applied to `arguments`.
+fn call_builtin(name: &str, arguments: Vec<Value>) -> Option<Value> {
+    match name {
+        "all" | "any" => {
+            let [argument] = &arguments[..] else {
+                return None;
+            };
+            let values = match argument {
+                Value::Vector(components) => components.clone(),
+                scalar => vec![scalar.clone()],
+            };
+            let booleans = values
+                .iter()
+                .map(|value| match value {
+                    Value::Bool(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(Value::Bool(if name == "all" {
+                booleans.iter().all(|value| *value)
+            } else {
+                booleans.iter().any(|value| *value)
+            }))
+        }
+        "select" => {
+            let [on_false, on_true, condition] = &arguments[..] else {
+                return None;
+            };
+            let [on_false, on_true] =
+                <[Value; 2]>::try_from(unify(&[on_false.clone(), on_true.clone()])?).ok()?;
+            match condition {
+                Value::Bool(true) => Some(on_true),
+                Value::Bool(false) => Some(on_false),
+                Value::Vector(_) => componentwise(
+                    &[on_false, on_true, condition.clone()],
+                    &|args| match args[2] {
+                        Value::Bool(condition) => {
+                            Some(if condition { &args[1] } else { &args[0] }.clone())
+                        }
+                        _ => None,
+                    },
+                ),
+                _ => None,
+            }
+        }
+        "dot" => {
+            let [Value::Vector(left), Value::Vector(right)] = &unify(&arguments)?[..] else {
+                return None;
+            };
+            if left.len() != right.len() {
+                return None;
+            }
+            left.iter()
+                .zip(right)
+                .try_fold(None, |sum, (left, right)| {
+                    let product = binary_operation(
+                        &BinaryOperator::Multiplication,
+                        left.clone(),
+                        right.clone(),
+                    )?;
+                    Some(Some(match sum {
+                        Some(sum) => binary_operation(&BinaryOperator::Addition, sum, product)?,
+                        None => product,
+                    }))
+                })?
+        }
+        "min" | "max" | "clamp" => {
+            let arguments = unify(&arguments)?;
+            let pick = |left: &Value, right: &Value, less: bool| {
+                let ordering = left.as_f64()?.partial_cmp(&right.as_f64()?)?;
+                Some(
+                    if ordering.is_lt() == less {
+                        left
+                    } else {
+                        right
+                    }
+                    .clone(),
+                )
+            };
+            componentwise(&arguments, &|args| match (name, args) {
+                ("min", [left, right]) => pick(left, right, true),
+                ("max", [left, right]) => pick(left, right, false),
+                ("clamp", [value, low, high]) => pick(&pick(value, low, false)?, high, true),
+                _ => None,
+            })
+        }
+        "abs" | "sign" | "countOneBits" => componentwise(&arguments, &|args| match (name, args) {
+            ("abs", [Value::AbstractInt(value)]) => value.checked_abs().map(Value::AbstractInt),
+            ("abs", [Value::I32(value)]) => Some(Value::I32(value.wrapping_abs())),
+            ("abs", [Value::U32(value)]) => Some(Value::U32(*value)),
+            ("abs", [Value::AbstractFloat(value)]) => Some(Value::AbstractFloat(value.abs())),
+            ("abs", [Value::F32(value)]) => Some(Value::F32(value.abs())),
+            ("sign", [Value::AbstractInt(value)]) => Some(Value::AbstractInt(value.signum())),
+            ("sign", [Value::I32(value)]) => Some(Value::I32(value.signum())),
+            ("sign", [value @ (Value::AbstractFloat(_) | Value::F32(_))]) => {
+                let value = value.as_f64()?;
+                let sign = if value == 0.0 { 0.0 } else { value.signum() };
+                float_like(&args[0], sign)
+            }
+            ("countOneBits", [Value::I32(value)]) => {
+                Some(Value::I32(i32::try_from(value.count_ones()).ok()?))
+            }
+            ("countOneBits", [Value::U32(value)]) => Some(Value::U32(value.count_ones())),
+            _ => None,
+        }),
+        "floor" | "ceil" | "round" | "trunc" | "fract" | "sqrt" | "exp" | "exp2" | "log"
+        | "log2" | "pow" => {
+            // integers convert to floats
+            let arguments = arguments
+                .iter()
+                .map(|argument| match argument.leaf_scalar()? {
+                    Scalar::AbstractInt => argument.convert_leaves(Scalar::AbstractFloat),
+                    _ => Some(argument.clone()),
+                })
+                .collect::<Option<Vec<_>>>()?;
+            let arguments = unify(&arguments)?;
+            componentwise(&arguments, &|args| {
+                let x = args.first()?;
+                if !matches!(x, Value::AbstractFloat(_) | Value::F32(_)) {
+                    return None;
+                }
+                let value = x.as_f64()?;
+                let result = match (name, args.len()) {
+                    ("floor", 1) => value.floor(),
+                    ("ceil", 1) => value.ceil(),
+                    ("round", 1) => value.round_ties_even(),
+                    ("trunc", 1) => value.trunc(),
+                    ("fract", 1) => value - value.floor(),
+                    ("sqrt", 1) if value >= 0.0 => value.sqrt(),
+                    ("exp", 1) => value.exp(),
+                    ("exp2", 1) => value.exp2(),
+                    ("log", 1) if value > 0.0 => value.ln(),
+                    ("log2", 1) if value > 0.0 => value.log2(),
+                    ("pow", 2) => value.powf(args[1].as_f64()?),
+                    _ => return None,
+                };
+                float_like(x, result)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// `value` as a float of the same type as `like`.
+fn float_like(like: &Value, value: f64) -> Option<Value> {
+    match like {
+        Value::AbstractFloat(_) => finite_f64(value).map(Value::AbstractFloat),
+        Value::F32(_) => finite_f32(value as f32).map(Value::F32),
+        _ => None,
+    }
+}
+
+/// The type named by a template argument, e.g. `f32` in `array<f32, 4>`.
+fn type_argument(
+    expression: &Expression,
+    evaluate: &dyn Fn(&Expression) -> Option<Value>,
+) -> Option<Target> {
+    match expression {
+        Expression::Identifier(IdentifierExpression { path })
+        | Expression::Type(TypeExpression { path }) => target(path, evaluate),
+        _ => None,
+    }
+}
+
+/// The type named by `path`, e.g. `u32`, `vec3<f32>`, `vec3f` or `array<u32, 4>`.
+fn target(path: &[PathPart], evaluate: &dyn Fn(&Expression) -> Option<Value>) -> Option<Target> {
+    let [part] = path else {
+        return None;
+    };
+    let template_args = part.template_args.as_deref().unwrap_or_default();
+    let name = &part.name[..];
+    if let Some(scalar) = Scalar::from_name(name) {
+        return template_args.is_empty().then_some(Target::Scalar(scalar));
+    }
+    if name == "array" {
+        let element = type_argument(&template_args.first()?.expression, evaluate)?;
+        let count = match template_args.get(1) {
+            Some(count) => Some(usize::try_from(evaluate(&count.expression)?.as_i64()?).ok()?),
+            None => None,
+        };
+        return Some(Target::Array(Box::new(element), count));
+    }
+    let (size, suffix) = name.strip_prefix("vec")?.split_at_checked(1)?;
+    let size = match size {
+        "2" => 2,
+        "3" => 3,
+        "4" => 4,
+        _ => return None,
+    };
+    let scalar = match (suffix, template_args) {
+        ("", [scalar]) => match type_argument(&scalar.expression, evaluate)? {
+            Target::Scalar(scalar) => scalar,
+            _ => return None,
+        },
+        ("i", []) => Scalar::I32,
+        ("u", []) => Scalar::U32,
+        ("f", []) => Scalar::F32,
+        _ => return None,
+    };
+    Some(Target::Vector(size, scalar))
+}
@@ -0,0 +1,751 @@
+//! The memory layout of host-shareable types, i.e. the types of `var<uniform>` and
+//! `var<storage>` declarations, following the rules of the WGSL specification. Both address
+//! spaces share the same layout, the uniform address space only adds constraints on it, see
+//! [`UniformLayoutChecker`].
+//!
+//! Layouts are computed on flattened translation units: every struct is a global
+//! declaration, and template arguments and aliases are resolved.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use mew_parse::{
+    span::{Span, Spanned},
+    syntax::{
+        Declaration, DeclarationKind, Expression, GlobalDeclaration, PathPart, Struct,
+        StructMember, TemplateArg, TranslationUnit, TypeExpression,
+    },
+};
+
+use crate::{consteval::ConstEvaluator, CompilerPass, CompilerPassError, ErrorCollector};
+
+/// The layout of a type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TypeLayout {
+    pub size: u32,
+    pub align: u32,
+    /// The distance between consecutive elements, for arrays.
+    pub stride: Option<u32>,
+    /// Whether the type is a runtime-sized array, or a struct ending with one. Its `size`
+    /// is then the size without any element.
+    pub runtime_sized: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scalar {
+    I32,
+    U32,
+    F32,
+    F16,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "i32" => Some(Scalar::I32),
+            "u32" => Some(Scalar::U32),
+            "f32" => Some(Scalar::F32),
+            "f16" => Some(Scalar::F16),
+            _ => None,
+        }
+    }
+
+    /// The scalar of the suffix of predeclared aliases, e.g. `vec3f` or `mat4x4h`.
+    fn from_suffix(suffix: char) -> Option<Self> {
+        match suffix {
+            'i' => Some(Scalar::I32),
+            'u' => Some(Scalar::U32),
+            'f' => Some(Scalar::F32),
+            'h' => Some(Scalar::F16),
+            _ => None,
+        }
+    }
+
+    pub fn size(self) -> u32 {
+        match self {
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F16 => 2,
+        }
+    }
+}
+
+impl Display for Scalar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scalar::I32 => write!(f, "i32"),
+            Scalar::U32 => write!(f, "u32"),
+            Scalar::F32 => write!(f, "f32"),
+            Scalar::F16 => write!(f, "f16"),
+        }
+    }
+}
+
+/// A type that can be shared with the host.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HostType {
+    Scalar(Scalar),
+    Atomic(Scalar),
+    Vector {
+        size: u32,
+        scalar: Scalar,
+    },
+    Matrix {
+        columns: u32,
+        rows: u32,
+        scalar: Scalar,
+    },
+    /// `count` is `None` for runtime-sized arrays.
+    Array {
+        element: Box<HostType>,
+        count: Option<u32>,
+    },
+    /// A struct, by name in the compiled WGSL.
+    Struct(String),
+}
+
+impl Display for HostType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostType::Scalar(scalar) => write!(f, "{scalar}"),
+            HostType::Atomic(scalar) => write!(f, "atomic<{scalar}>"),
+            HostType::Vector { size, scalar } => write!(f, "vec{size}<{scalar}>"),
+            HostType::Matrix {
+                columns,
+                rows,
+                scalar,
+            } => write!(f, "mat{columns}x{rows}<{scalar}>"),
+            HostType::Array {
+                element,
+                count: Some(count),
+            } => write!(f, "array<{element}, {count}>"),
+            HostType::Array {
+                element,
+                count: None,
+            } => write!(f, "array<{element}>"),
+            HostType::Struct(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StructLayout {
+    /// The name of the struct in the compiled WGSL.
+    pub name: String,
+    pub layout: TypeLayout,
+    pub members: Vec<MemberLayout>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MemberLayout {
+    pub name: String,
+    /// The type, as written in the compiled WGSL.
+    pub ty: String,
+    pub host_type: HostType,
+    /// The offset from the start of the struct, in bytes.
+    pub offset: u32,
+    /// The size of the member, which is larger than the size of its type with `@size`.
+    pub size: u32,
+    /// The alignment of the member, which differs from the alignment of its type with
+    /// `@align`.
+    pub align: u32,
+    /// The layout of the type of the member.
+    pub layout: TypeLayout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LayoutError {
+    /// The type cannot be shared with the host, e.g. `bool` or a texture.
+    NotHostShareable(String),
+    /// The element count of an array is not a const-expression that evaluates to a
+    /// non-negative integer.
+    UnsupportedArrayLength(String),
+    /// The argument of the `@size` or `@align` attribute of a struct member is not a
+    /// const-expression that evaluates to a non-negative integer.
+    UnsupportedAttributeArgument { attribute: String, member: String },
+    /// The argument of the `@align` attribute of a struct member is not a positive power
+    /// of two.
+    InvalidAlign { member: String, align: u32 },
+    /// The argument of the `@size` attribute of a struct member is less than the size of
+    /// its type.
+    InvalidSize { member: String, size: u32, min: u32 },
+    /// The size of the type does not fit in a `u32`.
+    TooLarge(String),
+    /// A runtime-sized array that is not the last member of its struct.
+    RuntimeSizedArrayNotLast(String),
+    /// A struct that contains itself.
+    Recursive(String),
+    /// A runtime-sized array in the uniform address space.
+    UniformRuntimeSizedArray(String),
+    /// An array in the uniform address space with a stride that is not a multiple of 16.
+    UniformArrayStride { ty: String, stride: u32 },
+    /// A struct member in the uniform address space with an offset that is not a multiple
+    /// of the alignment its type requires there.
+    UniformMemberOffset {
+        member: String,
+        offset: u32,
+        align: u32,
+    },
+    /// A struct member in the uniform address space that follows a member of struct type
+    /// less than `roundUp(16, size)` bytes after it.
+    UniformStructPadding { member: String, required: u32 },
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::NotHostShareable(ty) => {
+                write!(f, "`{ty}` cannot be shared with the host")
+            }
+            LayoutError::UnsupportedArrayLength(ty) => {
+                write!(f, "the element count of `{ty}` is not a constant integer")
+            }
+            LayoutError::UnsupportedAttributeArgument { attribute, member } => write!(
+                f,
+                "the argument of `@{attribute}` on `{member}` is not a constant integer"
+            ),
+            LayoutError::InvalidAlign { member, align } => write!(
+                f,
+                "`@align({align})` on `{member}` is not a positive power of two"
+            ),
+            LayoutError::InvalidSize { member, size, min } => write!(
+                f,
+                "`@size({size})` on `{member}` is less than the {min} bytes of its type"
+            ),
+            LayoutError::TooLarge(ty) => {
+                write!(f, "`{ty}` is larger than {} bytes", u32::MAX)
+            }
+            LayoutError::RuntimeSizedArrayNotLast(member) => write!(
+                f,
+                "runtime-sized array `{member}` must be the last member of its struct"
+            ),
+            LayoutError::Recursive(ty) => write!(f, "struct `{ty}` contains itself"),
+            LayoutError::UniformRuntimeSizedArray(ty) => write!(
+                f,
+                "`{ty}` is runtime-sized, which is not allowed in the uniform address space"
+            ),
+            LayoutError::UniformArrayStride { ty, stride } => write!(
+                f,
+                "`{ty}` has a stride of {stride} bytes, but arrays in the uniform address space \
+                 need a stride that is a multiple of 16"
+            ),
+            LayoutError::UniformMemberOffset {
+                member,
+                offset,
+                align,
+            } => write!(
+                f,
+                "`{member}` is at offset {offset}, but its type must be aligned to {align} bytes \
+                 in the uniform address space"
+            ),
+            LayoutError::UniformStructPadding { member, required } => write!(
+                f,
+                "`{member}` is a struct, so the next member must be at least {required} bytes \
+                 after it in the uniform address space"
+            ),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Spanned<LayoutError>>;
+
+/// The layout of every host-shareable struct of `translation_unit`, in declaration order.
+/// Structs that cannot be shared with the host, e.g. because they contain a `bool`, are
+/// skipped.
+pub fn struct_layouts(translation_unit: &TranslationUnit) -> Vec<StructLayout> {
+    let mut layouts = Layouts::new(translation_unit);
+    translation_unit
+        .global_declarations
+        .iter()
+        .filter_map(|declaration| match declaration.as_ref() {
+            GlobalDeclaration::Struct(s) => layouts.struct_layout(&s.name, s.name.span()).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks that the type of every `var<uniform>` satisfies the constraints of the uniform
+/// address space. Runs on flattened translation units, and leaves them unchanged.
+pub struct UniformLayoutChecker;
+
+impl CompilerPass for UniformLayoutChecker {
+    fn apply_mut(
+        &mut self,
+        translation_unit: &mut TranslationUnit,
+    ) -> std::result::Result<(), CompilerPassError> {
+        let mut layouts = Layouts::new(translation_unit);
+        let mut errors = ErrorCollector::default();
+        for declaration in &translation_unit.global_declarations {
+            if let GlobalDeclaration::Declaration(declaration) = declaration.as_ref() {
+                layouts.check_uniform(declaration, &mut errors);
+            }
+        }
+        Ok(errors.finish()?)
+    }
+}
+
+/// Computes layouts of the types of a translation unit.
+pub struct Layouts<'a> {
+    structs: HashMap<&'a str, &'a Struct>,
+    evaluator: ConstEvaluator<'a>,
+    cache: HashMap<String, StructLayout>,
+    /// The structs whose layout is being computed, to detect recursive structs.
+    in_progress: Vec<String>,
+}
+
+impl<'a> Layouts<'a> {
+    pub fn new(translation_unit: &'a TranslationUnit) -> Self {
+        let mut structs = HashMap::new();
+        for declaration in &translation_unit.global_declarations {
+            if let GlobalDeclaration::Struct(s) = declaration.as_ref() {
+                structs.insert(s.name.as_str(), s);
+            }
+        }
+        Self {
+            structs,
+            evaluator: ConstEvaluator::new(translation_unit),
+            cache: HashMap::new(),
+            in_progress: vec![],
+        }
+    }
+
+    /// The layout of `ty`.
+    pub fn type_layout(&mut self, ty: &Spanned<TypeExpression>) -> Result<TypeLayout> {
+        self.path_layout(&ty.path, ty.span())
+    }
+
+    /// The layout of the struct called `name`, `span` is used to report errors.
+    pub fn struct_layout(&mut self, name: &str, span: Span) -> Result<StructLayout> {
+        if let Some(layout) = self.cache.get(name) {
+            return Ok(layout.clone());
+        }
+        let Some(s) = self.structs.get(name).copied() else {
+            return Err(layout_error(
+                LayoutError::NotHostShareable(name.to_string()),
+                span,
+            ));
+        };
+        if self.in_progress.iter().any(|other| other == name) {
+            return Err(layout_error(LayoutError::Recursive(name.to_string()), span));
+        }
+        self.in_progress.push(name.to_string());
+        let layout = self.compute_struct_layout(s);
+        self.in_progress.pop();
+        let layout = layout?;
+        self.cache.insert(name.to_string(), layout.clone());
+        Ok(layout)
+    }
+
+    fn compute_struct_layout(&mut self, s: &Struct) -> Result<StructLayout> {
+        let mut members = vec![];
+        let mut end = 0;
+        let mut align = 1;
+        let mut runtime_sized = false;
+        for (index, member) in s.members.iter().enumerate() {
+            let host_type = self.host_type(&member.typ.path, member.typ.span())?;
+            let layout = self.host_type_layout(&host_type, member.typ.span())?;
+            if layout.runtime_sized && index + 1 != s.members.len() {
+                return Err(layout_error(
+                    LayoutError::RuntimeSizedArrayNotLast(member.name.value.clone()),
+                    member.name.span(),
+                ));
+            }
+            let member_align = self
+                .attribute_u32(member, "align", &layout)?
+                .unwrap_or(layout.align);
+            let member_size = self
+                .attribute_u32(member, "size", &layout)?
+                .unwrap_or(layout.size);
+            let too_large = || {
+                layout_error(
+                    LayoutError::TooLarge(s.name.value.clone()),
+                    member.name.span(),
+                )
+            };
+            let offset = round_up(member_align, end).ok_or_else(too_large)?;
+            members.push(MemberLayout {
+                name: member.name.value.clone(),
+                ty: format!("{}", member.typ.value),
+                host_type,
+                offset,
+                size: member_size,
+                align: member_align,
+                layout,
+            });
+            end = offset.checked_add(member_size).ok_or_else(too_large)?;
+            align = align.max(member_align);
+            runtime_sized = layout.runtime_sized;
+        }
+        let size = round_up(align, end).ok_or_else(|| {
+            layout_error(LayoutError::TooLarge(s.name.value.clone()), s.name.span())
+        })?;
+        Ok(StructLayout {
+            name: s.name.value.clone(),
+            layout: TypeLayout {
+                size,
+                align,
+                stride: None,
+                runtime_sized,
+            },
+            members,
+        })
+    }
+
+    fn path_layout(&mut self, path: &[PathPart], span: Span) -> Result<TypeLayout> {
+        let ty = self.host_type(path, span.clone())?;
+        self.host_type_layout(&ty, span)
+    }
+
+    /// Classifies the type at `path`, `span` is used to report errors.
+    pub fn host_type(&self, path: &[PathPart], span: Span) -> Result<HostType> {
+        let not_host_shareable = || {
+            let ty = TypeExpression {
+                path: Spanned::new(path.to_vec(), span.clone()),
+            };
+            layout_error(LayoutError::NotHostShareable(format!("{ty}")), span.clone())
+        };
+        let [part] = path else {
+            return Err(not_host_shareable());
+        };
+        let args = part.template_args.as_deref().unwrap_or_default();
+        let scalar_arg = || {
+            args.first()
+                .and_then(type_path)
+                .and_then(|path| match path {
+                    [scalar] => Scalar::from_name(&scalar.name),
+                    _ => None,
+                })
+                .ok_or_else(not_host_shareable)
+        };
+        let name = part.name.as_str();
+        if let Some(scalar) = Scalar::from_name(name) {
+            return Ok(HostType::Scalar(scalar));
+        }
+        if let Some(shape) = shape(name) {
+            return Ok(shape.host_type(scalar_arg()?));
+        }
+        // predeclared aliases, e.g. `vec3f` or `mat4x4h`
+        if let Some((shape, scalar)) = name.char_indices().last().and_then(|(index, suffix)| {
+            Some((shape(&name[..index])?, Scalar::from_suffix(suffix)?))
+        }) {
+            return Ok(shape.host_type(scalar));
+        }
+        match name {
+            "atomic" => Ok(HostType::Atomic(scalar_arg()?)),
+            "array" => {
+                let Some((element, element_path)) = args
+                    .first()
+                    .and_then(|element| Some((element, type_path(element)?)))
+                else {
+                    return Err(not_host_shareable());
+                };
+                let element = self.host_type(element_path, element.expression.span())?;
+                let count = match args.get(1) {
+                    Some(count) => match self
+                        .evaluator
+                        .evaluate(&count.expression)
+                        .and_then(|count| count.as_u32())
+                    {
+                        Some(count) => Some(count),
+                        None => {
+                            let ty = TypeExpression {
+                                path: Spanned::new(path.to_vec(), span.clone()),
+                            };
+                            return Err(layout_error(
+                                LayoutError::UnsupportedArrayLength(format!("{ty}")),
+                                span,
+                            ));
+                        }
+                    },
+                    None => None,
+                };
+                Ok(HostType::Array {
+                    element: Box::new(element),
+                    count,
+                })
+            }
+            _ if self.structs.contains_key(name) => Ok(HostType::Struct(name.to_string())),
+            _ => Err(not_host_shareable()),
+        }
+    }
+
+    /// The layout of `ty`, `span` is used to report errors.
+    pub fn host_type_layout(&mut self, ty: &HostType, span: Span) -> Result<TypeLayout> {
+        match ty {
+            HostType::Scalar(scalar) | HostType::Atomic(scalar) => {
+                Ok(plain(scalar.size(), scalar.size()))
+            }
+            HostType::Vector { size, scalar } => {
+                let align = if *size == 2 { 2 } else { 4 } * scalar.size();
+                Ok(plain(size * scalar.size(), align))
+            }
+            HostType::Matrix {
+                columns,
+                rows,
+                scalar,
+            } => {
+                let column = HostType::Vector {
+                    size: *rows,
+                    scalar: *scalar,
+                };
+                let column = self.host_type_layout(&column, span)?;
+                // at most 4 columns of 16 bytes
+                let stride = column.size.next_multiple_of(column.align);
+                Ok(plain(columns * stride, column.align))
+            }
+            HostType::Array { element, count } => {
+                let element = self.host_type_layout(element, span.clone())?;
+                if element.runtime_sized {
+                    return Err(layout_error(
+                        LayoutError::NotHostShareable(format!("{ty}")),
+                        span,
+                    ));
+                }
+                let too_large =
+                    || layout_error(LayoutError::TooLarge(format!("{ty}")), span.clone());
+                let stride = round_up(element.align, element.size).ok_or_else(too_large)?;
+                let size = count.unwrap_or(0).checked_mul(stride);
+                Ok(TypeLayout {
+                    size: size.ok_or_else(too_large)?,
+                    align: element.align,
+                    stride: Some(stride),
+                    runtime_sized: count.is_none(),
+                })
+            }
+            HostType::Struct(name) => Ok(self.struct_layout(name, span)?.layout),
+        }
+    }
+
+    /// Checks the type of `declaration`, if it is a `var<uniform>`, against the constraints
+    /// of the uniform address space.
+    pub fn check_uniform(&mut self, declaration: &Declaration, errors: &mut ErrorCollector) {
+        let is_uniform = declaration.kind.value == DeclarationKind::Var
+            && declaration
+                .template_args
+                .iter()
+                .flatten()
+                .next()
+                .and_then(type_path)
+                .is_some_and(|path| matches!(path, [part] if part.name.value == "uniform"));
+        let Some(ty) = declaration.typ.as_ref().filter(|_| is_uniform) else {
+            return;
+        };
+        let mut checked = HashSet::new();
+        self.check_uniform_type(&ty.path, ty.span(), &mut checked, errors);
+    }
+
+    fn check_uniform_type(
+        &mut self,
+        path: &[PathPart],
+        span: Span,
+        checked: &mut HashSet<String>,
+        errors: &mut ErrorCollector,
+    ) {
+        let Some(layout) = check_layout(errors, self.path_layout(path, span.clone())) else {
+            return;
+        };
+        let [part] = path else {
+            return;
+        };
+        let ty = || {
+            let ty = TypeExpression {
+                path: Spanned::new(path.to_vec(), span.clone()),
+            };
+            format!("{ty}")
+        };
+        if layout.runtime_sized {
+            errors.push(CompilerPassError::InvalidLayout(
+                LayoutError::UniformRuntimeSizedArray(ty()),
+                span.clone(),
+            ));
+            return;
+        }
+        if part.name.value == "array" {
+            if let Some(stride) = layout.stride.filter(|stride| stride % 16 != 0) {
+                errors.push(CompilerPassError::InvalidLayout(
+                    LayoutError::UniformArrayStride { ty: ty(), stride },
+                    span.clone(),
+                ));
+            }
+            let element = part.template_args.iter().flatten().next();
+            if let Some((element, element_path)) =
+                element.and_then(|element| Some((element, type_path(element)?)))
+            {
+                self.check_uniform_type(element_path, element.expression.span(), checked, errors);
+            }
+            return;
+        }
+        let Some(s) = self.structs.get(part.name.as_str()).copied() else {
+            return;
+        };
+        if !checked.insert(part.name.value.clone()) {
+            return;
+        }
+        let Some(layout) = check_layout(errors, self.struct_layout(&part.name, span)) else {
+            return;
+        };
+        for (index, (member, member_layout)) in s.members.iter().zip(&layout.members).enumerate() {
+            let is_struct = |path: &[PathPart]| matches!(path, [part] if self.structs.contains_key(part.name.as_str()));
+            let is_array =
+                matches!(member.typ.path.as_slice(), [part] if part.name.value == "array");
+            let required_align = if is_array || is_struct(&member.typ.path) {
+                member_layout.layout.align.max(16)
+            } else {
+                member_layout.layout.align
+            };
+            if member_layout.offset % required_align != 0 {
+                errors.push(CompilerPassError::InvalidLayout(
+                    LayoutError::UniformMemberOffset {
+                        member: member.name.value.clone(),
+                        offset: member_layout.offset,
+                        align: required_align,
+                    },
+                    member.name.span(),
+                ));
+            }
+            if let Some(next) = layout.members.get(index + 1) {
+                let required = round_up(16, member_layout.layout.size).unwrap_or(u32::MAX);
+                if is_struct(&member.typ.path) && next.offset - member_layout.offset < required {
+                    errors.push(CompilerPassError::InvalidLayout(
+                        LayoutError::UniformStructPadding {
+                            member: member.name.value.clone(),
+                            required,
+                        },
+                        member.name.span(),
+                    ));
+                }
+            }
+            self.check_uniform_type(&member.typ.path, member.typ.span(), checked, errors);
+        }
+    }
+
+    /// The value of the attribute called `name` of `member`, `None` if it has none. `@align`
+    /// must be a positive power of two, and `@size` at least the size of `layout`, the
+    /// layout of the type of `member`.
+    fn attribute_u32(
+        &self,
+        member: &StructMember,
+        name: &str,
+        layout: &TypeLayout,
+    ) -> Result<Option<u32>> {
+        let Some(attribute) = member
+            .attributes
+            .iter()
+            .find(|attr| attr.name.value == name)
+        else {
+            return Ok(None);
+        };
+        let member_name = || member.name.value.clone();
+        let value = attribute
+            .arguments
+            .iter()
+            .flatten()
+            .next()
+            .and_then(|argument| self.evaluator.evaluate(argument)?.as_u32())
+            .ok_or_else(|| {
+                layout_error(
+                    LayoutError::UnsupportedAttributeArgument {
+                        attribute: name.to_string(),
+                        member: member_name(),
+                    },
+                    attribute.span(),
+                )
+            })?;
+        let error = match name {
+            "align" if !value.is_power_of_two() => LayoutError::InvalidAlign {
+                member: member_name(),
+                align: value,
+            },
+            "size" if value < layout.size => LayoutError::InvalidSize {
+                member: member_name(),
+                size: value,
+                min: layout.size,
+            },
+            _ => return Ok(Some(value)),
+        };
+        Err(layout_error(error, attribute.span()))
+    }
+}
+
+/// The number of rows and columns of a vector or matrix type.
+#[derive(Clone, Copy)]
+enum Shape {
+    Vector(u32),
+    Matrix(u32, u32),
+}
+
+impl Shape {
+    fn host_type(self, scalar: Scalar) -> HostType {
+        match self {
+            Shape::Vector(size) => HostType::Vector { size, scalar },
+            Shape::Matrix(columns, rows) => HostType::Matrix {
+                columns,
+                rows,
+                scalar,
+            },
+        }
+    }
+}
+
+/// `vec2` to `vec4`, and `mat2x2` to `mat4x4`.
+fn shape(name: &str) -> Option<Shape> {
+    let dimension = |d: &str| match d {
+        "2" => Some(2),
+        "3" => Some(3),
+        "4" => Some(4),
+        _ => None,
+    };
+    if let Some(rows) = name.strip_prefix("vec") {
+        return Some(Shape::Vector(dimension(rows)?));
+    }
+    let (columns, rows) = name.strip_prefix("mat")?.split_once('x')?;
+    Some(Shape::Matrix(dimension(columns)?, dimension(rows)?))
+}
+
+/// The path of a template argument that names a type.
+fn type_path(arg: &Spanned<TemplateArg>) -> Option<&[PathPart]> {
+    match &arg.expression.value {
+        Expression::Type(ty) => Some(&ty.path),
+        Expression::Identifier(identifier) => Some(&identifier.path),
+        _ => None,
+    }
+}
+
+fn plain(size: u32, align: u32) -> TypeLayout {
+    TypeLayout {
+        size,
+        align,
+        stride: None,
+        runtime_sized: false,
+    }
+}
+
+/// `value` rounded up to a multiple of `align`, `None` if it does not fit in a `u32`.
+fn round_up(align: u32, value: u32) -> Option<u32> {
+    value.checked_next_multiple_of(align)
+}
+
+fn layout_error(error: LayoutError, span: Span) -> Spanned<LayoutError> {
+    Spanned::new(error, span)
+}
+
+/// Records the error of `result`, if any, and returns its value otherwise. Layouts that
+/// depend on a constant the evaluator cannot compute are valid as far as the checker knows,
+/// so their errors are dropped and the check is skipped.
+fn check_layout<T>(errors: &mut ErrorCollector, result: Result<T>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            if !matches!(
+                error.value,
+                LayoutError::UnsupportedArrayLength(_)
+                    | LayoutError::UnsupportedAttributeArgument { .. }
+            ) {
+                let span = error.span();
+                errors.push(CompilerPassError::InvalidLayout(error.value, span));
+            }
+            None
+        }
+    }
+}
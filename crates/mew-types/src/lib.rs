@@ -7,7 +7,18 @@ use mew_parse::{
 };
 
 pub mod builtins;
+pub mod consteval;
+pub mod layout;
 pub mod mangling;
+pub mod typing;
+
+/// The attribute of the functions and declarations to compile even if no entry point uses
+/// them, e.g. `@keep override scale: f32;`.
+pub const KEEP_ATTRIBUTE: &str = "keep";
+
+/// The attribute of the module members that only their module, and the modules inside it,
+/// can refer to, e.g. `@private fn helper() {}`.
+pub const PRIVATE_ATTRIBUTE: &str = "private";
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CompilerPassError {
@@ -17,6 +28,73 @@ pub enum CompilerPassError {
     InternalError(InternalCompilerError),
     MalformedTemplateArgument(Span),
     ParseError(String, Span),
+    /// A path or `use` item refers to a private member from outside its module: the path of
+    /// the member, the span of the reference and the span of the member's name.
+    PrivateMember(Vec<PathPart>, Span, Span),
+    /// A type has no memory layout, or breaks the layout rules of its address space.
+    InvalidLayout(layout::LayoutError, Span),
+    /// An expression or declaration has the wrong type.
+    TypeError(typing::TypeError, Span),
+    /// Several errors, reported by passes that keep going after the first one.
+    Many(Vec<CompilerPassError>),
+}
+
+impl CompilerPassError {
+    /// Flattens [`CompilerPassError::Many`] into the errors it contains.
+    pub fn into_errors(self) -> Vec<CompilerPassError> {
+        match self {
+            CompilerPassError::Many(errors) => errors
+                .into_iter()
+                .flat_map(CompilerPassError::into_errors)
+                .collect(),
+            other => vec![other],
+        }
+    }
+}
+
+/// Collects the errors of a pass so that it can keep going after the first one.
+#[derive(Debug, Default)]
+pub struct ErrorCollector {
+    errors: Vec<CompilerPassError>,
+}
+
+impl ErrorCollector {
+    /// Records the error of `result`, if any, and returns its value otherwise.
+    pub fn check<T>(&mut self, result: Result<T, CompilerPassError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                for err in err.into_errors() {
+                    if !self.errors.contains(&err) {
+                        self.errors.push(err);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Records `err`.
+    pub fn push(&mut self, err: CompilerPassError) {
+        self.check::<()>(Err(err));
+    }
+
+    /// Returns the collected errors: the error itself if there is only one,
+    /// [`CompilerPassError::Many`] if there are several. The error is boxed, `?` unboxes it
+    /// in passes.
+    pub fn finish(mut self) -> Result<(), Box<CompilerPassError>> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(Box::new(self.errors.remove(0))),
+            _ => Err(Box::new(CompilerPassError::Many(self.errors))),
+        }
+    }
+}
+
+impl From<Box<CompilerPassError>> for CompilerPassError {
+    fn from(err: Box<CompilerPassError>) -> Self {
+        *err
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -0,0 +1,299 @@
+//! The types of WGSL expressions, as inferred by the type checker of `mew-typecheck`, and
+//! the type errors it reports.
+//!
+//! Abstract numeric types follow the automatic conversions of the WGSL specification: an
+//! `AbstractInt` converts to any numeric scalar, an `AbstractFloat` to any floating point
+//! scalar, and vectors, matrices and arrays convert along with their elements.
+
+use std::fmt::Display;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Type {
+    Bool,
+    AbstractInt,
+    AbstractFloat,
+    I32,
+    U32,
+    F32,
+    F16,
+    Vector(u8, Box<Type>),
+    Matrix {
+        columns: u8,
+        rows: u8,
+        scalar: Box<Type>,
+    },
+    /// `None` for runtime-sized arrays, and for element counts that are not known.
+    Array(Box<Type>, Option<u32>),
+    Atomic(Box<Type>),
+    /// A pointer to a value of this type. Address spaces and access modes are not checked.
+    Ptr(Box<Type>),
+    /// A struct, by path.
+    Struct(Vec<String>),
+    /// A type that could not be inferred, e.g. a texture or the result of a builtin that is
+    /// not modelled. It is compatible with every type, so that it never causes an error.
+    Unknown,
+}
+
+impl Type {
+    /// The scalar type called `name`, e.g. `f32`.
+    pub fn scalar(name: &str) -> Option<Type> {
+        match name {
+            "bool" => Some(Type::Bool),
+            "AbstractInt" => Some(Type::AbstractInt),
+            "AbstractFloat" => Some(Type::AbstractFloat),
+            "i32" => Some(Type::I32),
+            "u32" => Some(Type::U32),
+            "f32" => Some(Type::F32),
+            "f16" => Some(Type::F16),
+            _ => None,
+        }
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        matches!(
+            self,
+            Type::Bool
+                | Type::AbstractInt
+                | Type::AbstractFloat
+                | Type::I32
+                | Type::U32
+                | Type::F32
+                | Type::F16
+        )
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_scalar() && *self != Type::Bool
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::AbstractInt | Type::I32 | Type::U32)
+    }
+
+    /// Whether the type is fully inferred, i.e. contains no [`Type::Unknown`].
+    pub fn is_known(&self) -> bool {
+        match self {
+            Type::Vector(_, inner)
+            | Type::Matrix { scalar: inner, .. }
+            | Type::Array(inner, _)
+            | Type::Atomic(inner)
+            | Type::Ptr(inner) => inner.is_known(),
+            Type::Unknown => false,
+            _ => true,
+        }
+    }
+
+    /// The scalar of a vector or matrix, the type itself otherwise.
+    pub fn element(&self) -> &Type {
+        match self {
+            Type::Vector(_, scalar) | Type::Matrix { scalar, .. } => scalar,
+            other => other,
+        }
+    }
+
+    /// Whether a value of this type can be used where a value of type `to` is expected,
+    /// possibly after an automatic conversion of abstract types.
+    pub fn converts_to(&self, to: &Type) -> bool {
+        match (self, to) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::AbstractInt, to) => to.is_numeric(),
+            (Type::AbstractFloat, Type::F32 | Type::F16) => true,
+            (Type::Vector(size, from), Type::Vector(to_size, to)) => {
+                size == to_size && from.converts_to(to)
+            }
+            (
+                Type::Matrix {
+                    columns,
+                    rows,
+                    scalar: from,
+                },
+                Type::Matrix {
+                    columns: to_columns,
+                    rows: to_rows,
+                    scalar: to,
+                },
+            ) => columns == to_columns && rows == to_rows && from.converts_to(to),
+            (Type::Array(from, count), Type::Array(to, to_count)) => {
+                let counts_match = match (count, to_count) {
+                    (Some(count), Some(to_count)) => count == to_count,
+                    _ => true,
+                };
+                counts_match && from.converts_to(to)
+            }
+            // no conversion happens behind pointers and atomics
+            (Type::Atomic(from), Type::Atomic(to)) | (Type::Ptr(from), Type::Ptr(to)) => {
+                from.converts_to(to) && to.converts_to(from)
+            }
+            (from, to) => from == to,
+        }
+    }
+
+    /// The type that values of both types convert to, e.g. `f32` for `AbstractInt` and
+    /// `f32`.
+    pub fn join(&self, other: &Type) -> Option<Type> {
+        match (self, other) {
+            (Type::Unknown, _) | (_, Type::Unknown) => Some(Type::Unknown),
+            (Type::Vector(size, left), Type::Vector(other_size, right)) if size == other_size => {
+                Some(Type::Vector(*size, Box::new(left.join(right)?)))
+            }
+            (
+                Type::Matrix {
+                    columns,
+                    rows,
+                    scalar: left,
+                },
+                Type::Matrix {
+                    columns: other_columns,
+                    rows: other_rows,
+                    scalar: right,
+                },
+            ) if columns == other_columns && rows == other_rows => Some(Type::Matrix {
+                columns: *columns,
+                rows: *rows,
+                scalar: Box::new(left.join(right)?),
+            }),
+            (Type::Array(left, count), Type::Array(right, other_count)) => {
+                let count = match (count, other_count) {
+                    (Some(count), Some(other_count)) if count != other_count => return None,
+                    (Some(count), _) | (_, Some(count)) => Some(*count),
+                    (None, None) => None,
+                };
+                Some(Type::Array(Box::new(left.join(right)?), count))
+            }
+            (left, right) if left.converts_to(right) => Some(right.clone()),
+            (left, right) if right.converts_to(left) => Some(left.clone()),
+            _ => None,
+        }
+    }
+
+    /// The type of a `let` or `var` initialized with a value of this type: abstract types
+    /// become `i32` and `f32`.
+    pub fn concretize(&self) -> Type {
+        match self {
+            Type::AbstractInt => Type::I32,
+            Type::AbstractFloat => Type::F32,
+            Type::Vector(size, scalar) => Type::Vector(*size, Box::new(scalar.concretize())),
+            Type::Matrix {
+                columns,
+                rows,
+                scalar,
+            } => Type::Matrix {
+                columns: *columns,
+                rows: *rows,
+                scalar: Box::new(scalar.concretize()),
+            },
+            Type::Array(element, count) => Type::Array(Box::new(element.concretize()), *count),
+            other => other.clone(),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::AbstractInt => write!(f, "AbstractInt"),
+            Type::AbstractFloat => write!(f, "AbstractFloat"),
+            Type::I32 => write!(f, "i32"),
+            Type::U32 => write!(f, "u32"),
+            Type::F32 => write!(f, "f32"),
+            Type::F16 => write!(f, "f16"),
+            Type::Vector(size, scalar) => write!(f, "vec{size}<{scalar}>"),
+            Type::Matrix {
+                columns,
+                rows,
+                scalar,
+            } => write!(f, "mat{columns}x{rows}<{scalar}>"),
+            Type::Array(element, Some(count)) => write!(f, "array<{element}, {count}>"),
+            Type::Array(element, None) => write!(f, "array<{element}>"),
+            Type::Atomic(scalar) => write!(f, "atomic<{scalar}>"),
+            Type::Ptr(pointee) => write!(f, "ptr<{pointee}>"),
+            Type::Struct(path) => write!(f, "{}", path.join("::")),
+            Type::Unknown => write!(f, "_"),
+        }
+    }
+}
+
+/// An error reported by the type checker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeError {
+    /// A value of type `found` is used where a value of type `expected` is required.
+    Mismatch {
+        expected: Type,
+        found: Type,
+    },
+    InvalidOperands {
+        operator: String,
+        left: Type,
+        right: Type,
+    },
+    /// No overload of a builtin function accepts these arguments.
+    NoMatchingOverload {
+        function: String,
+        arguments: Vec<Type>,
+    },
+    ArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    /// The arguments of a value constructor do not add up to the constructed type.
+    InvalidConstructor {
+        ty: Type,
+        arguments: Vec<Type>,
+    },
+    NoMember {
+        ty: Type,
+        member: String,
+    },
+    NotIndexable(Type),
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |types: &[Type]| {
+            types
+                .iter()
+                .map(|ty| format!("`{ty}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            TypeError::Mismatch { expected, found } => {
+                write!(
+                    f,
+                    "mismatched types: expected `{expected}`, found `{found}`"
+                )
+            }
+            TypeError::InvalidOperands {
+                operator,
+                left,
+                right,
+            } => write!(f, "cannot apply `{operator}` to `{left}` and `{right}`"),
+            TypeError::NoMatchingOverload {
+                function,
+                arguments,
+            } => write!(
+                f,
+                "no overload of `{function}` takes arguments ({})",
+                list(arguments)
+            ),
+            TypeError::ArgumentCount {
+                function,
+                expected,
+                found,
+            } => {
+                let plural = if *expected == 1 { "" } else { "s" };
+                write!(
+                    f,
+                    "`{function}` takes {expected} argument{plural}, {found} given"
+                )
+            }
+            TypeError::InvalidConstructor { ty, arguments } => {
+                write!(f, "cannot construct `{ty}` from ({})", list(arguments))
+            }
+            TypeError::NoMember { ty, member } => write!(f, "no member `{member}` on `{ty}`"),
+            TypeError::NotIndexable(ty) => write!(f, "cannot index into a value of type `{ty}`"),
+        }
+    }
+}
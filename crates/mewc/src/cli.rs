@@ -0,0 +1,316 @@
+//! The `mewc` command line. [`run`] executes a parsed [`Cli`] and returns the exit code:
+//! * `0` ([`EXIT_SUCCESS`]): the command succeeded,
+//! * `1` ([`EXIT_COMPILE_ERROR`]): the inputs have errors, reported on stderr,
+//! * `2` ([`EXIT_USAGE_ERROR`]): a file could not be read or written, or the arguments are
+//!   invalid.
+
+use std::{
+    fs,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mew_api::{FileSystemLoader, MewApi, MewError, ModuleDescriptor, Source};
+
+pub const EXIT_SUCCESS: u8 = 0;
+pub const EXIT_COMPILE_ERROR: u8 = 1;
+pub const EXIT_USAGE_ERROR: u8 = 2;
+
+#[derive(Parser, Debug)]
+#[command(name = "mewc", version, about = "Compiles mew shaders to WGSL")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+    /// How errors are reported on stderr.
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human, global = true)]
+    pub message_format: MessageFormat,
+    /// Whether errors are colored. `auto` colors them if stderr is a terminal.
+    #[arg(long, value_enum, default_value_t = Color::Auto, global = true)]
+    pub color: Color,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compiles entry points and everything they use to WGSL.
+    Compile {
+        #[command(flatten)]
+        input: Input,
+        /// The module member to compile, e.g. `My_Lib<A, B>::main`. Can be repeated to
+        /// compile several entry points into one module.
+        #[arg(short, long, required = true)]
+        entry: Vec<String>,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Checks that the inputs and the modules they use parse, and that the entry points,
+    /// if any, compile.
+    Check {
+        #[command(flatten)]
+        input: Input,
+        /// A module member to compile, can be repeated.
+        #[arg(short, long)]
+        entry: Vec<String>,
+    },
+    /// Prints the inputs and the modules they use as a single mew file.
+    Bundle {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Prints the syntax tree of the inputs and the modules they use.
+    DumpAst {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct Input {
+    /// Root files, or directories whose `.mew` and `.wgsl` files are roots. Each root is
+    /// a module named after its file, with `-` replaced by `_`.
+    #[arg(required = true)]
+    pub inputs: Vec<PathBuf>,
+    /// The directory in which modules referenced by `use` and `extend` are looked up.
+    /// Defaults to the first input directory, or the directory of the first input file.
+    #[arg(long)]
+    pub root: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct Output {
+    /// Writes the result to this file instead of stdout.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    /// Errors with the offending source highlighted.
+    Human,
+    /// One `file:line:column: error: message` line per error.
+    Short,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Runs `cli`, writing results to `stdout` (unless written to a file) and errors to
+/// `stderr`. Returns the exit code.
+pub fn run(cli: &Cli, stdout: &mut impl Write, stderr: &mut impl Write) -> u8 {
+    let mut reporter = Reporter {
+        format: cli.message_format,
+        color: match cli.color {
+            Color::Auto => std::io::stderr().is_terminal(),
+            Color::Always => true,
+            Color::Never => false,
+        },
+        stderr,
+        roots: vec![],
+        errors: 0,
+    };
+    let result = match &cli.command {
+        Command::Compile {
+            input,
+            entry,
+            output,
+        } => reporter.load(input).and_then(|api| {
+            let paths: Vec<_> = entry.iter().cloned().map(mew_api::Path::Text).collect();
+            let wgsl = api
+                .compile_many(&paths)
+                .map_err(|err| reporter.report(&api, err))?;
+            reporter.write(output, &wgsl, stdout)
+        }),
+        Command::Check { input, entry } => reporter.load(input).and_then(|api| {
+            let mut code = EXIT_SUCCESS;
+            for entry in entry {
+                if let Err(err) = api.compile(&mew_api::Path::Text(entry.clone())) {
+                    code = reporter.report(&api, err);
+                }
+            }
+            match code {
+                EXIT_SUCCESS => Ok(()),
+                code => Err(code),
+            }
+        }),
+        Command::Bundle { input, output } => reporter
+            .load(input)
+            .and_then(|api| reporter.write(output, &format!("{}", api.translation_unit), stdout)),
+        Command::DumpAst { input, output } => reporter.load(input).and_then(|api| {
+            reporter.write(output, &format!("{:#?}\n", api.translation_unit), stdout)
+        }),
+    };
+    if reporter.errors > 0 && reporter.format == MessageFormat::Human {
+        let plural = if reporter.errors == 1 { "" } else { "s" };
+        let _ = writeln!(
+            reporter.stderr,
+            "error: aborting due to {} previous error{plural}",
+            reporter.errors
+        );
+    }
+    match result {
+        Ok(()) => EXIT_SUCCESS,
+        Err(code) => code,
+    }
+}
+
+struct Reporter<'a, W: Write> {
+    format: MessageFormat,
+    color: bool,
+    stderr: &'a mut W,
+    /// The module name and path of every root, to report errors with the path.
+    roots: Vec<(String, PathBuf)>,
+    /// The number of errors reported so far.
+    errors: usize,
+}
+
+impl<W: Write> Reporter<'_, W> {
+    /// Adds every root file of `input` as a module, then loads the modules they use.
+    /// Reports the errors of every root before failing.
+    fn load(&mut self, input: &Input) -> Result<MewApi, u8> {
+        let mut files = vec![];
+        for path in &input.inputs {
+            if path.is_dir() {
+                let entries = fs::read_dir(path).map_err(|err| self.io_error(path, err))?;
+                let mut dir_files = vec![];
+                for entry in entries {
+                    let entry = entry.map_err(|err| self.io_error(path, err))?.path();
+                    let is_source = entry
+                        .extension()
+                        .is_some_and(|ext| ext == "mew" || ext == "wgsl");
+                    if entry.is_file() && is_source {
+                        dir_files.push(entry);
+                    }
+                }
+                dir_files.sort();
+                files.extend(dir_files);
+            } else {
+                files.push(path.clone());
+            }
+        }
+
+        let mut api = MewApi::default();
+        let mut code = EXIT_SUCCESS;
+        for path in files {
+            let source = fs::read_to_string(&path).map_err(|err| self.io_error(&path, err))?;
+            let module_name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().replace('-', "_"))
+                .unwrap_or_default();
+            self.roots.push((module_name.clone(), path));
+            let module = ModuleDescriptor {
+                module_name: &module_name,
+                source: Source::Text(&source),
+            };
+            if let Err(err) = api.add_module(module) {
+                code = self.report(&api, err);
+            }
+        }
+        if code != EXIT_SUCCESS {
+            return Err(code);
+        }
+
+        // the loader is set after all roots are added, so that roots using each other are
+        // not loaded a second time.
+        let root = input.root.clone().unwrap_or_else(|| {
+            let first_dir = input.inputs.iter().find(|path| path.is_dir());
+            let first_parent = input.inputs.first().and_then(|path| path.parent());
+            match (first_dir, first_parent) {
+                (Some(dir), _) => dir.clone(),
+                (None, Some(parent)) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            }
+        });
+        api.loader = Some(Box::new(FileSystemLoader::new(root)));
+        api.load_missing_modules()
+            .map_err(|err| self.report(&api, err))?;
+        Ok(api)
+    }
+
+    fn write(
+        &mut self,
+        output: &Output,
+        contents: &str,
+        stdout: &mut impl Write,
+    ) -> Result<(), u8> {
+        match &output.output {
+            Some(path) => fs::write(path, contents).map_err(|err| self.io_error(path, err)),
+            None => stdout
+                .write_all(contents.as_bytes())
+                .map_err(|err| self.io_error(Path::new("<stdout>"), err)),
+        }
+    }
+
+    /// Prints every error contained in `err` and returns [`EXIT_COMPILE_ERROR`].
+    fn report(&mut self, api: &MewApi, err: MewError) -> u8 {
+        let errors = err.into_errors();
+        for err in &errors {
+            let message = match self.format {
+                MessageFormat::Human => format!("{}\n", api.format_error(err)),
+                MessageFormat::Short => self.short_message(api, err),
+            };
+            let message = match self.color {
+                true => message,
+                false => strip_styles(&message),
+            };
+            let _ = writeln!(self.stderr, "{message}");
+        }
+        self.errors += errors.len();
+        EXIT_COMPILE_ERROR
+    }
+
+    /// `file:line:column: error: message`, or `error: message` if the error has no span.
+    /// Parse errors are reported with their title only.
+    fn short_message(&self, api: &MewApi, err: &MewError) -> String {
+        let message = strip_styles(&err.to_string());
+        let message = message.lines().next().unwrap_or_default();
+        let message = message.strip_prefix("error: ").unwrap_or(message);
+        let location = err
+            .span
+            .as_ref()
+            .and_then(|span| api.source_map.location(span.start));
+        match location {
+            Some(location) => {
+                let name = &api.source_map.file(location.file).name;
+                let file = self
+                    .roots
+                    .iter()
+                    .find(|(module_name, _)| module_name == name)
+                    .map(|(_, path)| path.display().to_string())
+                    .unwrap_or_else(|| name.clone());
+                format!(
+                    "{file}:{}:{}: error: {message}",
+                    location.line, location.column
+                )
+            }
+            None => format!("error: {message}"),
+        }
+    }
+
+    fn io_error(&mut self, path: &Path, err: std::io::Error) -> u8 {
+        let _ = writeln!(self.stderr, "error: {}: {err}", path.display());
+        EXIT_USAGE_ERROR
+    }
+}
+
+/// Removes the ANSI escape sequences of errors rendered with styles.
+fn strip_styles(message: &str) -> String {
+    let mut plain = String::with_capacity(message.len());
+    let mut chars = message.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // `ESC [ parameters final-byte`
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
@@ -0,0 +1,7 @@
+use clap::Parser;
+
+fn main() -> std::process::ExitCode {
+    let cli = mewc::Cli::parse();
+    let code = mewc::run(&cli, &mut std::io::stdout(), &mut std::io::stderr());
+    std::process::ExitCode::from(code)
+}